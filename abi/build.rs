@@ -15,6 +15,7 @@ fn main() {
         "page",
         "page_size",
         "desc",
+        "timezone",
       ],
     )
     .with_bulider_into(
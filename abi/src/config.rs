@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::BTreeMap, fs, num::NonZeroU64};
 
 use serde::{Deserialize, Serialize};
 
@@ -25,10 +25,112 @@ pub struct ServerConfig {
   pub port: u16,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HoldConfig {
+  /// seconds a pending reservation without hold_until is held, 0 to hold forever
+  #[serde(default)]
+  pub default_ttl: u64,
+  /// seconds between two sweeps of expired pending reservations
+  #[serde(default = "default_sweep_interval")]
+  pub sweep_interval: u64,
+  /// max pending reservations expired in one statement
+  #[serde(default = "default_sweep_batch_size")]
+  pub batch_size: i64,
+}
+
+fn default_sweep_interval() -> u64 {
+  60
+}
+
+fn default_sweep_batch_size() -> i64 {
+  100
+}
+
+impl HoldConfig {
+  /// a sweep needs a positive interval and batch size to make progress
+  pub fn validate(&self) -> Result<(), Error> {
+    self.get_sweep_interval()?;
+    self.get_batch_size()?;
+    Ok(())
+  }
+
+  pub fn get_sweep_interval(&self) -> Result<NonZeroU64, Error> {
+    NonZeroU64::new(self.sweep_interval).ok_or_else(|| {
+      Error::InvalidConfig("hold.sweep_interval must be positive".to_string())
+    })
+  }
+
+  pub fn get_batch_size(&self) -> Result<NonZeroU64, Error> {
+    u64::try_from(self.batch_size)
+      .ok()
+      .and_then(NonZeroU64::new)
+      .ok_or_else(|| {
+        Error::InvalidConfig("hold.batch_size must be positive".to_string())
+      })
+  }
+}
+
+impl Default for HoldConfig {
+  fn default() -> Self {
+    Self {
+      default_ttl: 0,
+      sweep_interval: default_sweep_interval(),
+      batch_size: default_sweep_batch_size(),
+    }
+  }
+}
+
+/// booking quotas of every user, a limit of 0 is no limit
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QuotaConfig {
+  /// max pending, confirmed or blocked reservations
+  #[serde(default)]
+  pub max_active: i64,
+  /// max reservations overlapping at the same time
+  #[serde(default)]
+  pub max_concurrent: i64,
+  /// max hours reserved per week (monday to sunday in UTC) by resource type
+  #[serde(default)]
+  pub weekly_hours: BTreeMap<String, i64>,
+}
+
+impl QuotaConfig {
+  pub fn is_empty(&self) -> bool {
+    self.max_active <= 0
+      && self.max_concurrent <= 0
+      && self.weekly_hours.values().all(|hours| *hours <= 0)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdempotencyConfig {
+  /// seconds the response of a request sent with an idempotency key is kept
+  #[serde(default = "default_idempotency_ttl")]
+  pub ttl: u64,
+}
+
+fn default_idempotency_ttl() -> u64 {
+  24 * 60 * 60
+}
+
+impl Default for IdempotencyConfig {
+  fn default() -> Self {
+    Self {
+      ttl: default_idempotency_ttl(),
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Config {
   pub db: DbConfig,
   pub server: ServerConfig,
+  #[serde(default)]
+  pub hold: HoldConfig,
+  #[serde(default)]
+  pub quota: QuotaConfig,
+  #[serde(default)]
+  pub idempotency: IdempotencyConfig,
 }
 
 impl Config {
@@ -36,16 +138,16 @@ impl Config {
     let str =
       fs::read_to_string(filename).map_err(|_| Error::ConfigReadError)?;
 
-    Ok(serde_yml::from_str(&str).map_err(|_| Error::ConfigParseError)?)
+    let config: Self =
+      serde_yml::from_str(&str).map_err(|_| Error::ConfigParseError)?;
+    config.hold.validate()?;
+    Ok(config)
   }
 }
 impl DbConfig {
   pub fn server_url(&self) -> String {
     if self.password.is_empty() {
-      return format!(
-        "postgres://{}@{}:{}",
-        self.username, self.host, self.port
-      );
+      format!("postgres://{}@{}:{}", self.username, self.host, self.port)
     } else {
       format!(
         "postgres://{}:{}@{}:{}",
@@ -78,8 +180,34 @@ mod tests {
         server: ServerConfig {
           host: "0.0.0.0".to_string(),
           port: 50051,
-        }
+        },
+        hold: HoldConfig::default(),
+        quota: QuotaConfig::default(),
+        idempotency: IdempotencyConfig::default(),
       }
     );
   }
+
+  #[test]
+  fn hold_config_without_progress_should_be_rejected() {
+    assert!(HoldConfig::default().validate().is_ok());
+    for (sweep_interval, batch_size, field) in [
+      (0, 100, "sweep_interval"),
+      (60, 0, "batch_size"),
+      (60, -1, "batch_size"),
+    ] {
+      let config = HoldConfig {
+        sweep_interval,
+        batch_size,
+        ..Default::default()
+      };
+      assert_eq!(
+        config.validate(),
+        Err(Error::InvalidConfig(format!(
+          "hold.{} must be positive",
+          field
+        )))
+      );
+    }
+  }
 }
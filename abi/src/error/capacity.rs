@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// sqlstate raised by rsvp.reservation_capacity_trigger
+pub(crate) const CAPACITY_EXCEEDED_CODE: &str = "RV001";
+
+/// a reservation asked for more units than a resource has left over its
+/// window, decoded from the JSON detail of the capacity trigger
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CapacityConflict {
+  pub resource_id: String,
+  pub start: DateTime<Utc>,
+  pub end: DateTime<Utc>,
+  pub capacity: i32,
+  /// units still free during the whole window
+  pub available: i32,
+  pub requested: i32,
+}
+
+impl FromStr for CapacityConflict {
+  type Err = serde_json::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    serde_json::from_str(s)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn capacity_conflict_should_parse_trigger_detail() {
+    let detail = r#"{"resource_id" : "hall-1", "start" : "2024-01-21T19:00:00+08:00", "end" : "2024-01-22T12:00:00+08:00", "capacity" : 3, "available" : 1, "requested" : 2}"#;
+    let conflict: CapacityConflict = detail.parse().unwrap();
+
+    assert_eq!(conflict.resource_id, "hall-1");
+    assert_eq!(conflict.start.to_rfc3339(), "2024-01-21T11:00:00+00:00");
+    assert_eq!(conflict.end.to_rfc3339(), "2024-01-22T04:00:00+00:00");
+    assert_eq!(
+      (conflict.capacity, conflict.available, conflict.requested),
+      (3, 1, 2)
+    );
+  }
+}
@@ -1,17 +1,19 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use sqlx::types::chrono::{DateTime, Utc};
-use std::{collections::HashMap, convert::Infallible, str::FromStr};
+use std::{collections::HashMap, convert::Infallible, fmt, str::FromStr};
 
 lazy_static! {
   static ref REGEX: Regex = Regex::new(
-      r"\((?<k1>[a-zA-Z0-9_-]+)\s*,\s*(?<k2>[a-zZ-Z0-9_-]+)\)=\((?<v1>[a-zA-Z0-9_-]+)\s*,\s*[\[\(](?<v2>[^\)\]]+)[\]\)]\)",
+      r"\((?<k1>[a-zA-Z0-9_-]+)\s*,\s*(?<k2>[a-zA-Z0-9_-]+)\)=\((?<v1>.+?)\s*,\s*[\[\(](?<v2>[^\)\]]+)[\]\)]\)",
     ).unwrap();
 }
 
 #[derive(Debug, Clone)]
 pub enum ReservationConflictInfo {
-  Parsed(ReservationConflict),
+  /// the conflict is known, from the reservations it clashes with or else
+  /// the error detail of postgres
+  Parsed(Box<ReservationConflict>),
   UnParsed,
 }
 
@@ -19,21 +21,50 @@ pub enum ReservationConflictInfo {
 pub struct ReservationConflict {
   pub new: ReservationWindow,
   pub old: ReservationWindow,
+  /// the reservations don't overlap, only their buffers do. the windows
+  /// then include the buffers
+  pub buffer_only: bool,
+  /// the existing reservations clashing with the new one, empty if only
+  /// known from the error detail
+  pub existing: Vec<crate::Reservation>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReservationWindow {
   pub rid: String,
   pub start: DateTime<Utc>,
   pub end: DateTime<Utc>,
 }
 
+impl ReservationConflictInfo {
+  pub fn is_buffer_only(&self) -> bool {
+    matches!(self, ReservationConflictInfo::Parsed(conflict) if conflict.buffer_only)
+  }
+}
+
+impl fmt::Display for ReservationConflictInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReservationConflictInfo::Parsed(conflict) => write!(
+        f,
+        "{} {} - {} overlaps existing {} - {}",
+        conflict.new.rid,
+        conflict.new.start.to_rfc3339(),
+        conflict.new.end.to_rfc3339(),
+        conflict.old.start.to_rfc3339(),
+        conflict.old.end.to_rfc3339()
+      ),
+      ReservationConflictInfo::UnParsed => write!(f, "unknown window"),
+    }
+  }
+}
+
 impl FromStr for ReservationConflictInfo {
   type Err = Infallible;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
     if let Ok(conflict) = s.parse() {
-      Ok(ReservationConflictInfo::Parsed(conflict))
+      Ok(ReservationConflictInfo::Parsed(Box::new(conflict)))
     } else {
       Ok(ReservationConflictInfo::UnParsed)
     }
@@ -54,6 +85,9 @@ impl TryFrom<ParsedInfo> for ReservationConflict {
     Ok(Self {
       new: value.new.try_into()?,
       old: value.old.try_into()?,
+      // only timespans are parsed, the reservations overlap
+      buffer_only: false,
+      existing: Vec::new(),
     })
   }
 }
@@ -61,6 +95,9 @@ impl TryFrom<ParsedInfo> for ReservationConflict {
 impl TryFrom<HashMap<String, String>> for ReservationWindow {
   type Error = ();
   fn try_from(value: HashMap<String, String>) -> Result<Self, Self::Error> {
+    // reservation_buffer_conflict reports the padded spans, which don't tell
+    // whether the reservations overlap or only their buffers do, they are
+    // left for the reservation crate to look up
     let timespan_str = value.get("timespan").ok_or(())?.replace('"', "");
 
     let (start, end) = parse_timespan(&timespan_str)?;
@@ -101,12 +138,14 @@ impl FromStr for ParsedInfo {
 
 fn parse_datetime(s: &str) -> Result<DateTime<Utc>, ()> {
   Ok(
-    DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%#z")
+    DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%#z")
       .map_err(|_| ())?
       .with_timezone(&Utc),
   )
 }
-fn parse_timespan(s: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), ()> {
+pub(crate) fn parse_timespan(
+  s: &str,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), ()> {
   let mut split_str = s.splitn(2, ',');
 
   let start = parse_datetime(split_str.next().ok_or(())?)?;
@@ -122,7 +161,10 @@ mod tests {
   #[test]
   fn parse_datetime_should_work() {
     let datetime = parse_datetime("2022-12-26 22:00:00+00").unwrap();
-    assert_eq!(datetime.to_rfc3339(), "2022-12-26T22:00:00+00:00")
+    assert_eq!(datetime.to_rfc3339(), "2022-12-26T22:00:00+00:00");
+
+    let datetime = parse_datetime("2022-12-26 22:00:00.25+08").unwrap();
+    assert_eq!(datetime.to_rfc3339(), "2022-12-26T14:00:00.250+00:00")
   }
 
   #[test]
@@ -172,8 +214,33 @@ mod tests {
           "2022-12-25T22:00:00+00:00"
         );
         assert_eq!(conflict.old.end.to_rfc3339(), "2022-12-28T19:00:00+00:00");
+        assert!(!conflict.buffer_only);
       }
       ReservationConflictInfo::UnParsed => panic!("should have parsed"),
     }
   }
+
+  #[test]
+  fn conflict_error_message_with_any_resource_id_should_parse() {
+    let msg = ERR_MSG.replace("ocean-view-room-713", "room 7.13");
+    let info: ReservationConflictInfo = msg.parse().unwrap();
+    let ReservationConflictInfo::Parsed(conflict) = info else {
+      panic!("should have parsed");
+    };
+    assert_eq!(conflict.new.rid, "room 7.13");
+    assert_eq!(conflict.old.rid, "room 7.13");
+
+    let info: ReservationConflictInfo = "".parse().unwrap();
+    assert!(matches!(info, ReservationConflictInfo::UnParsed));
+  }
+
+  #[test]
+  fn buffer_conflict_error_message_should_not_parse() {
+    let msg = ERR_MSG.replace("timespan", "padded_span");
+    let info: ReservationConflictInfo = msg.parse().unwrap();
+    assert!(matches!(info, ReservationConflictInfo::UnParsed));
+
+    let info: ReservationConflictInfo = ERR_MSG.parse().unwrap();
+    assert!(!info.is_buffer_only());
+  }
 }
@@ -0,0 +1,468 @@
+use chrono::Duration;
+use prost::Message;
+use tonic::Status;
+
+use crate::{
+  convert_to_duration, convert_to_timestamp, convert_to_utc_time,
+  BatchItemError, Error, ErrorDetails, ErrorInfo, FieldViolation,
+  ReservationConflict, ReservationConflictDetail, ReservationConflictInfo,
+  ReservationWindow, RuleKind, RuleViolation, RuleViolationDetail,
+};
+
+const ERROR_DOMAIN: &str = "reservation";
+
+impl Error {
+  /// the kind of error in UPPER_SNAKE_CASE, see `ErrorInfo::reason`
+  pub fn reason(&self) -> &'static str {
+    match self {
+      Error::InvalidTime => "INVALID_TIME",
+      Error::ConfigReadError => "CONFIG_READ_ERROR",
+      Error::ConfigParseError => "CONFIG_PARSE_ERROR",
+      Error::InvalidConfig(_) => "INVALID_CONFIG",
+      Error::InvalidReservationId(_) => "INVALID_RESERVATION_ID",
+      Error::InvalidUserId(_) => "INVALID_USER_ID",
+      Error::InvalidResourceId(_) => "INVALID_RESOURCE_ID",
+      Error::DbError(_) => "DB_ERROR",
+      Error::InvalidResource(_) => "INVALID_RESOURCE",
+      Error::UnknownResource(_) => "UNKNOWN_RESOURCE",
+      Error::ResourceDisabled(_) => "RESOURCE_DISABLED",
+      Error::ResourceInUse(_) => "RESOURCE_IN_USE",
+      Error::ConflictReservation(_) => "CONFLICT_RESERVATION",
+      Error::CapacityExceeded(_) => "CAPACITY_EXCEEDED",
+      Error::QuotaExceeded(_) => "QUOTA_EXCEEDED",
+      Error::RuleViolation(_) => "RULE_VIOLATION",
+      Error::InvalidIcs(_) => "INVALID_ICS",
+      Error::InvalidRecurrence(_) => "INVALID_RECURRENCE",
+      Error::InvalidTimezone(_) => "INVALID_TIMEZONE",
+      Error::InvalidLocalTime(_) => "INVALID_LOCAL_TIME",
+      Error::InvalidQuantity(_) => "INVALID_QUANTITY",
+      Error::BatchConflict(_) => "BATCH_CONFLICT",
+      Error::InvalidTransition { .. } => "INVALID_TRANSITION",
+      Error::NotCancelled(_) => "NOT_CANCELLED",
+      Error::InvalidUpdateMask(_) => "INVALID_UPDATE_MASK",
+      Error::VersionMismatch { .. } => "VERSION_MISMATCH",
+      Error::InvalidIdempotencyKey(_) => "INVALID_IDEMPOTENCY_KEY",
+      Error::IdempotencyKeyMismatch(_) => "IDEMPOTENCY_KEY_MISMATCH",
+      Error::IdempotencyKeyInUse(_) => "IDEMPOTENCY_KEY_IN_USE",
+      Error::NotFound => "NOT_FOUND",
+      Error::Unknown => "UNKNOWN",
+    }
+  }
+}
+
+impl From<&Error> for ErrorDetails {
+  fn from(error: &Error) -> Self {
+    let mut details = Self::default();
+    let mut info = ErrorInfo {
+      reason: error.reason().to_string(),
+      domain: ERROR_DOMAIN.to_string(),
+      ..Default::default()
+    };
+    let field = match error {
+      Error::InvalidReservationId(_) => Some("id"),
+      Error::InvalidUserId(_) => Some("user_id"),
+      Error::InvalidResourceId(_) => Some("resource_id"),
+      Error::InvalidQuantity(_) => Some("quantity"),
+      Error::InvalidTimezone(_) => Some("timezone"),
+      Error::InvalidUpdateMask(_) => Some("update_mask"),
+      Error::InvalidIdempotencyKey(_) | Error::IdempotencyKeyMismatch(_) => {
+        Some("idempotency_key")
+      }
+      _ => None,
+    };
+    if let Some(field) = field {
+      details.field_violations.push(FieldViolation {
+        field: field.to_string(),
+        description: error.to_string(),
+      });
+    }
+
+    match error {
+      Error::UnknownResource(id)
+      | Error::ResourceDisabled(id)
+      | Error::ResourceInUse(id) => {
+        info.metadata.insert("resource_id".to_string(), id.clone());
+      }
+      Error::ConflictReservation(conflict) => {
+        details
+          .conflicts
+          .extend(ReservationConflictDetail::from_info(conflict));
+      }
+      Error::CapacityExceeded(conflict) => {
+        info.metadata.extend([
+          ("resource_id".to_string(), conflict.resource_id.clone()),
+          ("capacity".to_string(), conflict.capacity.to_string()),
+          ("available".to_string(), conflict.available.to_string()),
+          ("requested".to_string(), conflict.requested.to_string()),
+        ]);
+      }
+      Error::QuotaExceeded(usage) => details.quota = Some(usage.clone()),
+      Error::RuleViolation(violations) => {
+        details.rule_violations = violations.iter().map(Into::into).collect();
+      }
+      Error::BatchConflict(conflicts) => {
+        let indexes: Vec<String> =
+          conflicts.iter().map(|c| c.index.to_string()).collect();
+        info
+          .metadata
+          .insert("indexes".to_string(), indexes.join(","));
+        details.batch_errors = conflicts
+          .iter()
+          .map(|conflict| BatchItemError {
+            index: conflict.index as u32,
+            details: Some((&conflict.error).into()),
+          })
+          .collect();
+      }
+      Error::InvalidTransition { from, to } => {
+        info.metadata.extend([
+          ("from".to_string(), from.to_string()),
+          ("to".to_string(), to.to_string()),
+        ]);
+      }
+      Error::NotCancelled(status) => {
+        info
+          .metadata
+          .insert("status".to_string(), status.to_string());
+      }
+      Error::VersionMismatch { expected, actual } => {
+        info.metadata.extend([
+          ("expected_version".to_string(), expected.to_string()),
+          ("actual_version".to_string(), actual.to_string()),
+        ]);
+      }
+      _ => {}
+    }
+
+    details.info = Some(info);
+    details
+  }
+}
+
+impl ErrorDetails {
+  /// details carried by `status`, None if it has none
+  pub fn from_status(status: &Status) -> Option<Self> {
+    if status.details().is_empty() {
+      return None;
+    }
+    Self::decode(status.details()).ok()
+  }
+
+  /// `status` with its details replaced by these
+  pub fn attach_to(&self, status: Status) -> Status {
+    Status::with_details(
+      status.code(),
+      status.message(),
+      self.encode_to_vec().into(),
+    )
+  }
+
+  /// the conflicts of the details decoded back, incomplete ones are skipped
+  pub fn get_conflicts(&self) -> Vec<ReservationConflict> {
+    self
+      .conflicts
+      .iter()
+      .filter_map(|detail| detail.try_into().ok())
+      .collect()
+  }
+
+  /// the broken booking rules of the details decoded back, incomplete ones
+  /// are skipped
+  pub fn get_rule_violations(&self) -> Vec<RuleViolation> {
+    self
+      .rule_violations
+      .iter()
+      .filter_map(|detail| detail.try_into().ok())
+      .collect()
+  }
+}
+
+impl ReservationConflict {
+  /// the first conflict in the details of `status`, for clients of the
+  /// service
+  pub fn from_status(status: &Status) -> Option<Self> {
+    ErrorDetails::from_status(status)?
+      .get_conflicts()
+      .into_iter()
+      .next()
+  }
+}
+
+impl ReservationConflictDetail {
+  fn from_info(info: &ReservationConflictInfo) -> Option<Self> {
+    match info {
+      ReservationConflictInfo::Parsed(conflict) => {
+        Some(conflict.as_ref().into())
+      }
+      ReservationConflictInfo::UnParsed => None,
+    }
+  }
+}
+
+impl From<&ReservationConflict> for ReservationConflictDetail {
+  fn from(conflict: &ReservationConflict) -> Self {
+    Self {
+      resource_id: conflict.new.rid.clone(),
+      start: Some(convert_to_timestamp(conflict.new.start)),
+      end: Some(convert_to_timestamp(conflict.new.end)),
+      existing_start: Some(convert_to_timestamp(conflict.old.start)),
+      existing_end: Some(convert_to_timestamp(conflict.old.end)),
+      buffer_only: conflict.buffer_only,
+      existing: conflict.existing.clone(),
+    }
+  }
+}
+
+impl TryFrom<&ReservationConflictDetail> for ReservationConflict {
+  type Error = ();
+
+  fn try_from(detail: &ReservationConflictDetail) -> Result<Self, ()> {
+    let window = |start: Option<_>, end: Option<_>| {
+      Ok(ReservationWindow {
+        rid: detail.resource_id.clone(),
+        start: convert_to_utc_time(start.ok_or(())?),
+        end: convert_to_utc_time(end.ok_or(())?),
+      })
+    };
+
+    Ok(Self {
+      new: window(detail.start, detail.end)?,
+      old: window(detail.existing_start, detail.existing_end)?,
+      buffer_only: detail.buffer_only,
+      existing: detail.existing.clone(),
+    })
+  }
+}
+
+impl From<&RuleViolation> for RuleViolationDetail {
+  fn from(violation: &RuleViolation) -> Self {
+    let limit = |kind: RuleKind, limit: &Duration| Self {
+      kind: kind as i32,
+      limit: Some(prost_types::Duration {
+        seconds: limit.num_seconds(),
+        nanos: 0,
+      }),
+      ..Default::default()
+    };
+
+    match violation {
+      RuleViolation::OutsideOpeningHours => Self {
+        kind: RuleKind::OutsideOpeningHours as i32,
+        ..Default::default()
+      },
+      RuleViolation::Blackout { start, end, reason } => Self {
+        kind: RuleKind::Blackout as i32,
+        start: Some(convert_to_timestamp(*start)),
+        end: Some(convert_to_timestamp(*end)),
+        reason: reason.clone(),
+        limit: None,
+      },
+      RuleViolation::TooShort { min_duration } => {
+        limit(RuleKind::TooShort, min_duration)
+      }
+      RuleViolation::TooLong { max_duration } => {
+        limit(RuleKind::TooLong, max_duration)
+      }
+      RuleViolation::TooSoon { min_lead_time } => {
+        limit(RuleKind::TooSoon, min_lead_time)
+      }
+      RuleViolation::TooFarAhead { max_horizon } => {
+        limit(RuleKind::TooFarAhead, max_horizon)
+      }
+    }
+  }
+}
+
+impl TryFrom<&RuleViolationDetail> for RuleViolation {
+  type Error = ();
+
+  fn try_from(detail: &RuleViolationDetail) -> Result<Self, ()> {
+    let limit = || detail.limit.map(convert_to_duration).ok_or(());
+
+    Ok(match RuleKind::try_from(detail.kind).map_err(|_| ())? {
+      RuleKind::OutsideOpeningHours => RuleViolation::OutsideOpeningHours,
+      RuleKind::Blackout => RuleViolation::Blackout {
+        start: convert_to_utc_time(detail.start.ok_or(())?),
+        end: convert_to_utc_time(detail.end.ok_or(())?),
+        reason: detail.reason.clone(),
+      },
+      RuleKind::TooShort => RuleViolation::TooShort {
+        min_duration: limit()?,
+      },
+      RuleKind::TooLong => RuleViolation::TooLong {
+        max_duration: limit()?,
+      },
+      RuleKind::TooSoon => RuleViolation::TooSoon {
+        min_lead_time: limit()?,
+      },
+      RuleKind::TooFarAhead => RuleViolation::TooFarAhead {
+        max_horizon: limit()?,
+      },
+      RuleKind::Unknown => return Err(()),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    AvailableWindow, BatchConflict, CapacityConflict, QuotaKind, QuotaUsage,
+  };
+  use tonic::Code;
+
+  const CONFLICT_DETAIL: &str = "Key (resource_id, timespan)=(room-1, [\"2024-01-08 09:00:00+00\",\"2024-01-08 11:00:00+00\")) conflicts with existing key (resource_id, timespan)=(room-1, [\"2024-01-08 10:00:00+00\",\"2024-01-08 12:00:00+00\")).";
+
+  fn conflict() -> Error {
+    Error::ConflictReservation(CONFLICT_DETAIL.parse().unwrap())
+  }
+
+  #[test]
+  fn error_details_should_round_trip_through_status() {
+    let status = Status::already_exists("taken");
+    assert_eq!(ErrorDetails::from_status(&status), None);
+
+    let details = ErrorDetails {
+      alternatives: vec![AvailableWindow {
+        resource_id: "room-1".to_string(),
+        start: Some(convert_to_timestamp(
+          "2024-01-08T09:00:00Z".parse().unwrap(),
+        )),
+        end: Some(convert_to_timestamp(
+          "2024-01-08T10:00:00Z".parse().unwrap(),
+        )),
+      }],
+      ..Default::default()
+    };
+    let status = details.attach_to(status);
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(status.message(), "taken");
+    assert_eq!(ErrorDetails::from_status(&status), Some(details));
+  }
+
+  #[test]
+  fn conflict_status_should_decode_back_to_conflict() {
+    let status = Status::from(conflict());
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(
+      status.message(),
+      "Conflict reservation: room-1 2024-01-08T09:00:00+00:00 - 2024-01-08T11:00:00+00:00 overlaps existing 2024-01-08T10:00:00+00:00 - 2024-01-08T12:00:00+00:00"
+    );
+
+    let details = ErrorDetails::from_status(&status).unwrap();
+    assert_eq!(details.info.unwrap().reason, "CONFLICT_RESERVATION");
+
+    let conflict = ReservationConflict::from_status(&status).unwrap();
+    assert_eq!(conflict.new.rid, "room-1");
+    assert_eq!(conflict.new.start.to_rfc3339(), "2024-01-08T09:00:00+00:00");
+    assert_eq!(conflict.old.rid, "room-1");
+    assert_eq!(conflict.old.end.to_rfc3339(), "2024-01-08T12:00:00+00:00");
+    assert!(!conflict.buffer_only);
+  }
+
+  #[test]
+  fn status_details_should_describe_the_error() {
+    let status = Status::from(Error::InvalidUserId(String::new()));
+    let details = ErrorDetails::from_status(&status).unwrap();
+    assert_eq!(details.field_violations.len(), 1);
+    assert_eq!(details.field_violations[0].field, "user_id");
+
+    let usage = QuotaUsage {
+      kind: QuotaKind::ActiveReservations as i32,
+      limit: 3,
+      used: 3,
+      ..Default::default()
+    };
+    let status = Status::from(Error::QuotaExceeded(usage.clone()));
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    let details = ErrorDetails::from_status(&status).unwrap();
+    assert_eq!(details.quota, Some(usage));
+
+    let batch = Error::BatchConflict(vec![BatchConflict {
+      index: 1,
+      error: conflict(),
+    }]);
+    let details = ErrorDetails::from_status(&Status::from(batch)).unwrap();
+    assert_eq!(details.info.unwrap().metadata["indexes"], "1");
+    assert_eq!(details.batch_errors.len(), 1);
+    assert_eq!(
+      details.batch_errors[0]
+        .details
+        .as_ref()
+        .unwrap()
+        .conflicts
+        .len(),
+      1
+    );
+  }
+
+  #[test]
+  fn batch_status_should_describe_every_failed_item() {
+    let capacity = CapacityConflict {
+      resource_id: "room-1".to_string(),
+      start: "2024-01-08T09:00:00Z".parse().unwrap(),
+      end: "2024-01-08T11:00:00Z".parse().unwrap(),
+      capacity: 4,
+      available: 1,
+      requested: 2,
+    };
+    let violations = vec![
+      RuleViolation::OutsideOpeningHours,
+      RuleViolation::Blackout {
+        start: "2024-01-15T00:00:00Z".parse().unwrap(),
+        end: "2024-01-16T00:00:00Z".parse().unwrap(),
+        reason: "holiday".to_string(),
+      },
+      RuleViolation::TooLong {
+        max_duration: Duration::hours(2),
+      },
+    ];
+    let batch = Error::BatchConflict(vec![
+      BatchConflict {
+        index: 0,
+        error: conflict(),
+      },
+      BatchConflict {
+        index: 2,
+        error: Error::CapacityExceeded(capacity),
+      },
+      BatchConflict {
+        index: 3,
+        error: Error::RuleViolation(violations.clone()),
+      },
+    ]);
+    let status = Status::from(batch);
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let details = ErrorDetails::from_status(&status).unwrap();
+    let items: Vec<_> = details
+      .batch_errors
+      .into_iter()
+      .map(|item| (item.index, item.details.unwrap()))
+      .collect();
+    assert_eq!(items.len(), 3);
+
+    let (index, conflict) = &items[0];
+    assert_eq!(*index, 0);
+    assert_eq!(conflict.get_conflicts()[0].old.rid, "room-1");
+
+    let (index, capacity) = &items[1];
+    assert_eq!(*index, 2);
+    let info = capacity.info.as_ref().unwrap();
+    assert_eq!(info.reason, "CAPACITY_EXCEEDED");
+    assert_eq!(info.metadata["available"], "1");
+    assert_eq!(info.metadata["requested"], "2");
+
+    let (index, rules) = &items[2];
+    assert_eq!(*index, 3);
+    assert_eq!(rules.info.as_ref().unwrap().reason, "RULE_VIOLATION");
+    assert_eq!(rules.get_rule_violations(), violations);
+  }
+
+  #[test]
+  fn db_error_status_should_not_leak_the_cause() {
+    let status = Status::from(Error::DbError(sqlx::Error::PoolTimedOut));
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "database error");
+  }
+}
@@ -1,11 +1,20 @@
+mod capacity;
 mod conflict;
+mod details;
+mod rule;
+pub use capacity::CapacityConflict;
+use capacity::CAPACITY_EXCEEDED_CODE;
+pub(crate) use conflict::parse_timespan;
 pub use conflict::{
   ReservationConflict, ReservationConflictInfo, ReservationWindow,
 };
+pub use rule::RuleViolation;
 use sqlx::postgres::PgDatabaseError;
 use thiserror::Error;
 use tonic::Status;
 
+use crate::{ErrorDetails, QuotaUsage, ReservationStatus};
+
 #[derive(Error, Debug)]
 pub enum Error {
   #[error("time is error")]
@@ -17,6 +26,9 @@ pub enum Error {
   #[error("Failed to parse configuration file")]
   ConfigParseError,
 
+  #[error("configuration is invalid, {0}")]
+  InvalidConfig(String),
+
   #[error("id is invalid, id={0}")]
   InvalidReservationId(i64),
 
@@ -29,9 +41,72 @@ pub enum Error {
   #[error("sqlx query error")]
   DbError(sqlx::Error),
 
+  #[error("resource is invalid, {0}")]
+  InvalidResource(String),
+
+  #[error("resource not found, resource_id={0}")]
+  UnknownResource(String),
+
+  #[error("resource is disabled, resource_id={0}")]
+  ResourceDisabled(String),
+
+  #[error("resource still has reservations, resource_id={0}")]
+  ResourceInUse(String),
+
   #[error("conflict reservation")]
   ConflictReservation(ReservationConflictInfo),
 
+  #[error("not enough capacity left on resource {}", .0.resource_id)]
+  CapacityExceeded(CapacityConflict),
+
+  #[error("quota exceeded, {} of {} used", .0.used, .0.limit)]
+  QuotaExceeded(QuotaUsage),
+
+  #[error("reservation breaks {} booking rules", .0.len())]
+  RuleViolation(Vec<RuleViolation>),
+
+  #[error("ics file is invalid, {0}")]
+  InvalidIcs(String),
+
+  #[error("recurrence rule is invalid, {0}")]
+  InvalidRecurrence(String),
+
+  #[error("timezone is invalid, timezone={0}")]
+  InvalidTimezone(String),
+
+  #[error("local time is invalid, {0}")]
+  InvalidLocalTime(String),
+
+  #[error("quantity must be positive, quantity={0}")]
+  InvalidQuantity(i32),
+
+  #[error("{} reservations in the batch failed", .0.len())]
+  BatchConflict(Vec<BatchConflict>),
+
+  #[error("invalid transition from {from} to {to}")]
+  InvalidTransition {
+    from: ReservationStatus,
+    to: ReservationStatus,
+  },
+
+  #[error("reservation is {0}, only cancelled ones can be restored")]
+  NotCancelled(ReservationStatus),
+
+  #[error("invalid update mask path: {0:?}")]
+  InvalidUpdateMask(String),
+
+  #[error("reservation is at version {actual}, expected {expected}")]
+  VersionMismatch { expected: i64, actual: i64 },
+
+  #[error("idempotency key is invalid, key={0}")]
+  InvalidIdempotencyKey(String),
+
+  #[error("idempotency key was used for another request, key={0}")]
+  IdempotencyKeyMismatch(String),
+
+  #[error("request with the idempotency key is still running, key={0}")]
+  IdempotencyKeyInUse(String),
+
   #[error("no reservation found by the given condition")]
   NotFound,
 
@@ -39,18 +114,74 @@ pub enum Error {
   Unknown,
 }
 
+/// a failed reservation of a batch, `index` is its position in the batch and
+/// `error` is why it is invalid, `ConflictReservation`, `CapacityExceeded` or
+/// `RuleViolation`
+#[derive(Debug)]
+pub struct BatchConflict {
+  pub index: usize,
+  pub error: Error,
+}
+
 impl PartialEq for Error {
   fn eq(&self, other: &Self) -> bool {
-    matches!(
-      (self, other),
-      (Error::DbError(_), Error::DbError(_))
-        | (Error::InvalidTime, Error::InvalidTime)
-        | (Error::ConflictReservation(_), Error::ConflictReservation(_))
-        | (Error::NotFound, Error::NotFound)
-        | (Error::InvalidUserId(_), Error::InvalidUserId(_))
-        | (Error::InvalidResourceId(_), Error::InvalidResourceId(_))
-        | (Error::Unknown, Error::Unknown)
-    )
+    match (self, other) {
+      (
+        Error::InvalidTransition { from, to },
+        Error::InvalidTransition {
+          from: other_from,
+          to: other_to,
+        },
+      ) => from == other_from && to == other_to,
+      (Error::NotCancelled(status), Error::NotCancelled(other_status)) => {
+        status == other_status
+      }
+      (Error::InvalidConfig(reason), Error::InvalidConfig(other_reason)) => {
+        reason == other_reason
+      }
+      (
+        Error::VersionMismatch { expected, actual },
+        Error::VersionMismatch {
+          expected: other_expected,
+          actual: other_actual,
+        },
+      ) => expected == other_expected && actual == other_actual,
+      _ => matches!(
+        (self, other),
+        (Error::DbError(_), Error::DbError(_))
+          | (Error::InvalidTime, Error::InvalidTime)
+          | (Error::ConfigReadError, Error::ConfigReadError)
+          | (Error::ConfigParseError, Error::ConfigParseError)
+          | (Error::ConflictReservation(_), Error::ConflictReservation(_))
+          | (Error::CapacityExceeded(_), Error::CapacityExceeded(_))
+          | (Error::RuleViolation(_), Error::RuleViolation(_))
+          | (Error::QuotaExceeded(_), Error::QuotaExceeded(_))
+          | (Error::InvalidQuantity(_), Error::InvalidQuantity(_))
+          | (Error::InvalidTimezone(_), Error::InvalidTimezone(_))
+          | (Error::InvalidLocalTime(_), Error::InvalidLocalTime(_))
+          | (Error::InvalidRecurrence(_), Error::InvalidRecurrence(_))
+          | (Error::InvalidIcs(_), Error::InvalidIcs(_))
+          | (Error::BatchConflict(_), Error::BatchConflict(_))
+          | (Error::InvalidUpdateMask(_), Error::InvalidUpdateMask(_))
+          | (
+            Error::InvalidIdempotencyKey(_),
+            Error::InvalidIdempotencyKey(_)
+          )
+          | (
+            Error::IdempotencyKeyMismatch(_),
+            Error::IdempotencyKeyMismatch(_)
+          )
+          | (Error::IdempotencyKeyInUse(_), Error::IdempotencyKeyInUse(_))
+          | (Error::NotFound, Error::NotFound)
+          | (Error::InvalidUserId(_), Error::InvalidUserId(_))
+          | (Error::InvalidResourceId(_), Error::InvalidResourceId(_))
+          | (Error::InvalidResource(_), Error::InvalidResource(_))
+          | (Error::UnknownResource(_), Error::UnknownResource(_))
+          | (Error::ResourceDisabled(_), Error::ResourceDisabled(_))
+          | (Error::ResourceInUse(_), Error::ResourceInUse(_))
+          | (Error::Unknown, Error::Unknown)
+      ),
+    }
   }
 }
 
@@ -61,14 +192,22 @@ impl From<sqlx::Error> for Error {
         let err: &PgDatabaseError = e.downcast_ref();
         match (err.code(), err.schema(), err.table()) {
           ("23P01", Some("rsvp"), Some("reservations")) => {
+            // the detail is a fallback, the reservation crate looks the
+            // clashing reservations up
             Error::ConflictReservation(
               err
                 .detail()
-                .unwrap()
-                .parse::<ReservationConflictInfo>()
-                .unwrap(),
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(ReservationConflictInfo::UnParsed),
             )
           }
+          (CAPACITY_EXCEEDED_CODE, _, _) => {
+            match err.detail().unwrap_or_default().parse() {
+              Ok(conflict) => Error::CapacityExceeded(conflict),
+              Err(_) => Error::DbError(sqlx::Error::Database(e)),
+            }
+          }
           _ => Error::DbError(sqlx::Error::Database(e)),
         }
       }
@@ -80,11 +219,12 @@ impl From<sqlx::Error> for Error {
 
 impl From<Error> for Status {
   fn from(value: Error) -> Self {
-    match value {
+    let details = ErrorDetails::from(&value);
+    let status = match value {
       Error::InvalidTime => Status::invalid_argument(value.to_string()),
-      Error::ConfigReadError | Error::ConfigParseError => {
-        Status::internal(value.to_string())
-      }
+      Error::ConfigReadError
+      | Error::ConfigParseError
+      | Error::InvalidConfig(_) => Status::internal(value.to_string()),
       Error::InvalidReservationId(id) => {
         Status::invalid_argument(format!("Invalid reservation id: {}", id))
       }
@@ -94,12 +234,53 @@ impl From<Error> for Status {
       Error::InvalidResourceId(resource_id) => Status::invalid_argument(
         format!("Invalid resource id: {}", resource_id),
       ),
-      Error::DbError(e) => Status::internal(e.to_string()),
+      Error::InvalidResource(_) => Status::invalid_argument(value.to_string()),
+      Error::UnknownResource(_) => Status::not_found(value.to_string()),
+      Error::ResourceDisabled(_) | Error::ResourceInUse(_) => {
+        Status::failed_precondition(value.to_string())
+      }
+      // keep database internals out of the response
+      Error::DbError(_) => Status::internal("database error"),
+      Error::ConflictReservation(info) if info.is_buffer_only() => {
+        Status::already_exists(format!(
+          "Conflict reservation, only buffer times overlap: {}",
+          info
+        ))
+      }
       Error::ConflictReservation(info) => {
-        Status::already_exists(format!("Conflict reservation: {:?}", info))
+        Status::already_exists(format!("Conflict reservation: {}", info))
       }
+      Error::CapacityExceeded(_) => Status::already_exists(value.to_string()),
+      Error::QuotaExceeded(_) => Status::resource_exhausted(value.to_string()),
+      Error::RuleViolation(violations) => {
+        let violations: Vec<String> =
+          violations.iter().map(|v| v.to_string()).collect();
+        Status::failed_precondition(format!(
+          "Booking rules violated: {}",
+          violations.join("; ")
+        ))
+      }
+      Error::InvalidQuantity(_)
+      | Error::InvalidTimezone(_)
+      | Error::InvalidLocalTime(_)
+      | Error::InvalidRecurrence(_)
+      | Error::InvalidIcs(_) => Status::invalid_argument(value.to_string()),
+      Error::BatchConflict(_) => Status::already_exists(value.to_string()),
+      Error::InvalidTransition { .. } | Error::NotCancelled(_) => {
+        Status::failed_precondition(value.to_string())
+      }
+      Error::InvalidUpdateMask(_)
+      | Error::InvalidIdempotencyKey(_)
+      | Error::IdempotencyKeyMismatch(_) => {
+        Status::invalid_argument(value.to_string())
+      }
+      // the first request may still succeed or fail, retry later
+      Error::IdempotencyKeyInUse(_) => Status::aborted(value.to_string()),
+      // read the reservation again and retry
+      Error::VersionMismatch { .. } => Status::aborted(value.to_string()),
       Error::NotFound => Status::not_found(value.to_string()),
       Error::Unknown => Status::unknown(value.to_string()),
-    }
+    };
+    details.attach_to(status)
   }
 }
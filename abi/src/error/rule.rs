@@ -0,0 +1,59 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// a booking rule of its resource a reservation breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleViolation {
+  /// some of the reservation falls outside the opening hours
+  OutsideOpeningHours,
+  /// the reservation overlaps a blackout period
+  Blackout {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    reason: String,
+  },
+  TooShort {
+    min_duration: Duration,
+  },
+  TooLong {
+    max_duration: Duration,
+  },
+  /// made less than `min_lead_time` before its start
+  TooSoon {
+    min_lead_time: Duration,
+  },
+  /// made more than `max_horizon` before its start
+  TooFarAhead {
+    max_horizon: Duration,
+  },
+}
+
+impl fmt::Display for RuleViolation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RuleViolation::OutsideOpeningHours => {
+        write!(f, "outside opening hours")
+      }
+      RuleViolation::Blackout { start, end, reason } => {
+        write!(f, "overlaps blackout {} - {}", start, end)?;
+        if !reason.is_empty() {
+          write!(f, " ({})", reason)?;
+        }
+        Ok(())
+      }
+      RuleViolation::TooShort { min_duration } => {
+        write!(f, "shorter than {}", min_duration)
+      }
+      RuleViolation::TooLong { max_duration } => {
+        write!(f, "longer than {}", max_duration)
+      }
+      RuleViolation::TooSoon { min_lead_time } => {
+        write!(f, "starts in less than {}", min_lead_time)
+      }
+      RuleViolation::TooFarAhead { max_horizon } => {
+        write!(f, "starts in more than {}", max_horizon)
+      }
+    }
+  }
+}
@@ -15,27 +15,280 @@ pub struct Reservation {
   pub end: ::core::option::Option<::prost_types::Timestamp>,
   #[prost(string, tag = "7")]
   pub note: ::prost::alloc::string::String,
+  /// set when the reservation is cancelled
+  #[prost(message, optional, tag = "8")]
+  pub cancelled_at: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(string, tag = "9")]
+  pub cancelled_by: ::prost::alloc::string::String,
+  #[prost(string, tag = "10")]
+  pub reason: ::prost::alloc::string::String,
+  /// pending reservation expires after this time, empty to hold it forever
+  #[prost(message, optional, tag = "11")]
+  pub hold_until: ::core::option::Option<::prost_types::Timestamp>,
+  /// units of the resource it takes, 0 means 1
+  #[prost(int32, tag = "12")]
+  pub quantity: i32,
+  /// recurring series it is an occurrence of, 0 for none
+  #[prost(int64, tag = "13")]
+  pub series_id: i64,
+  /// IANA timezone it is made in, e.g. Asia/Tokyo, empty for the one of
+  /// its resource
+  #[prost(string, tag = "14")]
+  pub timezone: ::prost::alloc::string::String,
+  /// start and end as RFC 3339 in the timezone, only set in responses
+  #[prost(string, tag = "15")]
+  pub local_start: ::prost::alloc::string::String,
+  #[prost(string, tag = "16")]
+  pub local_end: ::prost::alloc::string::String,
+  /// bumped on every update, pass it as expected_version to update only
+  /// what was read
+  #[prost(int64, tag = "17")]
+  pub version: i64,
+  #[prost(message, optional, tag = "18")]
+  pub updated_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationSeries {
+  /// template of every occurrence, start and end are the first occurrence
+  #[prost(message, optional, tag = "1")]
+  pub reservation: ::core::option::Option<Reservation>,
+  /// iCalendar RRULE, e.g. FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10
+  #[prost(string, tag = "2")]
+  pub rrule: ::prost::alloc::string::String,
+  /// starts of occurrences to skip
+  #[prost(message, repeated, tag = "3")]
+  pub exdates: ::prost::alloc::vec::Vec<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Resource {
+  #[prost(string, tag = "1")]
+  pub id: ::prost::alloc::string::String,
+  #[prost(string, tag = "2")]
+  pub name: ::prost::alloc::string::String,
+  /// kind of the resource, e.g. room, desk, equipment
+  #[prost(string, tag = "3")]
+  pub resource_type: ::prost::alloc::string::String,
+  /// how many reservations it can hold at the same time
+  #[prost(int32, tag = "4")]
+  pub capacity: i32,
+  /// IANA timezone name of the resource, e.g. Asia/Shanghai
+  #[prost(string, tag = "5")]
+  pub timezone: ::prost::alloc::string::String,
+  /// JSON object of free-form attributes, empty for none
+  #[prost(string, tag = "6")]
+  pub attributes: ::prost::alloc::string::String,
+  /// disabled resources can't be reserved
+  #[prost(bool, tag = "7")]
+  pub active: bool,
+  /// when it can be booked, empty for any time
+  #[prost(message, optional, tag = "8")]
+  pub rules: ::core::option::Option<BookingRules>,
+  /// time kept free before and after every reservation, e.g. for
+  /// cleaning, in whole seconds
+  #[prost(message, optional, tag = "9")]
+  pub buffer_before: ::core::option::Option<::prost_types::Duration>,
+  #[prost(message, optional, tag = "10")]
+  pub buffer_after: ::core::option::Option<::prost_types::Duration>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BookingRules {
+  /// periods it is open in the timezone of the resource, empty for all day
+  /// every day
+  #[prost(message, repeated, tag = "1")]
+  pub opening_hours: ::prost::alloc::vec::Vec<OpeningHours>,
+  /// periods it can't be booked, e.g. holidays
+  #[prost(message, repeated, tag = "2")]
+  pub blackouts: ::prost::alloc::vec::Vec<Blackout>,
+  /// shortest and longest a reservation can be, empty for no limit
+  #[prost(message, optional, tag = "3")]
+  pub min_duration: ::core::option::Option<::prost_types::Duration>,
+  #[prost(message, optional, tag = "4")]
+  pub max_duration: ::core::option::Option<::prost_types::Duration>,
+  /// how long before its start a reservation has to be made
+  #[prost(message, optional, tag = "5")]
+  pub min_lead_time: ::core::option::Option<::prost_types::Duration>,
+  /// how far ahead of its start a reservation can be made
+  #[prost(message, optional, tag = "6")]
+  pub max_horizon: ::core::option::Option<::prost_types::Duration>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpeningHours {
+  /// ISO weekday, 1 for monday to 7 for sunday
+  #[prost(int32, tag = "1")]
+  pub weekday: i32,
+  /// local times as HH:MM, closing at 00:00 means midnight
+  #[prost(string, tag = "2")]
+  pub opens: ::prost::alloc::string::String,
+  #[prost(string, tag = "3")]
+  pub closes: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Blackout {
+  #[prost(message, optional, tag = "1")]
+  pub start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "2")]
+  pub end: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(string, tag = "3")]
+  pub reason: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReserveRequest {
   #[prost(message, optional, tag = "1")]
   pub reservation: ::core::option::Option<Reservation>,
+  /// join the waitlist of the window instead of failing if it's taken
+  #[prost(bool, tag = "2")]
+  pub join_waitlist: bool,
+  /// if the window is taken, suggest up to this many free windows of the
+  /// same duration in the error details
+  #[prost(uint32, tag = "3")]
+  pub suggest_alternatives: u32,
+  /// also suggest windows on active resources of the same type
+  #[prost(bool, tag = "4")]
+  pub suggest_similar_resources: bool,
+  /// a retry with the same key gets the response of the first request
+  #[prost(string, tag = "5")]
+  pub idempotency_key: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReserveResponse {
+  /// empty if the request joined the waitlist
+  #[prost(message, optional, tag = "1")]
+  pub reservation: ::core::option::Option<Reservation>,
+  #[prost(message, optional, tag = "2")]
+  pub waitlist_entry: ::core::option::Option<WaitlistEntry>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WaitlistEntry {
+  #[prost(int64, tag = "1")]
+  pub id: i64,
+  /// the requested reservation, without id
+  #[prost(message, optional, tag = "2")]
+  pub reservation: ::core::option::Option<Reservation>,
+  #[prost(enumeration = "WaitlistStatus", tag = "3")]
+  pub status: i32,
+  /// the pending reservation the entry was promoted to
+  #[prost(int64, tag = "4")]
+  pub reservation_id: i64,
+  #[prost(message, optional, tag = "5")]
+  pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct LeaveWaitlistRequest {
+  #[prost(int64, tag = "1")]
+  pub id: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeaveWaitlistResponse {
+  #[prost(message, optional, tag = "1")]
+  pub entry: ::core::option::Option<WaitlistEntry>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchRequest {
+  /// reserved together in one transaction, all or nothing
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveSeriesRequest {
+  /// every occurrence is reserved or none of them
   #[prost(message, optional, tag = "1")]
+  pub series: ::core::option::Option<ReservationSeries>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveSeriesResponse {
+  #[prost(int64, tag = "1")]
+  pub series_id: i64,
+  #[prost(message, repeated, tag = "2")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSeriesRequest {
+  #[prost(int64, tag = "1")]
+  pub series_id: i64,
+  /// active occurrences starting at or after this are updated
+  #[prost(message, optional, tag = "2")]
+  pub from: ::core::option::Option<::prost_types::Timestamp>,
+  /// new values for the first updated occurrence, start and end move the
+  /// following occurrences by the same offset
+  #[prost(message, optional, tag = "3")]
   pub reservation: ::core::option::Option<Reservation>,
+  /// fields to update, same as UpdateRequest
+  #[prost(message, optional, tag = "4")]
+  pub update_mask: ::core::option::Option<::prost_types::FieldMask>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSeriesResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSeriesRequest {
+  #[prost(int64, tag = "1")]
+  pub series_id: i64,
+  /// active occurrences starting at or after this are cancelled
+  #[prost(message, optional, tag = "2")]
+  pub from: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(string, tag = "3")]
+  pub operator: ::prost::alloc::string::String,
+  #[prost(string, tag = "4")]
+  pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSeriesResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetSeriesRequest {
+  #[prost(int64, tag = "1")]
+  pub series_id: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSeriesResponse {
+  /// every occurrence order by start
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ConfirmRequest {
   #[prost(int64, tag = "1")]
   pub id: i64,
+  /// a retry with the same key gets the response of the first request
+  #[prost(string, tag = "2")]
+  pub idempotency_key: ::prost::alloc::string::String,
+  /// fail if the reservation isn't at this version anymore, 0 to skip
+  #[prost(int64, tag = "3")]
+  pub expected_version: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ConfirmResponse {
   #[prost(message, optional, tag = "1")]
   pub reservation: ::core::option::Option<Reservation>,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransitionRequest {
+  #[prost(int64, tag = "1")]
+  pub id: i64,
+  /// status to move the reservation to
+  #[prost(enumeration = "ReservationStatus", tag = "2")]
+  pub status: i32,
+  /// who triggered the transition
+  #[prost(string, tag = "3")]
+  pub operator: ::prost::alloc::string::String,
+  /// fail if the reservation isn't at this version anymore, 0 to skip
+  #[prost(int64, tag = "4")]
+  pub expected_version: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransitionResponse {
+  #[prost(message, optional, tag = "1")]
+  pub reservation: ::core::option::Option<Reservation>,
+}
 #[derive(derive_builder::Builder, Clone, PartialEq, ::prost::Message)]
 pub struct ReservationQuery {
   #[prost(string, tag = "1")]
@@ -63,6 +316,11 @@ pub struct ReservationQuery {
   #[prost(bool, tag = "8")]
   #[builder(setter(into), default)]
   pub desc: bool,
+  /// IANA timezone of local times in the results, empty for the ones
+  /// the reservations are made in
+  #[prost(string, tag = "9")]
+  #[builder(setter(into), default)]
+  pub timezone: ::prost::alloc::string::String,
 }
 #[derive(derive_builder::Builder, Clone, PartialEq, ::prost::Message)]
 pub struct ReservationFilter {
@@ -75,6 +333,7 @@ pub struct ReservationFilter {
   #[prost(enumeration = "ReservationStatus", tag = "3")]
   #[builder(setter(into), default)]
   pub status: i32,
+  /// reservations after this id are returned, 0 for the first page
   #[prost(int64, tag = "4")]
   #[builder(setter(into), default)]
   pub cursor: i64,
@@ -88,10 +347,16 @@ pub struct ReservationFilter {
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct FilterPager {
+  /// cursor of the previous page, -1 if there is none. 0 if the previous
+  /// page is the first one, i.e. start from the beginning
   #[prost(int64, tag = "1")]
   pub prev: i64,
+  /// cursor of the next page, -1 if there is none
   #[prost(int64, tag = "2")]
   pub next: i64,
+  /// number of reservations matching the filter. it is counted apart from
+  /// the page, so it can be out of step with it if reservations changed in
+  /// between
   #[prost(int64, tag = "3")]
   pub total: i64,
 }
@@ -108,6 +373,136 @@ pub struct FilterRequest {
   pub filter: ::core::option::Option<ReservationFilter>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AvailabilityQuery {
+  #[prost(string, tag = "1")]
+  pub resource_id: ::prost::alloc::string::String,
+  /// window to search free slots in
+  #[prost(message, optional, tag = "2")]
+  pub start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "3")]
+  pub end: ::core::option::Option<::prost_types::Timestamp>,
+  /// shortest slot to return, empty to return every free slot
+  #[prost(message, optional, tag = "4")]
+  pub min_duration: ::core::option::Option<::prost_types::Duration>,
+  /// align slots to multiples of this from start, empty to not align
+  #[prost(message, optional, tag = "5")]
+  pub granularity: ::core::option::Option<::prost_types::Duration>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AvailableWindow {
+  #[prost(string, tag = "1")]
+  pub resource_id: ::prost::alloc::string::String,
+  #[prost(message, optional, tag = "2")]
+  pub start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "3")]
+  pub end: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// machine readable details of a failed request, encoded in the details of
+/// the grpc status
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ErrorDetails {
+  /// free windows nearest to a taken one, nearest first
+  #[prost(message, repeated, tag = "1")]
+  pub alternatives: ::prost::alloc::vec::Vec<AvailableWindow>,
+  #[prost(message, optional, tag = "2")]
+  pub info: ::core::option::Option<ErrorInfo>,
+  /// invalid fields of the request
+  #[prost(message, repeated, tag = "3")]
+  pub field_violations: ::prost::alloc::vec::Vec<FieldViolation>,
+  /// existing reservations the request clashes with
+  #[prost(message, repeated, tag = "4")]
+  pub conflicts: ::prost::alloc::vec::Vec<ReservationConflictDetail>,
+  /// the quota that would be exceeded
+  #[prost(message, optional, tag = "5")]
+  pub quota: ::core::option::Option<QuotaUsage>,
+  /// booking rules of the resource the reservation breaks
+  #[prost(message, repeated, tag = "6")]
+  pub rule_violations: ::prost::alloc::vec::Vec<RuleViolationDetail>,
+  /// why each failed item of a batch failed
+  #[prost(message, repeated, tag = "7")]
+  pub batch_errors: ::prost::alloc::vec::Vec<BatchItemError>,
+}
+/// a booking rule a reservation breaks
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RuleViolationDetail {
+  #[prost(enumeration = "RuleKind", tag = "1")]
+  pub kind: i32,
+  /// the blackout period overlapped, for RULE_KIND_BLACKOUT
+  #[prost(message, optional, tag = "2")]
+  pub start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "3")]
+  pub end: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(string, tag = "4")]
+  pub reason: ::prost::alloc::string::String,
+  /// the minimum or maximum broken, for the duration, lead time and horizon
+  /// rules
+  #[prost(message, optional, tag = "5")]
+  pub limit: ::core::option::Option<::prost_types::Duration>,
+}
+/// a failed item of a batch
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchItemError {
+  /// position of the item in the batch
+  #[prost(uint32, tag = "1")]
+  pub index: u32,
+  #[prost(message, optional, tag = "2")]
+  pub details: ::core::option::Option<ErrorDetails>,
+}
+/// why a request failed, like google.rpc.ErrorInfo
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ErrorInfo {
+  /// the kind of error in UPPER_SNAKE_CASE, e.g. CONFLICT_RESERVATION
+  #[prost(string, tag = "1")]
+  pub reason: ::prost::alloc::string::String,
+  #[prost(string, tag = "2")]
+  pub domain: ::prost::alloc::string::String,
+  /// more about the error, depending on the reason
+  #[prost(map = "string, string", tag = "3")]
+  pub metadata: ::std::collections::HashMap<
+    ::prost::alloc::string::String,
+    ::prost::alloc::string::String,
+  >,
+}
+/// like google.rpc.BadRequest.FieldViolation
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FieldViolation {
+  #[prost(string, tag = "1")]
+  pub field: ::prost::alloc::string::String,
+  #[prost(string, tag = "2")]
+  pub description: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationConflictDetail {
+  #[prost(string, tag = "1")]
+  pub resource_id: ::prost::alloc::string::String,
+  /// window of the requested reservation
+  #[prost(message, optional, tag = "2")]
+  pub start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "3")]
+  pub end: ::core::option::Option<::prost_types::Timestamp>,
+  /// window of the existing reservation it clashes with
+  #[prost(message, optional, tag = "4")]
+  pub existing_start: ::core::option::Option<::prost_types::Timestamp>,
+  #[prost(message, optional, tag = "5")]
+  pub existing_end: ::core::option::Option<::prost_types::Timestamp>,
+  /// only the buffers overlap, the windows then include the buffers
+  #[prost(bool, tag = "6")]
+  pub buffer_only: bool,
+  /// the existing reservations clashing with the requested one
+  #[prost(message, repeated, tag = "7")]
+  pub existing: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindAvailabilityRequest {
+  #[prost(message, optional, tag = "1")]
+  pub query: ::core::option::Option<AvailabilityQuery>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindAvailabilityResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub windows: ::prost::alloc::vec::Vec<AvailableWindow>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct QueryRequest {
   #[prost(message, optional, tag = "1")]
   pub query: ::core::option::Option<ReservationQuery>,
@@ -126,24 +521,197 @@ pub struct GetResponse {
 pub struct UpdateRequest {
   #[prost(int64, tag = "1")]
   pub id: i64,
+  /// only used when update_mask is empty, kept for older clients
   #[prost(string, tag = "2")]
   pub note: ::prost::alloc::string::String,
+  /// new values of the fields listed in update_mask
+  #[prost(message, optional, tag = "3")]
+  pub reservation: ::core::option::Option<Reservation>,
+  /// fields to update: note, start, end, resource_id, user_id, quantity
+  #[prost(message, optional, tag = "4")]
+  pub update_mask: ::core::option::Option<::prost_types::FieldMask>,
+  /// a retry with the same key gets the response of the first request
+  #[prost(string, tag = "5")]
+  pub idempotency_key: ::prost::alloc::string::String,
+  /// fail if the reservation isn't at this version anymore, 0 to skip
+  #[prost(int64, tag = "6")]
+  pub expected_version: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdateResponse {
   #[prost(message, optional, tag = "1")]
   pub reservation: ::core::option::Option<Reservation>,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CancelRequest {
   #[prost(int64, tag = "1")]
   pub id: i64,
+  /// who cancelled the reservation
+  #[prost(string, tag = "2")]
+  pub operator: ::prost::alloc::string::String,
+  /// why the reservation is cancelled
+  #[prost(string, tag = "3")]
+  pub reason: ::prost::alloc::string::String,
+  /// a retry with the same key gets the response of the first request
+  #[prost(string, tag = "4")]
+  pub idempotency_key: ::prost::alloc::string::String,
+  /// fail if the reservation isn't at this version anymore, 0 to skip
+  #[prost(int64, tag = "5")]
+  pub expected_version: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CancelResponse {
   #[prost(message, optional, tag = "1")]
   pub reservation: ::core::option::Option<Reservation>,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreRequest {
+  #[prost(int64, tag = "1")]
+  pub id: i64,
+  /// who restored the reservation
+  #[prost(string, tag = "2")]
+  pub operator: ::prost::alloc::string::String,
+  /// fail if the reservation isn't at this version anymore, 0 to skip
+  #[prost(int64, tag = "3")]
+  pub expected_version: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreResponse {
+  #[prost(message, optional, tag = "1")]
+  pub reservation: ::core::option::Option<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateResourceRequest {
+  /// new resources are always active
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateResourceResponse {
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetResourceRequest {
+  #[prost(string, tag = "1")]
+  pub id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetResourceResponse {
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateResourceRequest {
+  /// replaces every field of the resource with the same id
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateResourceResponse {
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteResourceRequest {
+  #[prost(string, tag = "1")]
+  pub id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteResourceResponse {
+  #[prost(message, optional, tag = "1")]
+  pub resource: ::core::option::Option<Resource>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListResourcesRequest {
+  /// include disabled resources
+  #[prost(bool, tag = "1")]
+  pub include_inactive: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListResourcesResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub resources: ::prost::alloc::vec::Vec<Resource>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportIcsRequest {
+  #[prost(message, optional, tag = "1")]
+  pub query: ::core::option::Option<ReservationQuery>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportIcsResponse {
+  /// iCalendar file with a VEVENT per reservation
+  #[prost(string, tag = "1")]
+  pub ics: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportIcsRequest {
+  /// iCalendar file, cancelled VEVENTs are skipped
+  #[prost(string, tag = "1")]
+  pub ics: ::prost::alloc::string::String,
+  /// owner of the imported reservations, empty to use X-RSVP-USER-ID
+  #[prost(string, tag = "2")]
+  pub user_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportIcsResponse {
+  #[prost(message, repeated, tag = "1")]
+  pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QuotaUsage {
+  #[prost(enumeration = "QuotaKind", tag = "1")]
+  pub kind: i32,
+  /// resource type of weekly hours, empty for the other quotas
+  #[prost(string, tag = "2")]
+  pub resource_type: ::prost::alloc::string::String,
+  /// number of reservations, or seconds for weekly hours
+  #[prost(int64, tag = "3")]
+  pub limit: i64,
+  #[prost(int64, tag = "4")]
+  pub used: i64,
+  #[prost(int64, tag = "5")]
+  pub remaining: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQuotaRequest {
+  #[prost(string, tag = "1")]
+  pub user_id: ::prost::alloc::string::String,
+  /// moment concurrent reservations and the week are counted at, empty
+  /// for now
+  #[prost(message, optional, tag = "2")]
+  pub at: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQuotaResponse {
+  /// configured quotas only
+  #[prost(message, repeated, tag = "1")]
+  pub quotas: ::prost::alloc::vec::Vec<QuotaUsage>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListenRequest {
+  /// resume after this change id, 0 to receive only new changes
+  #[prost(int64, tag = "1")]
+  pub last_change_id: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListenResponse {
+  /// id of the change in reservations_changes. changes are sent in the
+  /// order they were committed, which ids don't always follow
+  #[prost(int64, tag = "1")]
+  pub id: i64,
+  #[prost(enumeration = "ReservationUpdateType", tag = "2")]
+  pub op: i32,
+  /// reservation before the change, empty for create
+  #[prost(message, optional, tag = "3")]
+  pub old: ::core::option::Option<Reservation>,
+  /// reservation after the change, empty for delete
+  #[prost(message, optional, tag = "4")]
+  pub new: ::core::option::Option<Reservation>,
+  /// who triggered the change, empty if unknown
+  #[prost(string, tag = "5")]
+  pub operator: ::prost::alloc::string::String,
+}
 #[derive(
   sqlx::Type,
   Clone,
@@ -162,6 +730,9 @@ pub enum ReservationStatus {
   Pending = 1,
   Confirmed = 2,
   Blocked = 3,
+  Cancelled = 4,
+  Expired = 5,
+  Completed = 6,
 }
 impl ReservationStatus {
   /// String value of the enum field names used in the ProtoBuf definition.
@@ -174,6 +745,9 @@ impl ReservationStatus {
       Self::Pending => "RESERVATION_STATUS_PENDING",
       Self::Confirmed => "RESERVATION_STATUS_CONFIRMED",
       Self::Blocked => "RESERVATION_STATUS_BLOCKED",
+      Self::Cancelled => "RESERVATION_STATUS_CANCELLED",
+      Self::Expired => "RESERVATION_STATUS_EXPIRED",
+      Self::Completed => "RESERVATION_STATUS_COMPLETED",
     }
   }
   /// Creates an enum from field names used in the ProtoBuf definition.
@@ -183,26 +757,182 @@ impl ReservationStatus {
       "RESERVATION_STATUS_PENDING" => Some(Self::Pending),
       "RESERVATION_STATUS_CONFIRMED" => Some(Self::Confirmed),
       "RESERVATION_STATUS_BLOCKED" => Some(Self::Blocked),
+      "RESERVATION_STATUS_CANCELLED" => Some(Self::Cancelled),
+      "RESERVATION_STATUS_EXPIRED" => Some(Self::Expired),
+      "RESERVATION_STATUS_COMPLETED" => Some(Self::Completed),
       _ => None,
     }
   }
 }
-/// Generated client implementations.
-pub mod reservation_service_client {
-  #![allow(
-    unused_variables,
-    dead_code,
-    missing_docs,
-    clippy::wildcard_imports,
-    clippy::let_unit_value
-  )]
-  use tonic::codegen::http::Uri;
-  use tonic::codegen::*;
-  #[derive(Debug, Clone)]
-  pub struct ReservationServiceClient<T> {
-    inner: tonic::client::Grpc<T>,
-  }
-  impl ReservationServiceClient<tonic::transport::Channel> {
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum ReservationUpdateType {
+  Unknown = 0,
+  Create = 1,
+  Update = 2,
+  Delete = 3,
+}
+impl ReservationUpdateType {
+  /// String value of the enum field names used in the ProtoBuf definition.
+  ///
+  /// The values are not transformed in any way and thus are considered stable
+  /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+  pub fn as_str_name(&self) -> &'static str {
+    match self {
+      Self::Unknown => "RESERVATION_UPDATE_TYPE_UNKNOWN",
+      Self::Create => "RESERVATION_UPDATE_TYPE_CREATE",
+      Self::Update => "RESERVATION_UPDATE_TYPE_UPDATE",
+      Self::Delete => "RESERVATION_UPDATE_TYPE_DELETE",
+    }
+  }
+  /// Creates an enum from field names used in the ProtoBuf definition.
+  pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+    match value {
+      "RESERVATION_UPDATE_TYPE_UNKNOWN" => Some(Self::Unknown),
+      "RESERVATION_UPDATE_TYPE_CREATE" => Some(Self::Create),
+      "RESERVATION_UPDATE_TYPE_UPDATE" => Some(Self::Update),
+      "RESERVATION_UPDATE_TYPE_DELETE" => Some(Self::Delete),
+      _ => None,
+    }
+  }
+}
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum QuotaKind {
+  Unknown = 0,
+  /// pending, confirmed or blocked reservations of a user
+  ActiveReservations = 1,
+  /// reservations of a user overlapping at the same time
+  ConcurrentReservations = 2,
+  /// time a user reserves resources of a type in a week
+  WeeklyHours = 3,
+}
+impl QuotaKind {
+  /// String value of the enum field names used in the ProtoBuf definition.
+  ///
+  /// The values are not transformed in any way and thus are considered stable
+  /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+  pub fn as_str_name(&self) -> &'static str {
+    match self {
+      Self::Unknown => "QUOTA_KIND_UNKNOWN",
+      Self::ActiveReservations => "QUOTA_KIND_ACTIVE_RESERVATIONS",
+      Self::ConcurrentReservations => "QUOTA_KIND_CONCURRENT_RESERVATIONS",
+      Self::WeeklyHours => "QUOTA_KIND_WEEKLY_HOURS",
+    }
+  }
+  /// Creates an enum from field names used in the ProtoBuf definition.
+  pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+    match value {
+      "QUOTA_KIND_UNKNOWN" => Some(Self::Unknown),
+      "QUOTA_KIND_ACTIVE_RESERVATIONS" => Some(Self::ActiveReservations),
+      "QUOTA_KIND_CONCURRENT_RESERVATIONS" => {
+        Some(Self::ConcurrentReservations)
+      }
+      "QUOTA_KIND_WEEKLY_HOURS" => Some(Self::WeeklyHours),
+      _ => None,
+    }
+  }
+}
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum RuleKind {
+  Unknown = 0,
+  OutsideOpeningHours = 1,
+  Blackout = 2,
+  TooShort = 3,
+  TooLong = 4,
+  TooSoon = 5,
+  TooFarAhead = 6,
+}
+impl RuleKind {
+  /// String value of the enum field names used in the ProtoBuf definition.
+  ///
+  /// The values are not transformed in any way and thus are considered stable
+  /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+  pub fn as_str_name(&self) -> &'static str {
+    match self {
+      Self::Unknown => "RULE_KIND_UNKNOWN",
+      Self::OutsideOpeningHours => "RULE_KIND_OUTSIDE_OPENING_HOURS",
+      Self::Blackout => "RULE_KIND_BLACKOUT",
+      Self::TooShort => "RULE_KIND_TOO_SHORT",
+      Self::TooLong => "RULE_KIND_TOO_LONG",
+      Self::TooSoon => "RULE_KIND_TOO_SOON",
+      Self::TooFarAhead => "RULE_KIND_TOO_FAR_AHEAD",
+    }
+  }
+  /// Creates an enum from field names used in the ProtoBuf definition.
+  pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+    match value {
+      "RULE_KIND_UNKNOWN" => Some(Self::Unknown),
+      "RULE_KIND_OUTSIDE_OPENING_HOURS" => Some(Self::OutsideOpeningHours),
+      "RULE_KIND_BLACKOUT" => Some(Self::Blackout),
+      "RULE_KIND_TOO_SHORT" => Some(Self::TooShort),
+      "RULE_KIND_TOO_LONG" => Some(Self::TooLong),
+      "RULE_KIND_TOO_SOON" => Some(Self::TooSoon),
+      "RULE_KIND_TOO_FAR_AHEAD" => Some(Self::TooFarAhead),
+      _ => None,
+    }
+  }
+}
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum WaitlistStatus {
+  Unknown = 0,
+  /// waiting for the window to free up
+  Waiting = 1,
+  /// reserved as pending, see reservation_id
+  Promoted = 2,
+  /// left the waitlist before it was promoted
+  Withdrawn = 3,
+}
+impl WaitlistStatus {
+  /// String value of the enum field names used in the ProtoBuf definition.
+  ///
+  /// The values are not transformed in any way and thus are considered stable
+  /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+  pub fn as_str_name(&self) -> &'static str {
+    match self {
+      Self::Unknown => "WAITLIST_STATUS_UNKNOWN",
+      Self::Waiting => "WAITLIST_STATUS_WAITING",
+      Self::Promoted => "WAITLIST_STATUS_PROMOTED",
+      Self::Withdrawn => "WAITLIST_STATUS_WITHDRAWN",
+    }
+  }
+  /// Creates an enum from field names used in the ProtoBuf definition.
+  pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+    match value {
+      "WAITLIST_STATUS_UNKNOWN" => Some(Self::Unknown),
+      "WAITLIST_STATUS_WAITING" => Some(Self::Waiting),
+      "WAITLIST_STATUS_PROMOTED" => Some(Self::Promoted),
+      "WAITLIST_STATUS_WITHDRAWN" => Some(Self::Withdrawn),
+      _ => None,
+    }
+  }
+}
+/// Generated client implementations.
+pub mod reservation_service_client {
+  #![allow(
+    unused_variables,
+    dead_code,
+    missing_docs,
+    clippy::wildcard_imports,
+    clippy::let_unit_value
+  )]
+  use tonic::codegen::http::Uri;
+  use tonic::codegen::*;
+  #[derive(Debug, Clone)]
+  pub struct ReservationServiceClient<T> {
+    inner: tonic::client::Grpc<T>,
+  }
+  impl ReservationServiceClient<tonic::transport::Channel> {
     /// Attempt to create a new client by connecting to a given endpoint.
     pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
     where
@@ -298,6 +1028,115 @@ pub mod reservation_service_client {
         .insert(GrpcMethod::new("reservation.ReservationService", "reserve"));
       self.inner.unary(req, path, codec).await
     }
+    /// make several reservations at once, fails if any of them conflicts
+    pub async fn reserve_batch(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ReserveBatchRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ReserveBatchResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/reserve_batch",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "reserve_batch",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// reserve every occurrence of a recurring reservation
+    pub async fn reserve_series(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ReserveSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ReserveSeriesResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/reserve_series",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "reserve_series",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// update an occurrence and the following ones
+    pub async fn update_series(
+      &mut self,
+      request: impl tonic::IntoRequest<super::UpdateSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::UpdateSeriesResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/update_series",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "update_series",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// cancel an occurrence and the following ones
+    pub async fn cancel_series(
+      &mut self,
+      request: impl tonic::IntoRequest<super::CancelSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::CancelSeriesResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/cancel_series",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "cancel_series",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    pub async fn get_series(
+      &mut self,
+      request: impl tonic::IntoRequest<super::GetSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetSeriesResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/get_series",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "get_series",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
     /// update status to CONFIRMED
     pub async fn confirm(
       &mut self,
@@ -319,7 +1158,29 @@ pub mod reservation_service_client {
         .insert(GrpcMethod::new("reservation.ReservationService", "confirm"));
       self.inner.unary(req, path, codec).await
     }
-    /// update only note
+    /// move reservation to another status
+    pub async fn transition(
+      &mut self,
+      request: impl tonic::IntoRequest<super::TransitionRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::TransitionResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/transition",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "transition",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// update note, time window, resource or user by update_mask
     pub async fn update(
       &mut self,
       request: impl tonic::IntoRequest<super::UpdateRequest>,
@@ -361,6 +1222,27 @@ pub mod reservation_service_client {
         .insert(GrpcMethod::new("reservation.ReservationService", "cancel"));
       self.inner.unary(req, path, codec).await
     }
+    /// restore a cancelled reservation if its time slot is still free
+    pub async fn restore(
+      &mut self,
+      request: impl tonic::IntoRequest<super::RestoreRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::RestoreResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/restore",
+      );
+      let mut req = request.into_request();
+      req
+        .extensions_mut()
+        .insert(GrpcMethod::new("reservation.ReservationService", "restore"));
+      self.inner.unary(req, path, codec).await
+    }
     /// get reservation by id
     pub async fn get(
       &mut self,
@@ -422,54 +1304,347 @@ pub mod reservation_service_client {
         .insert(GrpcMethod::new("reservation.ReservationService", "filter"));
       self.inner.unary(req, path, codec).await
     }
-  }
-}
-/// Generated server implementations.
-pub mod reservation_service_server {
-  #![allow(
-    unused_variables,
-    dead_code,
-    missing_docs,
-    clippy::wildcard_imports,
-    clippy::let_unit_value
-  )]
-  use tonic::codegen::*;
-  /// Generated trait containing gRPC methods that should be implemented for use with ReservationServiceServer.
-  #[async_trait]
-  pub trait ReservationService:
-    std::marker::Send + std::marker::Sync + 'static
-  {
-    async fn reserve(
-      &self,
-      request: tonic::Request<super::ReserveRequest>,
-    ) -> std::result::Result<
-      tonic::Response<super::ReserveResponse>,
-      tonic::Status,
-    >;
-    /// update status to CONFIRMED
-    async fn confirm(
-      &self,
-      request: tonic::Request<super::ConfirmRequest>,
+    /// free slots of a resource not taken by any active reservation
+    pub async fn find_availability(
+      &mut self,
+      request: impl tonic::IntoRequest<super::FindAvailabilityRequest>,
     ) -> std::result::Result<
-      tonic::Response<super::ConfirmResponse>,
+      tonic::Response<super::FindAvailabilityResponse>,
       tonic::Status,
-    >;
-    /// update only note
-    async fn update(
-      &self,
-      request: tonic::Request<super::UpdateRequest>,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/find_availability",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "find_availability",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// listen to reservation changes
+    pub async fn listen(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ListenRequest>,
     ) -> std::result::Result<
-      tonic::Response<super::UpdateResponse>,
+      tonic::Response<tonic::codec::Streaming<super::ListenResponse>>,
       tonic::Status,
-    >;
-    /// cancel reservation
-    async fn cancel(
-      &self,
-      request: tonic::Request<super::CancelRequest>,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/listen",
+      );
+      let mut req = request.into_request();
+      req
+        .extensions_mut()
+        .insert(GrpcMethod::new("reservation.ReservationService", "listen"));
+      self.inner.server_streaming(req, path, codec).await
+    }
+    /// export reservations as an iCalendar file
+    pub async fn export_ics(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ExportIcsRequest>,
     ) -> std::result::Result<
-      tonic::Response<super::CancelResponse>,
+      tonic::Response<super::ExportIcsResponse>,
       tonic::Status,
-    >;
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/export_ics",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "export_ics",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// reserve every event of an iCalendar file, all or nothing
+    pub async fn import_ics(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ImportIcsRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ImportIcsResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/import_ics",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "import_ics",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// what is left of the booking quotas of a user
+    pub async fn get_quota(
+      &mut self,
+      request: impl tonic::IntoRequest<super::GetQuotaRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetQuotaResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/get_quota",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "get_quota",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// leave the waitlist joined by reserve
+    pub async fn leave_waitlist(
+      &mut self,
+      request: impl tonic::IntoRequest<super::LeaveWaitlistRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::LeaveWaitlistResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/leave_waitlist",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "leave_waitlist",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// add a resource to the catalogue
+    pub async fn create_resource(
+      &mut self,
+      request: impl tonic::IntoRequest<super::CreateResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::CreateResourceResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/create_resource",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "create_resource",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    pub async fn get_resource(
+      &mut self,
+      request: impl tonic::IntoRequest<super::GetResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetResourceResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/get_resource",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "get_resource",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    pub async fn update_resource(
+      &mut self,
+      request: impl tonic::IntoRequest<super::UpdateResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::UpdateResourceResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/update_resource",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "update_resource",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    /// delete a resource, resources with reservations can only be disabled
+    pub async fn delete_resource(
+      &mut self,
+      request: impl tonic::IntoRequest<super::DeleteResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::DeleteResourceResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/delete_resource",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "delete_resource",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+    pub async fn list_resources(
+      &mut self,
+      request: impl tonic::IntoRequest<super::ListResourcesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ListResourcesResponse>,
+      tonic::Status,
+    > {
+      self.inner.ready().await.map_err(|e| {
+        tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+      })?;
+      let codec = tonic::codec::ProstCodec::default();
+      let path = http::uri::PathAndQuery::from_static(
+        "/reservation.ReservationService/list_resources",
+      );
+      let mut req = request.into_request();
+      req.extensions_mut().insert(GrpcMethod::new(
+        "reservation.ReservationService",
+        "list_resources",
+      ));
+      self.inner.unary(req, path, codec).await
+    }
+  }
+}
+/// Generated server implementations.
+pub mod reservation_service_server {
+  #![allow(
+    unused_variables,
+    dead_code,
+    missing_docs,
+    clippy::wildcard_imports,
+    clippy::let_unit_value
+  )]
+  use tonic::codegen::*;
+  /// Generated trait containing gRPC methods that should be implemented for use with ReservationServiceServer.
+  #[async_trait]
+  pub trait ReservationService:
+    std::marker::Send + std::marker::Sync + 'static
+  {
+    async fn reserve(
+      &self,
+      request: tonic::Request<super::ReserveRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ReserveResponse>,
+      tonic::Status,
+    >;
+    /// make several reservations at once, fails if any of them conflicts
+    async fn reserve_batch(
+      &self,
+      request: tonic::Request<super::ReserveBatchRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ReserveBatchResponse>,
+      tonic::Status,
+    >;
+    /// reserve every occurrence of a recurring reservation
+    async fn reserve_series(
+      &self,
+      request: tonic::Request<super::ReserveSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ReserveSeriesResponse>,
+      tonic::Status,
+    >;
+    /// update an occurrence and the following ones
+    async fn update_series(
+      &self,
+      request: tonic::Request<super::UpdateSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::UpdateSeriesResponse>,
+      tonic::Status,
+    >;
+    /// cancel an occurrence and the following ones
+    async fn cancel_series(
+      &self,
+      request: tonic::Request<super::CancelSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::CancelSeriesResponse>,
+      tonic::Status,
+    >;
+    async fn get_series(
+      &self,
+      request: tonic::Request<super::GetSeriesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetSeriesResponse>,
+      tonic::Status,
+    >;
+    /// update status to CONFIRMED
+    async fn confirm(
+      &self,
+      request: tonic::Request<super::ConfirmRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ConfirmResponse>,
+      tonic::Status,
+    >;
+    /// move reservation to another status
+    async fn transition(
+      &self,
+      request: tonic::Request<super::TransitionRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::TransitionResponse>,
+      tonic::Status,
+    >;
+    /// update note, time window, resource or user by update_mask
+    async fn update(
+      &self,
+      request: tonic::Request<super::UpdateRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::UpdateResponse>,
+      tonic::Status,
+    >;
+    /// cancel reservation
+    async fn cancel(
+      &self,
+      request: tonic::Request<super::CancelRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::CancelResponse>,
+      tonic::Status,
+    >;
+    /// restore a cancelled reservation if its time slot is still free
+    async fn restore(
+      &self,
+      request: tonic::Request<super::RestoreRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::RestoreResponse>,
+      tonic::Status,
+    >;
     /// get reservation by id
     async fn get(
       &self,
@@ -493,6 +1668,93 @@ pub mod reservation_service_server {
       tonic::Response<super::FilterResponse>,
       tonic::Status,
     >;
+    /// free slots of a resource not taken by any active reservation
+    async fn find_availability(
+      &self,
+      request: tonic::Request<super::FindAvailabilityRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::FindAvailabilityResponse>,
+      tonic::Status,
+    >;
+    /// Server streaming response type for the listen method.
+    type listenStream: tonic::codegen::tokio_stream::Stream<
+        Item = std::result::Result<super::ListenResponse, tonic::Status>,
+      > + std::marker::Send
+      + 'static;
+    /// listen to reservation changes
+    async fn listen(
+      &self,
+      request: tonic::Request<super::ListenRequest>,
+    ) -> std::result::Result<tonic::Response<Self::listenStream>, tonic::Status>;
+    /// export reservations as an iCalendar file
+    async fn export_ics(
+      &self,
+      request: tonic::Request<super::ExportIcsRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ExportIcsResponse>,
+      tonic::Status,
+    >;
+    /// reserve every event of an iCalendar file, all or nothing
+    async fn import_ics(
+      &self,
+      request: tonic::Request<super::ImportIcsRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ImportIcsResponse>,
+      tonic::Status,
+    >;
+    /// what is left of the booking quotas of a user
+    async fn get_quota(
+      &self,
+      request: tonic::Request<super::GetQuotaRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetQuotaResponse>,
+      tonic::Status,
+    >;
+    /// leave the waitlist joined by reserve
+    async fn leave_waitlist(
+      &self,
+      request: tonic::Request<super::LeaveWaitlistRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::LeaveWaitlistResponse>,
+      tonic::Status,
+    >;
+    /// add a resource to the catalogue
+    async fn create_resource(
+      &self,
+      request: tonic::Request<super::CreateResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::CreateResourceResponse>,
+      tonic::Status,
+    >;
+    async fn get_resource(
+      &self,
+      request: tonic::Request<super::GetResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::GetResourceResponse>,
+      tonic::Status,
+    >;
+    async fn update_resource(
+      &self,
+      request: tonic::Request<super::UpdateResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::UpdateResourceResponse>,
+      tonic::Status,
+    >;
+    /// delete a resource, resources with reservations can only be disabled
+    async fn delete_resource(
+      &self,
+      request: tonic::Request<super::DeleteResourceRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::DeleteResourceResponse>,
+      tonic::Status,
+    >;
+    async fn list_resources(
+      &self,
+      request: tonic::Request<super::ListResourcesRequest>,
+    ) -> std::result::Result<
+      tonic::Response<super::ListResourcesResponse>,
+      tonic::Status,
+    >;
   }
   #[derive(Debug)]
   pub struct ReservationServiceServer<T> {
@@ -573,21 +1835,577 @@ pub mod reservation_service_server {
       match req.uri().path() {
         "/reservation.ReservationService/reserve" => {
           #[allow(non_camel_case_types)]
-          struct reserveSvc<T: ReservationService>(pub Arc<T>);
+          struct reserveSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ReserveRequest>
+            for reserveSvc<T>
+          {
+            type Response = super::ReserveResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ReserveRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::reserve(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = reserveSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/reserve_batch" => {
+          #[allow(non_camel_case_types)]
+          struct reserve_batchSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ReserveBatchRequest>
+            for reserve_batchSvc<T>
+          {
+            type Response = super::ReserveBatchResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ReserveBatchRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::reserve_batch(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = reserve_batchSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/reserve_series" => {
+          #[allow(non_camel_case_types)]
+          struct reserve_seriesSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ReserveSeriesRequest>
+            for reserve_seriesSvc<T>
+          {
+            type Response = super::ReserveSeriesResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ReserveSeriesRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::reserve_series(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = reserve_seriesSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/update_series" => {
+          #[allow(non_camel_case_types)]
+          struct update_seriesSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::UpdateSeriesRequest>
+            for update_seriesSvc<T>
+          {
+            type Response = super::UpdateSeriesResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::UpdateSeriesRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::update_series(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = update_seriesSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/cancel_series" => {
+          #[allow(non_camel_case_types)]
+          struct cancel_seriesSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::CancelSeriesRequest>
+            for cancel_seriesSvc<T>
+          {
+            type Response = super::CancelSeriesResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::CancelSeriesRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::cancel_series(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = cancel_seriesSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/get_series" => {
+          #[allow(non_camel_case_types)]
+          struct get_seriesSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::GetSeriesRequest>
+            for get_seriesSvc<T>
+          {
+            type Response = super::GetSeriesResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::GetSeriesRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::get_series(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = get_seriesSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/confirm" => {
+          #[allow(non_camel_case_types)]
+          struct confirmSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ConfirmRequest>
+            for confirmSvc<T>
+          {
+            type Response = super::ConfirmResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ConfirmRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::confirm(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = confirmSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/transition" => {
+          #[allow(non_camel_case_types)]
+          struct transitionSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::TransitionRequest>
+            for transitionSvc<T>
+          {
+            type Response = super::TransitionResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::TransitionRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::transition(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = transitionSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/update" => {
+          #[allow(non_camel_case_types)]
+          struct updateSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::UpdateRequest> for updateSvc<T>
+          {
+            type Response = super::UpdateResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::UpdateRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::update(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = updateSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/cancel" => {
+          #[allow(non_camel_case_types)]
+          struct cancelSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::CancelRequest> for cancelSvc<T>
+          {
+            type Response = super::CancelResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::CancelRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::cancel(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = cancelSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/restore" => {
+          #[allow(non_camel_case_types)]
+          struct restoreSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::RestoreRequest>
+            for restoreSvc<T>
+          {
+            type Response = super::RestoreResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::RestoreRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::restore(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = restoreSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/get" => {
+          #[allow(non_camel_case_types)]
+          struct getSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::GetRequest> for getSvc<T>
+          {
+            type Response = super::GetResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::GetRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::get(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = getSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/query" => {
+          #[allow(non_camel_case_types)]
+          struct querySvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::ServerStreamingService<super::QueryRequest>
+            for querySvc<T>
+          {
+            type Response = super::Reservation;
+            type ResponseStream = T::queryStream;
+            type Future =
+              BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::QueryRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::query(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = querySvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.server_streaming(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/filter" => {
+          #[allow(non_camel_case_types)]
+          struct filterSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::ReserveRequest>
-            for reserveSvc<T>
+            tonic::server::UnaryService<super::FilterRequest> for filterSvc<T>
           {
-            type Response = super::ReserveResponse;
+            type Response = super::FilterResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::ReserveRequest>,
+              request: tonic::Request<super::FilterRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::reserve(&inner, request).await
+                <T as ReservationService>::filter(&inner, request).await
               };
               Box::pin(fut)
             }
@@ -598,7 +2416,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = reserveSvc(inner);
+            let method = filterSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -614,23 +2432,24 @@ pub mod reservation_service_server {
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/confirm" => {
+        "/reservation.ReservationService/find_availability" => {
           #[allow(non_camel_case_types)]
-          struct confirmSvc<T: ReservationService>(pub Arc<T>);
+          struct find_availabilitySvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::ConfirmRequest>
-            for confirmSvc<T>
+            tonic::server::UnaryService<super::FindAvailabilityRequest>
+            for find_availabilitySvc<T>
           {
-            type Response = super::ConfirmResponse;
+            type Response = super::FindAvailabilityResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::ConfirmRequest>,
+              request: tonic::Request<super::FindAvailabilityRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::confirm(&inner, request).await
+                <T as ReservationService>::find_availability(&inner, request)
+                  .await
               };
               Box::pin(fut)
             }
@@ -641,7 +2460,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = confirmSvc(inner);
+            let method = find_availabilitySvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -657,22 +2476,67 @@ pub mod reservation_service_server {
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/update" => {
+        "/reservation.ReservationService/listen" => {
           #[allow(non_camel_case_types)]
-          struct updateSvc<T: ReservationService>(pub Arc<T>);
+          struct listenSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::UpdateRequest> for updateSvc<T>
+            tonic::server::ServerStreamingService<super::ListenRequest>
+            for listenSvc<T>
           {
-            type Response = super::UpdateResponse;
+            type Response = super::ListenResponse;
+            type ResponseStream = T::listenStream;
+            type Future =
+              BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ListenRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::listen(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = listenSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.server_streaming(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/export_ics" => {
+          #[allow(non_camel_case_types)]
+          struct export_icsSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ExportIcsRequest>
+            for export_icsSvc<T>
+          {
+            type Response = super::ExportIcsResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::UpdateRequest>,
+              request: tonic::Request<super::ExportIcsRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::update(&inner, request).await
+                <T as ReservationService>::export_ics(&inner, request).await
               };
               Box::pin(fut)
             }
@@ -683,7 +2547,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = updateSvc(inner);
+            let method = export_icsSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -699,22 +2563,23 @@ pub mod reservation_service_server {
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/cancel" => {
+        "/reservation.ReservationService/import_ics" => {
           #[allow(non_camel_case_types)]
-          struct cancelSvc<T: ReservationService>(pub Arc<T>);
+          struct import_icsSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::CancelRequest> for cancelSvc<T>
+            tonic::server::UnaryService<super::ImportIcsRequest>
+            for import_icsSvc<T>
           {
-            type Response = super::CancelResponse;
+            type Response = super::ImportIcsResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::CancelRequest>,
+              request: tonic::Request<super::ImportIcsRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::cancel(&inner, request).await
+                <T as ReservationService>::import_ics(&inner, request).await
               };
               Box::pin(fut)
             }
@@ -725,7 +2590,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = cancelSvc(inner);
+            let method = import_icsSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -741,22 +2606,23 @@ pub mod reservation_service_server {
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/get" => {
+        "/reservation.ReservationService/get_quota" => {
           #[allow(non_camel_case_types)]
-          struct getSvc<T: ReservationService>(pub Arc<T>);
+          struct get_quotaSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::GetRequest> for getSvc<T>
+            tonic::server::UnaryService<super::GetQuotaRequest>
+            for get_quotaSvc<T>
           {
-            type Response = super::GetResponse;
+            type Response = super::GetQuotaResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::GetRequest>,
+              request: tonic::Request<super::GetQuotaRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::get(&inner, request).await
+                <T as ReservationService>::get_quota(&inner, request).await
               };
               Box::pin(fut)
             }
@@ -767,7 +2633,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = getSvc(inner);
+            let method = get_quotaSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -783,24 +2649,23 @@ pub mod reservation_service_server {
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/query" => {
+        "/reservation.ReservationService/leave_waitlist" => {
           #[allow(non_camel_case_types)]
-          struct querySvc<T: ReservationService>(pub Arc<T>);
+          struct leave_waitlistSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::ServerStreamingService<super::QueryRequest>
-            for querySvc<T>
+            tonic::server::UnaryService<super::LeaveWaitlistRequest>
+            for leave_waitlistSvc<T>
           {
-            type Response = super::Reservation;
-            type ResponseStream = T::queryStream;
+            type Response = super::LeaveWaitlistResponse;
             type Future =
-              BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::QueryRequest>,
+              request: tonic::Request<super::LeaveWaitlistRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::query(&inner, request).await
+                <T as ReservationService>::leave_waitlist(&inner, request).await
               };
               Box::pin(fut)
             }
@@ -811,7 +2676,7 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = querySvc(inner);
+            let method = leave_waitlistSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -822,27 +2687,29 @@ pub mod reservation_service_server {
                 max_decoding_message_size,
                 max_encoding_message_size,
               );
-            let res = grpc.server_streaming(method, req).await;
+            let res = grpc.unary(method, req).await;
             Ok(res)
           };
           Box::pin(fut)
         }
-        "/reservation.ReservationService/filter" => {
+        "/reservation.ReservationService/create_resource" => {
           #[allow(non_camel_case_types)]
-          struct filterSvc<T: ReservationService>(pub Arc<T>);
+          struct create_resourceSvc<T: ReservationService>(pub Arc<T>);
           impl<T: ReservationService>
-            tonic::server::UnaryService<super::FilterRequest> for filterSvc<T>
+            tonic::server::UnaryService<super::CreateResourceRequest>
+            for create_resourceSvc<T>
           {
-            type Response = super::FilterResponse;
+            type Response = super::CreateResourceResponse;
             type Future =
               BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
             fn call(
               &mut self,
-              request: tonic::Request<super::FilterRequest>,
+              request: tonic::Request<super::CreateResourceRequest>,
             ) -> Self::Future {
               let inner = Arc::clone(&self.0);
               let fut = async move {
-                <T as ReservationService>::filter(&inner, request).await
+                <T as ReservationService>::create_resource(&inner, request)
+                  .await
               };
               Box::pin(fut)
             }
@@ -853,7 +2720,181 @@ pub mod reservation_service_server {
           let max_encoding_message_size = self.max_encoding_message_size;
           let inner = self.inner.clone();
           let fut = async move {
-            let method = filterSvc(inner);
+            let method = create_resourceSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/get_resource" => {
+          #[allow(non_camel_case_types)]
+          struct get_resourceSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::GetResourceRequest>
+            for get_resourceSvc<T>
+          {
+            type Response = super::GetResourceResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::GetResourceRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::get_resource(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = get_resourceSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/update_resource" => {
+          #[allow(non_camel_case_types)]
+          struct update_resourceSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::UpdateResourceRequest>
+            for update_resourceSvc<T>
+          {
+            type Response = super::UpdateResourceResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::UpdateResourceRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::update_resource(&inner, request)
+                  .await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = update_resourceSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/delete_resource" => {
+          #[allow(non_camel_case_types)]
+          struct delete_resourceSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::DeleteResourceRequest>
+            for delete_resourceSvc<T>
+          {
+            type Response = super::DeleteResourceResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::DeleteResourceRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::delete_resource(&inner, request)
+                  .await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = delete_resourceSvc(inner);
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc = tonic::server::Grpc::new(codec)
+              .apply_compression_config(
+                accept_compression_encodings,
+                send_compression_encodings,
+              )
+              .apply_max_message_size_config(
+                max_decoding_message_size,
+                max_encoding_message_size,
+              );
+            let res = grpc.unary(method, req).await;
+            Ok(res)
+          };
+          Box::pin(fut)
+        }
+        "/reservation.ReservationService/list_resources" => {
+          #[allow(non_camel_case_types)]
+          struct list_resourcesSvc<T: ReservationService>(pub Arc<T>);
+          impl<T: ReservationService>
+            tonic::server::UnaryService<super::ListResourcesRequest>
+            for list_resourcesSvc<T>
+          {
+            type Response = super::ListResourcesResponse;
+            type Future =
+              BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+            fn call(
+              &mut self,
+              request: tonic::Request<super::ListResourcesRequest>,
+            ) -> Self::Future {
+              let inner = Arc::clone(&self.0);
+              let fut = async move {
+                <T as ReservationService>::list_resources(&inner, request).await
+              };
+              Box::pin(fut)
+            }
+          }
+          let accept_compression_encodings = self.accept_compression_encodings;
+          let send_compression_encodings = self.send_compression_encodings;
+          let max_decoding_message_size = self.max_decoding_message_size;
+          let max_encoding_message_size = self.max_encoding_message_size;
+          let inner = self.inner.clone();
+          let fut = async move {
+            let method = list_resourcesSvc(inner);
             let codec = tonic::codec::ProstCodec::default();
             let mut grpc = tonic::server::Grpc::new(codec)
               .apply_compression_config(
@@ -0,0 +1,106 @@
+use chrono::Duration;
+use sqlx::{
+  postgres::{types::PgRange, PgRow},
+  types::chrono::{DateTime, Utc},
+  FromRow, Row,
+};
+
+use crate::{
+  convert_to_duration, convert_to_timestamp,
+  types::{get_timespan, validate_range},
+  AvailabilityQuery, AvailableWindow, Error, Validator,
+};
+
+impl AvailabilityQuery {
+  pub fn get_timespan(&self) -> PgRange<DateTime<Utc>> {
+    get_timespan(self.start.as_ref(), self.end.as_ref())
+  }
+
+  pub fn get_min_duration(&self) -> Option<Duration> {
+    self.min_duration.map(convert_to_duration)
+  }
+
+  pub fn get_granularity(&self) -> Option<Duration> {
+    self.granularity.map(convert_to_duration)
+  }
+}
+
+impl Validator for AvailabilityQuery {
+  fn validate(&self) -> Result<(), Error> {
+    if self.resource_id.is_empty() {
+      return Err(Error::InvalidResourceId(self.resource_id.clone()));
+    }
+    validate_range(self.start.as_ref(), self.end.as_ref())?;
+
+    if self
+      .get_min_duration()
+      .is_some_and(|d| d < Duration::zero())
+    {
+      return Err(Error::InvalidTime);
+    }
+    if self
+      .get_granularity()
+      .is_some_and(|d| d <= Duration::zero())
+    {
+      return Err(Error::InvalidTime);
+    }
+    Ok(())
+  }
+}
+
+impl FromRow<'_, PgRow> for AvailableWindow {
+  fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+    let start: DateTime<Utc> = row.get("start_at");
+    let end: DateTime<Utc> = row.get("end_at");
+
+    Ok(Self {
+      resource_id: row.get("resource_id"),
+      start: Some(convert_to_timestamp(start)),
+      end: Some(convert_to_timestamp(end)),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::convert_local_time_to_utc;
+
+  fn make_query() -> AvailabilityQuery {
+    AvailabilityQuery {
+      resource_id: "room-1".to_string(),
+      start: Some(convert_to_timestamp(convert_local_time_to_utc(
+        "2024-01-21 09:00:00",
+      ))),
+      end: Some(convert_to_timestamp(convert_local_time_to_utc(
+        "2024-01-21 17:00:00",
+      ))),
+      min_duration: None,
+      granularity: None,
+    }
+  }
+
+  #[test]
+  fn availability_query_validate_should_work() {
+    let query = make_query();
+    assert!(query.validate().is_ok());
+
+    let mut query = make_query();
+    query.resource_id = String::new();
+    assert_eq!(
+      query.validate().unwrap_err(),
+      Error::InvalidResourceId(String::new())
+    );
+
+    let mut query = make_query();
+    query.granularity = Some(prost_types::Duration::default());
+    assert_eq!(query.validate().unwrap_err(), Error::InvalidTime);
+
+    let mut query = make_query();
+    query.min_duration = Some(prost_types::Duration {
+      seconds: -60,
+      nanos: 0,
+    });
+    assert_eq!(query.validate().unwrap_err(), Error::InvalidTime);
+  }
+}
@@ -0,0 +1,315 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+  convert_to_duration, convert_to_utc_time, Blackout, BookingRules, Error,
+  OpeningHours, RuleViolation, Validator,
+};
+
+const HOURS_FORMAT: &str = "%H:%M";
+
+impl OpeningHours {
+  pub fn new(
+    weekday: chrono::Weekday,
+    opens: impl Into<String>,
+    closes: impl Into<String>,
+  ) -> Self {
+    Self {
+      weekday: weekday.number_from_monday() as i32,
+      opens: opens.into(),
+      closes: closes.into(),
+    }
+  }
+
+  /// opening and closing as offsets from midnight, closing at 00:00 is
+  /// midnight at the end of the day
+  pub fn get_hours(&self) -> Result<Range<Duration>, Error> {
+    let parse = |time: &str| {
+      NaiveTime::parse_from_str(time, HOURS_FORMAT)
+        .map(|time| time - NaiveTime::MIN)
+        .map_err(|_| {
+          Error::InvalidResource(format!("invalid opening time {}", time))
+        })
+    };
+    let opens = parse(&self.opens)?;
+    let closes = match parse(&self.closes)? {
+      closes if closes.is_zero() => Duration::days(1),
+      closes => closes,
+    };
+
+    if opens >= closes {
+      return Err(Error::InvalidResource(format!(
+        "opening hours {} - {} close before they open",
+        self.opens, self.closes
+      )));
+    }
+    Ok(opens..closes)
+  }
+}
+
+impl Validator for OpeningHours {
+  fn validate(&self) -> Result<(), Error> {
+    if !(1..=7).contains(&self.weekday) {
+      return Err(Error::InvalidResource(format!(
+        "weekday must be 1 to 7, got {}",
+        self.weekday
+      )));
+    }
+    self.get_hours()?;
+    Ok(())
+  }
+}
+
+impl Blackout {
+  pub fn get_timespan(&self) -> Range<DateTime<Utc>> {
+    let start = convert_to_utc_time(self.start.unwrap());
+    let end = convert_to_utc_time(self.end.unwrap());
+
+    start..end
+  }
+}
+
+impl BookingRules {
+  /// every rule a reservation over `span` made at `now` breaks, opening
+  /// hours are in `tz`
+  pub fn violations(
+    &self,
+    span: &Range<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    tz: Tz,
+  ) -> Vec<RuleViolation> {
+    let mut violations = Vec::new();
+    let start = span.start.with_timezone(&tz).naive_local();
+    let end = span.end.with_timezone(&tz).naive_local();
+
+    if !self.opening_hours.is_empty() && !self.is_open(start, end) {
+      violations.push(RuleViolation::OutsideOpeningHours);
+    }
+    for blackout in &self.blackouts {
+      let during = blackout.get_timespan();
+      if during.start < span.end && span.start < during.end {
+        violations.push(RuleViolation::Blackout {
+          start: during.start,
+          end: during.end,
+          reason: blackout.reason.clone(),
+        });
+      }
+    }
+
+    let duration = span.end - span.start;
+    let lead_time = span.start - now;
+    if let Some(min_duration) = self.min_duration.map(convert_to_duration) {
+      if duration < min_duration {
+        violations.push(RuleViolation::TooShort { min_duration });
+      }
+    }
+    if let Some(max_duration) = self.max_duration.map(convert_to_duration) {
+      if duration > max_duration {
+        violations.push(RuleViolation::TooLong { max_duration });
+      }
+    }
+    if let Some(min_lead_time) = self.min_lead_time.map(convert_to_duration) {
+      if lead_time < min_lead_time {
+        violations.push(RuleViolation::TooSoon { min_lead_time });
+      }
+    }
+    if let Some(max_horizon) = self.max_horizon.map(convert_to_duration) {
+      if lead_time > max_horizon {
+        violations.push(RuleViolation::TooFarAhead { max_horizon });
+      }
+    }
+
+    violations
+  }
+
+  /// whether opening hours cover all of the local times from `start` to
+  /// `end`, hours of consecutive days join up across midnight
+  fn is_open(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+    let mut covered = start;
+    let mut day = start.date();
+
+    while covered < end && day <= end.date() {
+      let midnight = day.and_time(NaiveTime::MIN);
+      let weekday = day.weekday().number_from_monday() as i32;
+      let mut hours: Vec<_> = self
+        .opening_hours
+        .iter()
+        .filter(|hours| hours.weekday == weekday)
+        .filter_map(|hours| hours.get_hours().ok())
+        .collect();
+      hours.sort_by_key(|hours| hours.start);
+
+      for hours in hours {
+        if midnight + hours.start <= covered && covered < midnight + hours.end {
+          covered = midnight + hours.end;
+        }
+      }
+      day = match day.succ_opt() {
+        Some(day) => day,
+        None => break,
+      };
+    }
+
+    covered >= end
+  }
+}
+
+impl Validator for BookingRules {
+  fn validate(&self) -> Result<(), Error> {
+    for hours in &self.opening_hours {
+      hours.validate()?;
+    }
+    for blackout in &self.blackouts {
+      if blackout.start.is_none()
+        || blackout.end.is_none()
+        || blackout.get_timespan().is_empty()
+      {
+        return Err(Error::InvalidResource(
+          "blackout must end after it starts".to_string(),
+        ));
+      }
+    }
+
+    let durations = [
+      ("min_duration", self.min_duration),
+      ("max_duration", self.max_duration),
+      ("min_lead_time", self.min_lead_time),
+      ("max_horizon", self.max_horizon),
+    ];
+    for (name, duration) in durations {
+      if duration.is_some_and(|d| convert_to_duration(d) < Duration::zero()) {
+        return Err(Error::InvalidResource(format!(
+          "{} must not be negative",
+          name
+        )));
+      }
+    }
+    if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+      if convert_to_duration(min) > convert_to_duration(max) {
+        return Err(Error::InvalidResource(
+          "min_duration is longer than max_duration".to_string(),
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::Weekday;
+
+  use super::*;
+  use crate::convert_to_timestamp;
+
+  fn time(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+  }
+
+  fn duration(hours: i64) -> Option<prost_types::Duration> {
+    Some(prost_types::Duration {
+      seconds: hours * 3600,
+      nanos: 0,
+    })
+  }
+
+  fn office_hours() -> BookingRules {
+    BookingRules {
+      opening_hours: [Weekday::Mon, Weekday::Tue]
+        .into_iter()
+        .map(|day| OpeningHours::new(day, "09:00", "18:00"))
+        .collect(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn opening_hours_should_be_checked_in_resource_timezone() {
+    let rules = office_hours();
+    let now = time("2024-01-01T00:00:00Z");
+    // 2024-01-08 is a monday, 09:00 in Tokyo is 00:00 UTC
+    let span = time("2024-01-08T00:00:00Z")..time("2024-01-08T02:00:00Z");
+
+    assert!(rules.violations(&span, now, Tz::Asia__Tokyo).is_empty());
+    assert_eq!(
+      rules.violations(&span, now, Tz::UTC),
+      vec![RuleViolation::OutsideOpeningHours]
+    );
+  }
+
+  #[test]
+  fn opening_hours_should_join_across_midnight() {
+    let mut rules = BookingRules {
+      opening_hours: vec![
+        OpeningHours::new(Weekday::Mon, "20:00", "00:00"),
+        OpeningHours::new(Weekday::Tue, "00:00", "06:00"),
+      ],
+      ..Default::default()
+    };
+    let now = time("2024-01-01T00:00:00Z");
+    let span = time("2024-01-08T22:00:00Z")..time("2024-01-09T05:00:00Z");
+    assert!(rules.violations(&span, now, Tz::UTC).is_empty());
+
+    rules.opening_hours.pop();
+    assert_eq!(
+      rules.violations(&span, now, Tz::UTC),
+      vec![RuleViolation::OutsideOpeningHours]
+    );
+  }
+
+  #[test]
+  fn violations_should_list_every_broken_rule() {
+    let rules = BookingRules {
+      blackouts: vec![Blackout {
+        start: Some(convert_to_timestamp(time("2024-01-08T00:00:00Z"))),
+        end: Some(convert_to_timestamp(time("2024-01-09T00:00:00Z"))),
+        reason: "holiday".to_string(),
+      }],
+      max_duration: duration(2),
+      min_lead_time: duration(24),
+      ..office_hours()
+    };
+    let now = time("2024-01-08T06:00:00Z");
+    let span = time("2024-01-08T08:00:00Z")..time("2024-01-08T20:00:00Z");
+
+    assert_eq!(
+      rules.violations(&span, now, Tz::UTC),
+      vec![
+        RuleViolation::OutsideOpeningHours,
+        RuleViolation::Blackout {
+          start: time("2024-01-08T00:00:00Z"),
+          end: time("2024-01-09T00:00:00Z"),
+          reason: "holiday".to_string(),
+        },
+        RuleViolation::TooLong {
+          max_duration: Duration::hours(2)
+        },
+        RuleViolation::TooSoon {
+          min_lead_time: Duration::hours(24)
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn booking_rules_validate_should_work() {
+    assert!(office_hours().validate().is_ok());
+
+    let mut rules = office_hours();
+    rules.opening_hours[0].weekday = 8;
+    assert!(matches!(rules.validate(), Err(Error::InvalidResource(_))));
+
+    let mut rules = office_hours();
+    rules.opening_hours[0].closes = "08:00".to_string();
+    assert!(matches!(rules.validate(), Err(Error::InvalidResource(_))));
+
+    let rules = BookingRules {
+      min_duration: duration(3),
+      max_duration: duration(2),
+      ..Default::default()
+    };
+    assert!(matches!(rules.validate(), Err(Error::InvalidResource(_))));
+  }
+}
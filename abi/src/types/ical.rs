@@ -0,0 +1,306 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::{convert_to_timestamp, Error, Reservation, ReservationStatus};
+
+const PRODID: &str = "-//reservation//reservation service//EN";
+
+/// lines longer than this many octets are folded, RFC 5545 3.1
+const MAX_LINE_LEN: usize = 75;
+
+const ICAL_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// render reservations as an iCalendar (RFC 5545) file of VEVENTs,
+/// `dtstamp` is when the calendar is generated
+pub fn to_ics<'a>(
+  rsvps: impl IntoIterator<Item = &'a Reservation>,
+  dtstamp: DateTime<Utc>,
+) -> String {
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    format!("PRODID:{}", PRODID),
+  ];
+  for rsvp in rsvps {
+    lines.extend(rsvp.to_vevent(dtstamp));
+  }
+  lines.push("END:VCALENDAR".to_string());
+
+  lines
+    .iter()
+    .map(|line| fold_line(line))
+    .collect::<Vec<_>>()
+    .join("\r\n")
+    + "\r\n"
+}
+
+/// parse the VEVENTs of an iCalendar file into reservations, ids are left 0
+pub fn from_ics(ics: &str) -> Result<Vec<Reservation>, Error> {
+  let mut rsvps = Vec::new();
+  let mut event: Option<Vec<(String, String, String)>> = None;
+
+  for line in unfold_lines(ics) {
+    let (name, params, value) = split_property(&line)?;
+    match (name.as_str(), value.as_str(), event.as_mut()) {
+      ("BEGIN", "VEVENT", None) => event = Some(Vec::new()),
+      ("END", "VEVENT", Some(_)) => {
+        rsvps.push(parse_vevent(event.take().unwrap())?);
+      }
+      (_, _, Some(props)) => props.push((name, params, value)),
+      _ => {}
+    }
+  }
+
+  if event.is_some() {
+    return Err(Error::InvalidIcs("VEVENT is not closed".to_string()));
+  }
+  Ok(rsvps)
+}
+
+impl Reservation {
+  /// lines of the VEVENT of this reservation, not folded
+  pub fn to_vevent(&self, dtstamp: DateTime<Utc>) -> Vec<String> {
+    let span = self.get_timespan();
+    let status = ReservationStatus::try_from(self.status)
+      .unwrap_or(ReservationStatus::Unknown);
+
+    let mut lines = vec![
+      "BEGIN:VEVENT".to_string(),
+      format!("UID:{}@reservation", self.id),
+      format!("DTSTAMP:{}", dtstamp.format(ICAL_TIME_FORMAT)),
+      format!("DTSTART:{}", span.start.format(ICAL_TIME_FORMAT)),
+      format!("DTEND:{}", span.end.format(ICAL_TIME_FORMAT)),
+      format!("SUMMARY:{}", escape_text(&self.note)),
+      format!("LOCATION:{}", escape_text(&self.resource_id)),
+    ];
+    if let Some(status) = vevent_status(status) {
+      lines.push(format!("STATUS:{}", status));
+    }
+    // keep what VEVENT can't express, so an export can be imported back
+    lines.push(format!("X-RSVP-STATUS:{}", status));
+    lines.push(format!("X-RSVP-USER-ID:{}", escape_text(&self.user_id)));
+    lines.push(format!(
+      "X-RSVP-RESOURCE-ID:{}",
+      escape_text(&self.resource_id)
+    ));
+    lines.push("END:VEVENT".to_string());
+    lines
+  }
+}
+
+/// VEVENT only knows TENTATIVE, CONFIRMED and CANCELLED
+fn vevent_status(status: ReservationStatus) -> Option<&'static str> {
+  match status {
+    ReservationStatus::Pending => Some("TENTATIVE"),
+    ReservationStatus::Confirmed
+    | ReservationStatus::Blocked
+    | ReservationStatus::Completed => Some("CONFIRMED"),
+    ReservationStatus::Cancelled | ReservationStatus::Expired => {
+      Some("CANCELLED")
+    }
+    ReservationStatus::Unknown => None,
+  }
+}
+
+fn parse_vevent(
+  props: Vec<(String, String, String)>,
+) -> Result<Reservation, Error> {
+  let mut rsvp = Reservation::default();
+  let mut status = None;
+  let mut rsvp_status = None;
+  let mut location = None;
+  let mut resource_id = None;
+
+  for (name, params, value) in props {
+    match name.as_str() {
+      "DTSTART" => {
+        rsvp.start = Some(convert_to_timestamp(parse_time(&params, &value)?))
+      }
+      "DTEND" => {
+        rsvp.end = Some(convert_to_timestamp(parse_time(&params, &value)?))
+      }
+      "SUMMARY" => rsvp.note = unescape_text(&value),
+      "LOCATION" => location = Some(unescape_text(&value)),
+      "STATUS" => status = Some(value),
+      "X-RSVP-STATUS" => rsvp_status = value.parse::<ReservationStatus>().ok(),
+      "X-RSVP-USER-ID" => rsvp.user_id = unescape_text(&value),
+      "X-RSVP-RESOURCE-ID" => resource_id = Some(unescape_text(&value)),
+      _ => {}
+    }
+  }
+
+  if rsvp.start.is_none() || rsvp.end.is_none() {
+    return Err(Error::InvalidIcs(
+      "VEVENT needs DTSTART and DTEND".to_string(),
+    ));
+  }
+  rsvp.resource_id = resource_id.or(location).unwrap_or_default();
+  let status = rsvp_status.unwrap_or(match status.as_deref() {
+    Some("CONFIRMED") => ReservationStatus::Confirmed,
+    Some("CANCELLED") => ReservationStatus::Cancelled,
+    _ => ReservationStatus::Pending,
+  });
+  rsvp.status = status as i32;
+
+  Ok(rsvp)
+}
+
+/// UTC date times and dates are supported, dates start at midnight UTC
+fn parse_time(params: &str, value: &str) -> Result<DateTime<Utc>, Error> {
+  let invalid = || Error::InvalidIcs(format!("unsupported time {}", value));
+  if params.split(';').any(|p| p.starts_with("TZID=")) {
+    return Err(invalid());
+  }
+
+  if params.split(';').any(|p| p == "VALUE=DATE") {
+    return NaiveDate::parse_from_str(value, "%Y%m%d")
+      .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc())
+      .map_err(|_| invalid());
+  }
+  NaiveDateTime::parse_from_str(value, ICAL_TIME_FORMAT)
+    .map(|t| t.and_utc())
+    .map_err(|_| invalid())
+}
+
+/// `NAME;PARAM=...:VALUE` into upper cased name, params and value
+fn split_property(line: &str) -> Result<(String, String, String), Error> {
+  let (head, value) = line
+    .split_once(':')
+    .ok_or_else(|| Error::InvalidIcs(format!("malformed line {}", line)))?;
+  let (name, params) = head.split_once(';').unwrap_or((head, ""));
+
+  Ok((
+    name.to_ascii_uppercase(),
+    params.to_ascii_uppercase(),
+    value.to_string(),
+  ))
+}
+
+fn unfold_lines(ics: &str) -> Vec<String> {
+  let mut lines: Vec<String> = Vec::new();
+  for line in ics.split('\n').map(|l| l.trim_end_matches('\r')) {
+    match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+      (Some(rest), Some(last)) => last.push_str(rest),
+      _ if line.is_empty() => {}
+      _ => lines.push(line.to_string()),
+    }
+  }
+  lines
+}
+
+fn fold_line(line: &str) -> String {
+  let mut ret = String::with_capacity(line.len());
+  let mut len = 0;
+  for c in line.chars() {
+    // continuation lines start with a space, which takes one octet
+    if len + c.len_utf8() > MAX_LINE_LEN {
+      ret.push_str("\r\n ");
+      len = 1;
+    }
+    ret.push(c);
+    len += c.len_utf8();
+  }
+  ret
+}
+
+fn escape_text(s: &str) -> String {
+  s.replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace('\n', "\\n")
+}
+
+fn unescape_text(s: &str) -> String {
+  let mut ret = String::with_capacity(s.len());
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      ret.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('n' | 'N') => ret.push('\n'),
+      Some(c) => ret.push(c),
+      None => ret.push('\\'),
+    }
+  }
+  ret
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::convert_to_utc_time;
+
+  fn make_reservation(id: i64, note: &str) -> Reservation {
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "ocean-view-room-713",
+      "2024-01-21T11:00:00Z".parse().unwrap(),
+      "2024-01-22T04:00:00Z".parse().unwrap(),
+      note,
+    );
+    rsvp.id = id;
+    rsvp
+  }
+
+  #[test]
+  fn to_ics_should_render_vevents() {
+    let rsvp = make_reservation(42, "birthday; bring cake, candles");
+    let dtstamp = "2024-01-01T00:00:00Z".parse().unwrap();
+    let ics = to_ics([&rsvp], dtstamp);
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.contains("\r\nUID:42@reservation\r\n"));
+    assert!(ics.contains("\r\nDTSTAMP:20240101T000000Z\r\n"));
+    assert!(ics.contains("\r\nDTSTART:20240121T110000Z\r\n"));
+    assert!(ics.contains("\r\nDTEND:20240122T040000Z\r\n"));
+    assert!(ics.contains("\r\nSUMMARY:birthday\\; bring cake\\, candles\r\n"));
+    assert!(ics.contains("\r\nSTATUS:TENTATIVE\r\n"));
+    assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_LEN));
+  }
+
+  #[test]
+  fn ics_should_round_trip() {
+    let mut blocked = make_reservation(2, &"long note\nwith lines ".repeat(10));
+    blocked.status = ReservationStatus::Blocked as i32;
+    let rsvps = vec![make_reservation(1, "stay"), blocked];
+
+    let ics = to_ics(&rsvps, Utc::now());
+    let parsed = from_ics(&ics).unwrap();
+
+    let expected: Vec<_> = rsvps
+      .into_iter()
+      .map(|rsvp| Reservation { id: 0, ..rsvp })
+      .collect();
+    assert_eq!(parsed, expected);
+  }
+
+  #[test]
+  fn from_ics_should_read_plain_vevents() {
+    let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:abc\r\nDTSTART;VALUE=DATE:20240121\r\nDTEND;VALUE=DATE:20240122\r\nSUMMARY:all\r\n  day\r\nLOCATION:room-1\r\nSTATUS:CONFIRMED\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+    let rsvps = from_ics(ics).unwrap();
+
+    assert_eq!(rsvps.len(), 1);
+    let rsvp = &rsvps[0];
+    assert_eq!(rsvp.note, "all day");
+    assert_eq!(rsvp.resource_id, "room-1");
+    assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
+    assert_eq!(
+      convert_to_utc_time(rsvp.start.unwrap()).to_rfc3339(),
+      "2024-01-21T00:00:00+00:00"
+    );
+  }
+
+  #[test]
+  fn from_ics_should_reject_invalid_events() {
+    let ics = "BEGIN:VEVENT\r\nDTSTART;TZID=Asia/Tokyo:20240121T090000\r\nDTEND:20240121T100000Z\r\nEND:VEVENT\r\n";
+    assert!(matches!(from_ics(ics), Err(Error::InvalidIcs(_))));
+
+    let ics = "BEGIN:VEVENT\r\nDTSTART:20240121T090000Z\r\nEND:VEVENT\r\n";
+    assert!(matches!(from_ics(ics), Err(Error::InvalidIcs(_))));
+
+    let ics = "BEGIN:VEVENT\r\nDTSTART:20240121T090000Z\r\n";
+    assert!(matches!(from_ics(ics), Err(Error::InvalidIcs(_))));
+  }
+}
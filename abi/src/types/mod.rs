@@ -1,6 +1,17 @@
+mod availability;
+mod booking_rules;
+mod ical;
+mod recurrence;
 mod reservation;
+mod reservation_change;
 mod reservation_query;
 mod reservation_status;
+mod reservation_update;
+mod resource;
+mod waitlist;
+
+pub use ical::{from_ics, to_ics};
+pub use recurrence::{ByDay, Frequency, RecurrenceRule, MAX_OCCURRENCES};
 
 pub type ReservationId = i64;
 use std::ops::Bound;
@@ -19,10 +30,30 @@ use crate::{convert_to_utc_time, Error, Validator};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Type)]
 #[sqlx(type_name = "reservation_status", rename_all = "lowercase")]
 pub enum RsvpStatus {
-  Unkonwn,
+  Unknown,
   Confirmed,
   Pending,
   Blocked,
+  Cancelled,
+  Expired,
+  Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[sqlx(type_name = "waitlist_status", rename_all = "lowercase")]
+pub enum RsvpWaitlistStatus {
+  Waiting,
+  Promoted,
+  Withdrawn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[sqlx(type_name = "reservation_update_type", rename_all = "lowercase")]
+pub enum RsvpUpdateType {
+  Unknown,
+  Create,
+  Update,
+  Delete,
 }
 
 fn validate_range(
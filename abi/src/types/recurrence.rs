@@ -0,0 +1,466 @@
+use std::str::FromStr;
+
+use chrono::{
+  DateTime, Datelike, Days, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc,
+  Weekday,
+};
+
+use crate::Error;
+
+/// most occurrences a single rule may expand to
+pub const MAX_OCCURRENCES: usize = 500;
+
+/// periods walked before giving up on a rule that never matches
+const MAX_PERIODS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+  Daily,
+  Weekly,
+  Monthly,
+  Yearly,
+}
+
+/// weekday of a BYDAY part, `ordinal` picks the nth (or nth last when
+/// negative) weekday of a month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+  pub ordinal: Option<i32>,
+  pub weekday: Weekday,
+}
+
+/// the subset of an iCalendar RRULE (RFC 5545) supported for recurring
+/// reservations: FREQ, INTERVAL, BYDAY, COUNT and UNTIL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+  pub freq: Frequency,
+  pub interval: u32,
+  pub by_day: Vec<ByDay>,
+  pub count: Option<usize>,
+  pub until: Option<DateTime<Utc>>,
+}
+
+impl FromStr for RecurrenceRule {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = |msg: &str| Error::InvalidRecurrence(msg.to_string());
+    let s = s.trim();
+    let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+    let mut freq = None;
+    let mut interval = 1;
+    let mut by_day = Vec::new();
+    let mut count = None;
+    let mut until = None;
+
+    for part in s.split(';').filter(|p| !p.is_empty()) {
+      let (key, value) = part
+        .split_once('=')
+        .ok_or_else(|| invalid(&format!("malformed part {}", part)))?;
+      match key.to_ascii_uppercase().as_str() {
+        "FREQ" => {
+          freq = Some(match value.to_ascii_uppercase().as_str() {
+            "DAILY" => Frequency::Daily,
+            "WEEKLY" => Frequency::Weekly,
+            "MONTHLY" => Frequency::Monthly,
+            "YEARLY" => Frequency::Yearly,
+            _ => return Err(invalid(&format!("unsupported FREQ {}", value))),
+          })
+        }
+        "INTERVAL" => {
+          interval = value
+            .parse()
+            .ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| invalid(&format!("invalid INTERVAL {}", value)))?
+        }
+        "COUNT" => {
+          count = Some(
+            value
+              .parse()
+              .ok()
+              .filter(|v| *v > 0)
+              .ok_or_else(|| invalid(&format!("invalid COUNT {}", value)))?,
+          )
+        }
+        "UNTIL" => until = Some(parse_ical_time(value)?),
+        "BYDAY" => {
+          by_day = value
+            .split(',')
+            .map(parse_by_day)
+            .collect::<Result<_, _>>()?
+        }
+        // weeks always start on monday
+        "WKST" => {}
+        _ => return Err(invalid(&format!("unsupported part {}", key))),
+      }
+    }
+
+    let freq = freq.ok_or_else(|| invalid("FREQ is required"))?;
+    if count.is_some() && until.is_some() {
+      return Err(invalid("COUNT and UNTIL can't be used together"));
+    }
+    let has_ordinal = by_day.iter().any(|d| d.ordinal.is_some());
+    if has_ordinal && freq != Frequency::Monthly {
+      return Err(invalid("BYDAY ordinals are only supported for MONTHLY"));
+    }
+    if freq == Frequency::Yearly && !by_day.is_empty() {
+      return Err(invalid("BYDAY is not supported for YEARLY"));
+    }
+
+    Ok(Self {
+      freq,
+      interval,
+      by_day,
+      count,
+      until,
+    })
+  }
+}
+
+impl RecurrenceRule {
+  /// start times of every occurrence beginning at `dtstart`, wall clock
+  /// times are kept in the zone of `dtstart`. occurrences in `exdates` are
+  /// skipped but still count towards COUNT, as RFC 5545 asks
+  pub fn occurrences<Tz: TimeZone>(
+    &self,
+    dtstart: &DateTime<Tz>,
+    exdates: &[DateTime<Utc>],
+  ) -> Result<Vec<DateTime<Utc>>, Error> {
+    let tz = dtstart.timezone();
+    let start = dtstart.naive_local();
+    let mut ret = Vec::new();
+    let mut seen = 0;
+
+    for period in 0..MAX_PERIODS {
+      // the calendar ends before the rule does
+      let Some(candidates) = self.period_candidates(start, period as i64)
+      else {
+        break;
+      };
+      for naive in candidates {
+        if naive < start {
+          continue;
+        }
+        // skip wall clock times that don't exist, e.g. in a DST gap
+        let Some(time) = tz.from_local_datetime(&naive).earliest() else {
+          continue;
+        };
+        let time = time.with_timezone(&Utc);
+        if self.until.is_some_and(|until| time > until) {
+          return Ok(ret);
+        }
+
+        seen += 1;
+        if !exdates.contains(&time) {
+          ret.push(time);
+        }
+        if self.count.is_some_and(|count| seen >= count) {
+          return Ok(ret);
+        }
+        if seen > MAX_OCCURRENCES {
+          return Err(Error::InvalidRecurrence(format!(
+            "rule expands to more than {} occurrences",
+            MAX_OCCURRENCES
+          )));
+        }
+      }
+    }
+
+    if self.count.is_none() && self.until.is_none() {
+      return Err(Error::InvalidRecurrence(
+        "rule needs COUNT or UNTIL".to_string(),
+      ));
+    }
+    Ok(ret)
+  }
+
+  /// sorted candidates of the `period`th FREQ period after `start`, None if
+  /// the period is past the last date chrono can represent
+  fn period_candidates(
+    &self,
+    start: NaiveDateTime,
+    period: i64,
+  ) -> Option<Vec<NaiveDateTime>> {
+    let step = period as u64 * self.interval as u64;
+    let time = start.time();
+    let mut days: Vec<NaiveDate> = match self.freq {
+      Frequency::Daily => {
+        let day = start.date().checked_add_days(Days::new(step))?;
+        if self.by_day.is_empty()
+          || self.by_day.iter().any(|d| d.weekday == day.weekday())
+        {
+          vec![day]
+        } else {
+          vec![]
+        }
+      }
+      Frequency::Weekly => {
+        let monday = (start.date()
+          - Duration::days(start.weekday().num_days_from_monday() as i64))
+        .checked_add_days(Days::new(step.checked_mul(7)?))?;
+        let weekday = |day: Weekday| {
+          monday.checked_add_days(Days::new(day.num_days_from_monday() as u64))
+        };
+        if self.by_day.is_empty() {
+          vec![weekday(start.weekday())?]
+        } else {
+          self
+            .by_day
+            .iter()
+            .filter_map(|d| weekday(d.weekday))
+            .collect()
+        }
+      }
+      Frequency::Monthly => {
+        let months =
+          start.year() as i64 * 12 + start.month0() as i64 + step as i64;
+        let year = i32::try_from(months / 12)
+          .ok()
+          .filter(|year| *year <= NaiveDate::MAX.year())?;
+        let month = (months % 12) as u32 + 1;
+        if self.by_day.is_empty() {
+          NaiveDate::from_ymd_opt(year, month, start.day())
+            .into_iter()
+            .collect()
+        } else {
+          self
+            .by_day
+            .iter()
+            .flat_map(|d| weekdays_of_month(year, month, *d))
+            .collect()
+        }
+      }
+      Frequency::Yearly => {
+        let year = i32::try_from(step)
+          .ok()
+          .and_then(|step| start.year().checked_add(step))
+          .filter(|year| *year <= NaiveDate::MAX.year())?;
+        NaiveDate::from_ymd_opt(year, start.month(), start.day())
+          .into_iter()
+          .collect()
+      }
+    };
+    days.sort();
+    days.dedup();
+    Some(days.into_iter().map(|day| day.and_time(time)).collect())
+  }
+}
+
+fn weekdays_of_month(year: i32, month: u32, by_day: ByDay) -> Vec<NaiveDate> {
+  let days: Vec<NaiveDate> = (1..=31)
+    .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+    .filter(|day| day.weekday() == by_day.weekday)
+    .collect();
+
+  match by_day.ordinal {
+    None => days,
+    Some(n) if n > 0 => days.get(n as usize - 1).copied().into_iter().collect(),
+    Some(n) => days
+      .len()
+      .checked_sub(n.unsigned_abs() as usize)
+      .and_then(|i| days.get(i))
+      .copied()
+      .into_iter()
+      .collect(),
+  }
+}
+
+fn parse_by_day(s: &str) -> Result<ByDay, Error> {
+  let invalid = || Error::InvalidRecurrence(format!("invalid BYDAY {}", s));
+  let s = s.trim().to_ascii_uppercase();
+  // split_at needs the weekday to be two single byte chars
+  if s.len() < 2 || !s.is_ascii() {
+    return Err(invalid());
+  }
+  let (ordinal, day) = s.split_at(s.len() - 2);
+  let weekday = match day {
+    "MO" => Weekday::Mon,
+    "TU" => Weekday::Tue,
+    "WE" => Weekday::Wed,
+    "TH" => Weekday::Thu,
+    "FR" => Weekday::Fri,
+    "SA" => Weekday::Sat,
+    "SU" => Weekday::Sun,
+    _ => return Err(invalid()),
+  };
+  let ordinal = match ordinal {
+    "" => None,
+    n => Some(
+      n.parse::<i32>()
+        .ok()
+        .filter(|n| *n != 0 && n.abs() <= 5)
+        .ok_or_else(invalid)?,
+    ),
+  };
+
+  Ok(ByDay { ordinal, weekday })
+}
+
+/// UNTIL is either a date or a UTC date time, e.g. 20240131 or
+/// 20240131T090000Z, floating times are taken as UTC
+fn parse_ical_time(s: &str) -> Result<DateTime<Utc>, Error> {
+  let s = s.trim_end_matches('Z');
+  NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S")
+    .or_else(|_| {
+      NaiveDate::parse_from_str(s, "%Y%m%d")
+        // a date UNTIL includes the whole day
+        .map(|d| d.and_hms_opt(23, 59, 59).unwrap())
+    })
+    .map(|t| t.and_utc())
+    .map_err(|_| Error::InvalidRecurrence(format!("invalid UNTIL {}", s)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn utc(s: &str) -> DateTime<Utc> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+      .unwrap()
+      .and_utc()
+  }
+
+  fn expand(rule: &str, dtstart: &str) -> Vec<DateTime<Utc>> {
+    let rule: RecurrenceRule = rule.parse().unwrap();
+    rule.occurrences(&utc(dtstart), &[]).unwrap()
+  }
+
+  #[test]
+  fn parse_rrule_should_work() {
+    let rule: RecurrenceRule =
+      "RRULE:FREQ=MONTHLY;INTERVAL=2;BYDAY=1MO,-1FR;UNTIL=20240630"
+        .parse()
+        .unwrap();
+    assert_eq!(rule.freq, Frequency::Monthly);
+    assert_eq!(rule.interval, 2);
+    assert_eq!(
+      rule.by_day,
+      vec![
+        ByDay {
+          ordinal: Some(1),
+          weekday: Weekday::Mon
+        },
+        ByDay {
+          ordinal: Some(-1),
+          weekday: Weekday::Fri
+        },
+      ]
+    );
+    assert_eq!(rule.until, Some(utc("2024-06-30 23:59:59")));
+  }
+
+  #[test]
+  fn parse_invalid_rrule_should_fail() {
+    for rule in [
+      "INTERVAL=2",
+      "FREQ=HOURLY",
+      "FREQ=DAILY;COUNT=0",
+      "FREQ=DAILY;COUNT=2;UNTIL=20240101",
+      "FREQ=WEEKLY;BYDAY=1MO",
+      "FREQ=WEEKLY;BYDAY=XX",
+      "FREQ=WEEKLY;BYDAY=éM;COUNT=2",
+      "FREQ=DAILY;BYHOUR=9",
+    ] {
+      assert!(
+        matches!(
+          rule.parse::<RecurrenceRule>(),
+          Err(Error::InvalidRecurrence(_))
+        ),
+        "{}",
+        rule
+      );
+    }
+  }
+
+  #[test]
+  fn weekly_by_day_should_expand() {
+    // 2024-01-01 is a monday
+    let ret = expand("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5", "2024-01-03 09:00:00");
+    assert_eq!(
+      ret,
+      vec![
+        utc("2024-01-03 09:00:00"),
+        utc("2024-01-08 09:00:00"),
+        utc("2024-01-10 09:00:00"),
+        utc("2024-01-15 09:00:00"),
+        utc("2024-01-17 09:00:00"),
+      ]
+    );
+
+    let ret = expand(
+      "FREQ=WEEKLY;INTERVAL=2;UNTIL=20240201",
+      "2024-01-01 09:00:00",
+    );
+    assert_eq!(
+      ret,
+      vec![
+        utc("2024-01-01 09:00:00"),
+        utc("2024-01-15 09:00:00"),
+        utc("2024-01-29 09:00:00"),
+      ]
+    );
+  }
+
+  #[test]
+  fn daily_and_monthly_should_expand() {
+    let ret = expand("FREQ=DAILY;BYDAY=SA,SU;COUNT=3", "2024-01-05 09:00:00");
+    assert_eq!(
+      ret,
+      vec![
+        utc("2024-01-06 09:00:00"),
+        utc("2024-01-07 09:00:00"),
+        utc("2024-01-13 09:00:00"),
+      ]
+    );
+
+    // months without a 31st are skipped
+    let ret = expand("FREQ=MONTHLY;COUNT=3", "2024-01-31 09:00:00");
+    assert_eq!(
+      ret,
+      vec![
+        utc("2024-01-31 09:00:00"),
+        utc("2024-03-31 09:00:00"),
+        utc("2024-05-31 09:00:00"),
+      ]
+    );
+
+    let ret = expand("FREQ=MONTHLY;BYDAY=-1FR;COUNT=2", "2024-01-01 09:00:00");
+    assert_eq!(
+      ret,
+      vec![utc("2024-01-26 09:00:00"), utc("2024-02-23 09:00:00")]
+    );
+  }
+
+  #[test]
+  fn exdates_should_be_skipped_but_counted() {
+    let rule: RecurrenceRule = "FREQ=DAILY;COUNT=3".parse().unwrap();
+    let ret = rule
+      .occurrences(&utc("2024-01-01 09:00:00"), &[utc("2024-01-02 09:00:00")])
+      .unwrap();
+    assert_eq!(
+      ret,
+      vec![utc("2024-01-01 09:00:00"), utc("2024-01-03 09:00:00")]
+    );
+  }
+
+  #[test]
+  fn huge_interval_should_stop_at_end_of_calendar() {
+    for freq in ["DAILY", "WEEKLY", "MONTHLY", "YEARLY"] {
+      let rule = format!("FREQ={};INTERVAL=1000000000;COUNT=2", freq);
+      assert_eq!(
+        expand(&rule, "2024-01-01 09:00:00"),
+        vec![utc("2024-01-01 09:00:00")],
+        "{}",
+        freq
+      );
+    }
+  }
+
+  #[test]
+  fn unbounded_rule_should_be_rejected() {
+    let rule: RecurrenceRule = "FREQ=DAILY".parse().unwrap();
+    assert!(rule.occurrences(&utc("2024-01-01 09:00:00"), &[]).is_err());
+  }
+}
@@ -1,15 +1,19 @@
 use std::ops::{Bound, Range};
 
+use chrono_tz::Tz;
 use sqlx::{
   postgres::{types::PgRange, PgRow},
-  types::chrono::{DateTime, Utc},
+  types::chrono::{DateTime, NaiveDateTime, Utc},
   FromRow, Row,
 };
 
 use crate::{
   types::validate_range,
-  utils::{convert_to_timestamp, convert_to_utc_time},
-  Error, Reservation, ReservationStatus, RsvpStatus, Validator,
+  utils::{
+    convert_to_timestamp, convert_to_utc_time, convert_tz_time_to_utc,
+    format_tz_time, parse_timezone,
+  },
+  Ambiguity, Error, Reservation, ReservationStatus, RsvpStatus, Validator,
 };
 
 impl Reservation {
@@ -28,15 +32,55 @@ impl Reservation {
       end: Some(convert_to_timestamp(end)),
       note: note.into(),
       status: ReservationStatus::Pending as i32,
+      ..Default::default()
     }
   }
 
+  /// pending reservation from wall clock times in `timezone`, a repeated
+  /// time when daylight saving ends is taken as its first occurrence
+  pub fn new_pending_in(
+    user_id: impl Into<String>,
+    resource_id: impl Into<String>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    timezone: &str,
+    note: impl Into<String>,
+  ) -> Result<Self, Error> {
+    let tz = parse_timezone(timezone)?;
+    let start = convert_tz_time_to_utc(start, tz, Ambiguity::Earliest)?;
+    let end = convert_tz_time_to_utc(end, tz, Ambiguity::Earliest)?;
+
+    let mut rsvp = Self::new_pending(user_id, resource_id, start, end, note);
+    rsvp.timezone = tz.name().to_string();
+    Ok(rsvp)
+  }
+
   pub fn get_timespan(&self) -> Range<DateTime<Utc>> {
     let start = convert_to_utc_time(self.start.unwrap());
     let end = convert_to_utc_time(self.end.unwrap());
 
     Range { start, end }
   }
+
+  /// timezone it is made in, None to use the one of its resource
+  pub fn get_timezone(&self) -> Result<Option<Tz>, Error> {
+    if self.timezone.is_empty() {
+      return Ok(None);
+    }
+    parse_timezone(&self.timezone).map(Some)
+  }
+
+  /// fill `local_start` and `local_end` with start and end in `tz`
+  pub fn set_local_times(&mut self, tz: Tz) {
+    self.local_start = self
+      .start
+      .map(|start| format_tz_time(convert_to_utc_time(start), tz))
+      .unwrap_or_default();
+    self.local_end = self
+      .end
+      .map(|end| format_tz_time(convert_to_utc_time(end), tz))
+      .unwrap_or_default();
+  }
 }
 
 impl Validator for Reservation {
@@ -48,7 +92,11 @@ impl Validator for Reservation {
     if self.resource_id.is_empty() {
       return Err(Error::InvalidResourceId(self.resource_id.clone()));
     }
+    if self.quantity < 0 {
+      return Err(Error::InvalidQuantity(self.quantity));
+    }
     validate_range(self.start.as_ref(), self.end.as_ref())?;
+    self.get_timezone()?;
     Ok(())
   }
 }
@@ -82,7 +130,7 @@ impl FromRow<'_, PgRow> for Reservation {
 
     let status: RsvpStatus = row.get("status");
 
-    Ok(Self {
+    let mut rsvp = Self {
       id: row.get("id"),
       user_id: row.get("user_id"),
       resource_id: row.get("resource_id"),
@@ -90,7 +138,28 @@ impl FromRow<'_, PgRow> for Reservation {
       end: Some(convert_to_timestamp(end.unwrap())),
       status: ReservationStatus::from(status) as i32,
       note: row.get("note"),
-    })
+      cancelled_at: row
+        .get::<Option<DateTime<Utc>>, _>("cancelled_at")
+        .map(convert_to_timestamp),
+      cancelled_by: row
+        .get::<Option<String>, _>("cancelled_by")
+        .unwrap_or_default(),
+      reason: row.get::<Option<String>, _>("reason").unwrap_or_default(),
+      hold_until: row
+        .get::<Option<DateTime<Utc>>, _>("hold_until")
+        .map(convert_to_timestamp),
+      quantity: row.get("quantity"),
+      series_id: row.get::<Option<i64>, _>("series_id").unwrap_or_default(),
+      timezone: row.get("timezone"),
+      version: row.get("version"),
+      updated_at: Some(convert_to_timestamp(row.get("updated_at"))),
+      ..Default::default()
+    };
+    if let Ok(Some(tz)) = rsvp.get_timezone() {
+      rsvp.set_local_times(tz);
+    }
+
+    Ok(rsvp)
   }
 }
 
@@ -113,4 +182,31 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn new_pending_in_should_convert_wall_clock_times() {
+    let time =
+      |s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+    let mut rsvp = Reservation::new_pending_in(
+      "user_id",
+      "resource_id",
+      time("2024-01-21 09:00:00"),
+      time("2024-01-21 10:00:00"),
+      "Asia/Tokyo",
+      "",
+    )
+    .unwrap();
+    assert_eq!(rsvp.timezone, "Asia/Tokyo");
+    assert_eq!(rsvp.start.unwrap(), "2024-01-21T00:00:00Z".parse().unwrap());
+
+    rsvp.set_local_times(rsvp.get_timezone().unwrap().unwrap());
+    assert_eq!(rsvp.local_start, "2024-01-21T09:00:00+09:00");
+    assert_eq!(rsvp.local_end, "2024-01-21T10:00:00+09:00");
+
+    rsvp.timezone = "Mars/Olympus_Mons".to_string();
+    assert_eq!(
+      rsvp.validate().unwrap_err(),
+      Error::InvalidTimezone(String::new())
+    );
+  }
 }
@@ -0,0 +1,149 @@
+use prost_types::Timestamp;
+use serde::Deserialize;
+use sqlx::{
+  postgres::PgRow,
+  types::{
+    chrono::{DateTime, Utc},
+    Json,
+  },
+  FromRow, Row,
+};
+
+use crate::{
+  error::parse_timespan, utils::convert_to_timestamp, ListenResponse,
+  Reservation, ReservationStatus, ReservationUpdateType, RsvpUpdateType,
+};
+
+/// reservation row serialized by `to_jsonb` in rsvp.reservation_trigger
+#[derive(Debug, Deserialize)]
+struct ReservationRecord {
+  id: i64,
+  user_id: String,
+  status: String,
+  resource_id: String,
+  timespan: String,
+  note: Option<String>,
+  #[serde(default)]
+  cancelled_at: Option<String>,
+  #[serde(default)]
+  cancelled_by: Option<String>,
+  #[serde(default)]
+  reason: Option<String>,
+  #[serde(default)]
+  hold_until: Option<String>,
+  #[serde(default)]
+  quantity: i32,
+  #[serde(default)]
+  series_id: Option<i64>,
+  #[serde(default)]
+  timezone: String,
+  #[serde(default)]
+  version: i64,
+  #[serde(default)]
+  updated_at: Option<String>,
+}
+
+impl TryFrom<ReservationRecord> for Reservation {
+  type Error = ();
+
+  fn try_from(value: ReservationRecord) -> Result<Self, Self::Error> {
+    // ["2024-01-21 11:00:00+00","2024-01-22 04:00:00+00")
+    let timespan = value
+      .timespan
+      .trim_matches(|c| matches!(c, '[' | '(' | ']' | ')'))
+      .replace('"', "");
+    let (start, end) = parse_timespan(&timespan)?;
+    let cancelled_at = parse_optional_time(value.cancelled_at)?;
+    let hold_until = parse_optional_time(value.hold_until)?;
+    let updated_at = parse_optional_time(value.updated_at)?;
+
+    let mut rsvp = Self {
+      id: value.id,
+      user_id: value.user_id,
+      status: value.status.parse::<ReservationStatus>()? as i32,
+      resource_id: value.resource_id,
+      start: Some(convert_to_timestamp(start)),
+      end: Some(convert_to_timestamp(end)),
+      note: value.note.unwrap_or_default(),
+      cancelled_at,
+      cancelled_by: value.cancelled_by.unwrap_or_default(),
+      reason: value.reason.unwrap_or_default(),
+      hold_until,
+      quantity: value.quantity,
+      series_id: value.series_id.unwrap_or_default(),
+      timezone: value.timezone,
+      version: value.version,
+      updated_at,
+      ..Default::default()
+    };
+    if let Ok(Some(tz)) = rsvp.get_timezone() {
+      rsvp.set_local_times(tz);
+    }
+
+    Ok(rsvp)
+  }
+}
+
+fn parse_optional_time(s: Option<String>) -> Result<Option<Timestamp>, ()> {
+  s.map(|s| DateTime::parse_from_rfc3339(&s).map_err(|_| ()))
+    .transpose()
+    .map(|time| time.map(|time| convert_to_timestamp(time.with_timezone(&Utc))))
+}
+
+impl From<RsvpUpdateType> for ReservationUpdateType {
+  fn from(value: RsvpUpdateType) -> Self {
+    match value {
+      RsvpUpdateType::Unknown => ReservationUpdateType::Unknown,
+      RsvpUpdateType::Create => ReservationUpdateType::Create,
+      RsvpUpdateType::Update => ReservationUpdateType::Update,
+      RsvpUpdateType::Delete => ReservationUpdateType::Delete,
+    }
+  }
+}
+
+fn decode_record(
+  record: Option<Json<ReservationRecord>>,
+) -> Result<Option<Reservation>, sqlx::Error> {
+  record
+    .map(|Json(record)| record.try_into())
+    .transpose()
+    .map_err(|_| sqlx::Error::Decode("invalid reservation record".into()))
+}
+
+impl FromRow<'_, PgRow> for ListenResponse {
+  fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+    // reservations_changes.id is a SERIAL, an INT4
+    let id: i32 = row.get("id");
+    let op: RsvpUpdateType = row.get("op");
+
+    Ok(Self {
+      id: id as i64,
+      op: ReservationUpdateType::from(op) as i32,
+      old: decode_record(row.get("old"))?,
+      new: decode_record(row.get("new"))?,
+      operator: row.get::<Option<String>, _>("operator").unwrap_or_default(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reservation_record_should_convert() {
+    let record: ReservationRecord = serde_json::from_str(
+      r#"{"id": 1, "note": "hello", "status": "pending", "user_id": "xiaozhangId", "timespan": "[\"2024-01-21 11:00:00+00\",\"2024-01-22 04:00:00+00\")", "resource_id": "ocean-view-room-713"}"#,
+    )
+    .unwrap();
+    let rsvp: Reservation = record.try_into().unwrap();
+
+    assert_eq!(rsvp.id, 1);
+    assert_eq!(rsvp.user_id, "xiaozhangId");
+    assert_eq!(rsvp.resource_id, "ocean-view-room-713");
+    assert_eq!(rsvp.status, ReservationStatus::Pending as i32);
+    assert_eq!(rsvp.start.unwrap(), "2024-01-21T11:00:00Z".parse().unwrap());
+    assert_eq!(rsvp.end.unwrap(), "2024-01-22T04:00:00Z".parse().unwrap());
+    assert_eq!(rsvp.note, "hello");
+  }
+}
@@ -1,3 +1,4 @@
+use chrono_tz::Tz;
 use sqlx::{
   postgres::types::PgRange,
   types::chrono::{DateTime, Utc},
@@ -5,6 +6,7 @@ use sqlx::{
 
 use crate::{
   types::{get_timespan, validate_range},
+  utils::parse_timezone,
   ReservationQuery, Validator,
 };
 
@@ -12,11 +14,20 @@ impl ReservationQuery {
   pub fn get_timespan(&self) -> PgRange<DateTime<Utc>> {
     get_timespan(self.start.as_ref(), self.end.as_ref())
   }
+
+  /// timezone to show local times in, None to keep the reservations' own
+  pub fn get_timezone(&self) -> Result<Option<Tz>, crate::Error> {
+    if self.timezone.is_empty() {
+      return Ok(None);
+    }
+    parse_timezone(&self.timezone).map(Some)
+  }
 }
 
 impl Validator for ReservationQuery {
   fn validate(&self) -> Result<(), crate::Error> {
     validate_range(self.start.as_ref(), self.end.as_ref())?;
+    self.get_timezone()?;
 
     Ok(())
   }
@@ -1,16 +1,64 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use crate::ReservationStatus;
 
 use super::RsvpStatus;
 
+impl ReservationStatus {
+  /// active reservations hold the time slot of the resource
+  pub fn is_active(&self) -> bool {
+    matches!(
+      self,
+      ReservationStatus::Pending
+        | ReservationStatus::Confirmed
+        | ReservationStatus::Blocked
+    )
+  }
+
+  /// pending -> confirmed -> completed
+  /// pending/confirmed -> cancelled
+  /// pending -> expired
+  /// blocked (admin hold) -> cancelled
+  pub fn can_transition_to(&self, to: ReservationStatus) -> bool {
+    matches!(
+      (self, to),
+      (ReservationStatus::Pending, ReservationStatus::Confirmed)
+        | (ReservationStatus::Pending, ReservationStatus::Cancelled)
+        | (ReservationStatus::Pending, ReservationStatus::Expired)
+        | (ReservationStatus::Confirmed, ReservationStatus::Completed)
+        | (ReservationStatus::Confirmed, ReservationStatus::Cancelled)
+        | (ReservationStatus::Blocked, ReservationStatus::Cancelled)
+    )
+  }
+}
+
 impl Display for ReservationStatus {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      ReservationStatus::Unknown => write!(f, "unkonwn"),
+      ReservationStatus::Unknown => write!(f, "unknown"),
       ReservationStatus::Pending => write!(f, "pending"),
       ReservationStatus::Confirmed => write!(f, "confirmed"),
       ReservationStatus::Blocked => write!(f, "blocked"),
+      ReservationStatus::Cancelled => write!(f, "cancelled"),
+      ReservationStatus::Expired => write!(f, "expired"),
+      ReservationStatus::Completed => write!(f, "completed"),
+    }
+  }
+}
+
+impl FromStr for ReservationStatus {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "unknown" => Ok(ReservationStatus::Unknown),
+      "pending" => Ok(ReservationStatus::Pending),
+      "confirmed" => Ok(ReservationStatus::Confirmed),
+      "blocked" => Ok(ReservationStatus::Blocked),
+      "cancelled" => Ok(ReservationStatus::Cancelled),
+      "expired" => Ok(ReservationStatus::Expired),
+      "completed" => Ok(ReservationStatus::Completed),
+      _ => Err(()),
     }
   }
 }
@@ -18,10 +66,57 @@ impl Display for ReservationStatus {
 impl From<RsvpStatus> for ReservationStatus {
   fn from(value: RsvpStatus) -> Self {
     match value {
-      RsvpStatus::Unkonwn => ReservationStatus::Unknown,
+      RsvpStatus::Unknown => ReservationStatus::Unknown,
       RsvpStatus::Confirmed => ReservationStatus::Confirmed,
       RsvpStatus::Pending => ReservationStatus::Pending,
       RsvpStatus::Blocked => ReservationStatus::Blocked,
+      RsvpStatus::Cancelled => ReservationStatus::Cancelled,
+      RsvpStatus::Expired => ReservationStatus::Expired,
+      RsvpStatus::Completed => ReservationStatus::Completed,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn allowed_transitions_should_pass() {
+    use ReservationStatus::*;
+    assert!(Pending.can_transition_to(Confirmed));
+    assert!(Pending.can_transition_to(Cancelled));
+    assert!(Pending.can_transition_to(Expired));
+    assert!(Confirmed.can_transition_to(Completed));
+    assert!(Confirmed.can_transition_to(Cancelled));
+    assert!(Blocked.can_transition_to(Cancelled));
+  }
+
+  #[test]
+  fn disallowed_transitions_should_fail() {
+    use ReservationStatus::*;
+    assert!(!Pending.can_transition_to(Completed));
+    assert!(!Confirmed.can_transition_to(Pending));
+    assert!(!Confirmed.can_transition_to(Expired));
+    assert!(!Cancelled.can_transition_to(Pending));
+    assert!(!Expired.can_transition_to(Confirmed));
+    assert!(!Completed.can_transition_to(Cancelled));
+    assert!(!Blocked.can_transition_to(Confirmed));
+    assert!(!Pending.can_transition_to(Pending));
+  }
+
+  #[test]
+  fn status_should_round_trip_through_string() {
+    for status in [
+      ReservationStatus::Unknown,
+      ReservationStatus::Pending,
+      ReservationStatus::Confirmed,
+      ReservationStatus::Blocked,
+      ReservationStatus::Cancelled,
+      ReservationStatus::Expired,
+      ReservationStatus::Completed,
+    ] {
+      assert_eq!(status.to_string().parse::<ReservationStatus>(), Ok(status));
     }
   }
 }
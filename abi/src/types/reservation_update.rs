@@ -0,0 +1,110 @@
+use prost_types::FieldMask;
+
+use crate::{Error, Reservation};
+
+impl Reservation {
+  /// copy the fields listed in `mask` from `update` into this reservation,
+  /// only note, start, end, resource_id, user_id and quantity can be updated
+  pub fn apply_update(
+    &mut self,
+    update: Reservation,
+    mask: &FieldMask,
+  ) -> Result<(), Error> {
+    if mask.paths.is_empty() {
+      return Err(Error::InvalidUpdateMask(String::new()));
+    }
+
+    for path in &mask.paths {
+      match path.as_str() {
+        "note" => self.note.clone_from(&update.note),
+        "start" => self.start = update.start,
+        "end" => self.end = update.end,
+        "resource_id" => self.resource_id.clone_from(&update.resource_id),
+        "user_id" => self.user_id.clone_from(&update.user_id),
+        // like a new reservation, 0 stands for a single unit
+        "quantity" if update.quantity < 0 => {
+          return Err(Error::InvalidQuantity(update.quantity))
+        }
+        "quantity" => self.quantity = update.quantity.max(1),
+        _ => return Err(Error::InvalidUpdateMask(path.clone())),
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::convert_local_time_to_utc;
+
+  fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+      paths: paths.iter().map(|p| p.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn apply_update_should_only_touch_masked_fields() {
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "room-1",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "old note",
+    );
+    let update = Reservation::new_pending(
+      "someoneElse",
+      "room-2",
+      convert_local_time_to_utc("2024-01-23 19:00:00"),
+      convert_local_time_to_utc("2024-01-24 12:00:00"),
+      "new note",
+    );
+
+    let mut expected = rsvp.clone();
+    expected.resource_id = "room-2".to_string();
+    expected.end = update.end;
+
+    rsvp
+      .apply_update(update, &mask(&["resource_id", "end"]))
+      .unwrap();
+    assert_eq!(rsvp, expected);
+  }
+
+  #[test]
+  fn apply_update_should_reject_unknown_or_empty_mask() {
+    let mut rsvp = Reservation::default();
+
+    let err = rsvp
+      .apply_update(Reservation::default(), &mask(&["status"]))
+      .unwrap_err();
+    assert!(matches!(err, Error::InvalidUpdateMask(p) if p == "status"));
+
+    let err = rsvp
+      .apply_update(Reservation::default(), &mask(&[]))
+      .unwrap_err();
+    assert_eq!(err, Error::InvalidUpdateMask(String::new()));
+  }
+
+  #[test]
+  fn apply_update_should_reject_negative_quantity() {
+    let mut rsvp = Reservation {
+      quantity: 2,
+      ..Default::default()
+    };
+
+    let update = Reservation {
+      quantity: -1,
+      ..Default::default()
+    };
+    let err = rsvp.apply_update(update, &mask(&["quantity"])).unwrap_err();
+    assert_eq!(err, Error::InvalidQuantity(-1));
+    assert_eq!(rsvp.quantity, 2);
+
+    rsvp
+      .apply_update(Reservation::default(), &mask(&["quantity"]))
+      .unwrap();
+    assert_eq!(rsvp.quantity, 1);
+  }
+}
@@ -0,0 +1,169 @@
+use prost_types::Duration;
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{utils::parse_timezone, Error, Resource, Validator};
+
+/// longest id the resources table accepts
+const MAX_RESOURCE_ID_LEN: usize = 64;
+
+impl Resource {
+  /// attributes as a JSON object, empty attributes are an empty object
+  pub fn get_attributes(&self) -> Result<serde_json::Value, Error> {
+    if self.attributes.is_empty() {
+      return Ok(serde_json::Value::Object(Default::default()));
+    }
+
+    match serde_json::from_str(&self.attributes) {
+      Ok(value @ serde_json::Value::Object(_)) => Ok(value),
+      _ => Err(Error::InvalidResource(format!(
+        "attributes must be a JSON object, got {}",
+        self.attributes
+      ))),
+    }
+  }
+
+  /// buffer before and after its reservations in whole seconds, empty
+  /// buffers are 0
+  pub fn get_buffers(&self) -> Result<(i32, i32), Error> {
+    let seconds = |buffer: Option<Duration>| match buffer {
+      None => Ok(0),
+      Some(Duration { seconds, nanos: 0 }) if seconds >= 0 => {
+        i32::try_from(seconds).map_err(|_| {
+          Error::InvalidResource(format!("buffer of {}s is too long", seconds))
+        })
+      }
+      Some(buffer) => Err(Error::InvalidResource(format!(
+        "buffer must be whole seconds and not negative, got {:?}",
+        buffer
+      ))),
+    };
+
+    Ok((seconds(self.buffer_before)?, seconds(self.buffer_after)?))
+  }
+}
+
+/// empty for no buffer
+fn buffer_from_seconds(seconds: i32) -> Option<Duration> {
+  (seconds > 0).then_some(Duration {
+    seconds: seconds as i64,
+    nanos: 0,
+  })
+}
+
+impl Validator for Resource {
+  fn validate(&self) -> Result<(), Error> {
+    if self.id.is_empty() || self.id.len() > MAX_RESOURCE_ID_LEN {
+      return Err(Error::InvalidResourceId(self.id.clone()));
+    }
+    if self.name.is_empty() {
+      return Err(Error::InvalidResource("name is required".to_string()));
+    }
+    if self.capacity < 1 {
+      return Err(Error::InvalidResource(format!(
+        "capacity must be positive, got {}",
+        self.capacity
+      )));
+    }
+    if !self.timezone.is_empty() {
+      parse_timezone(&self.timezone).map_err(|_| {
+        Error::InvalidResource(format!("unknown timezone {}", self.timezone))
+      })?;
+    }
+    if let Some(rules) = &self.rules {
+      rules.validate()?;
+    }
+    self.get_buffers()?;
+    self.get_attributes()?;
+    Ok(())
+  }
+}
+
+impl FromRow<'_, PgRow> for Resource {
+  fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+    let attributes: serde_json::Value = row.get("attributes");
+
+    Ok(Self {
+      id: row.get("id"),
+      name: row.get("name"),
+      resource_type: row.get("rtype"),
+      capacity: row.get("capacity"),
+      timezone: row.get("timezone"),
+      attributes: attributes.to_string(),
+      active: row.get("active"),
+      // stored in their own tables, loaded by the catalogue
+      rules: None,
+      buffer_before: buffer_from_seconds(row.get("buffer_before")),
+      buffer_after: buffer_from_seconds(row.get("buffer_after")),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn make_resource() -> Resource {
+    Resource {
+      id: "room-1".to_string(),
+      name: "Room 1".to_string(),
+      resource_type: "room".to_string(),
+      capacity: 1,
+      timezone: "Asia/Shanghai".to_string(),
+      attributes: r#"{"floor": 3}"#.to_string(),
+      active: true,
+      rules: None,
+      buffer_before: None,
+      buffer_after: None,
+    }
+  }
+
+  #[test]
+  fn resource_validate_should_work() {
+    assert!(make_resource().validate().is_ok());
+
+    let mut resource = make_resource();
+    resource.id = String::new();
+    assert_eq!(
+      resource.validate().unwrap_err(),
+      Error::InvalidResourceId(String::new())
+    );
+
+    let mut resource = make_resource();
+    resource.capacity = 0;
+    assert!(matches!(
+      resource.validate().unwrap_err(),
+      Error::InvalidResource(_)
+    ));
+
+    let mut resource = make_resource();
+    resource.timezone = "Asia/Atlantis".to_string();
+    assert!(matches!(
+      resource.validate().unwrap_err(),
+      Error::InvalidResource(_)
+    ));
+
+    let mut resource = make_resource();
+    resource.buffer_after = Some(Duration {
+      seconds: 60,
+      nanos: 500,
+    });
+    assert!(matches!(
+      resource.validate().unwrap_err(),
+      Error::InvalidResource(_)
+    ));
+
+    let mut resource = make_resource();
+    resource.attributes = "[1, 2]".to_string();
+    assert!(matches!(
+      resource.validate().unwrap_err(),
+      Error::InvalidResource(_)
+    ));
+  }
+
+  #[test]
+  fn empty_attributes_should_be_empty_object() {
+    let mut resource = make_resource();
+    resource.attributes = String::new();
+    assert_eq!(resource.get_attributes().unwrap(), serde_json::json!({}));
+  }
+}
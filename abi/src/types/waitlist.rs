@@ -0,0 +1,65 @@
+use std::ops::Bound;
+
+use sqlx::{
+  postgres::{types::PgRange, PgRow},
+  types::chrono::{DateTime, Utc},
+  FromRow, Row,
+};
+
+use crate::{
+  utils::convert_to_timestamp, Reservation, RsvpWaitlistStatus, WaitlistEntry,
+  WaitlistStatus,
+};
+
+impl From<RsvpWaitlistStatus> for WaitlistStatus {
+  fn from(value: RsvpWaitlistStatus) -> Self {
+    match value {
+      RsvpWaitlistStatus::Waiting => WaitlistStatus::Waiting,
+      RsvpWaitlistStatus::Promoted => WaitlistStatus::Promoted,
+      RsvpWaitlistStatus::Withdrawn => WaitlistStatus::Withdrawn,
+    }
+  }
+}
+
+impl WaitlistEntry {
+  pub fn is_waiting(&self) -> bool {
+    self.status == WaitlistStatus::Waiting as i32
+  }
+}
+
+impl FromRow<'_, PgRow> for WaitlistEntry {
+  fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+    let timespan: PgRange<DateTime<Utc>> = row.get("timespan");
+    let bound = |bound| match bound {
+      Bound::Included(v) | Bound::Excluded(v) => Some(v),
+      Bound::Unbounded => None,
+    };
+    let start = bound(timespan.start);
+    let end = bound(timespan.end);
+    let status: RsvpWaitlistStatus = row.get("status");
+
+    let mut rsvp = Reservation {
+      user_id: row.get("user_id"),
+      resource_id: row.get("resource_id"),
+      start: start.map(convert_to_timestamp),
+      end: end.map(convert_to_timestamp),
+      note: row.get::<Option<String>, _>("note").unwrap_or_default(),
+      quantity: row.get("quantity"),
+      timezone: row.get("timezone"),
+      ..Default::default()
+    };
+    if let Ok(Some(tz)) = rsvp.get_timezone() {
+      rsvp.set_local_times(tz);
+    }
+
+    Ok(Self {
+      id: row.get("id"),
+      reservation: Some(rsvp),
+      status: WaitlistStatus::from(status) as i32,
+      reservation_id: row
+        .get::<Option<i64>, _>("reservation_id")
+        .unwrap_or_default(),
+      created_at: Some(convert_to_timestamp(row.get("created_at"))),
+    })
+  }
+}
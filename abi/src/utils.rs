@@ -1,6 +1,23 @@
+use chrono::{Duration, LocalResult};
+use chrono_tz::Tz;
 use prost_types::Timestamp;
 use sqlx::types::chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 
+use crate::Error;
+
+/// which instant to pick for a wall clock time that happens twice, e.g.
+/// 01:30 on the night daylight saving time ends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ambiguity {
+  /// the first one, before the clocks are turned back
+  #[default]
+  Earliest,
+  /// the second one, after the clocks are turned back
+  Latest,
+  /// refuse to guess
+  Reject,
+}
+
 /// conversion
 pub fn convert_to_utc_time(ts: Timestamp) -> DateTime<Utc> {
   DateTime::<Utc>::from_timestamp(ts.seconds, ts.nanos as _).unwrap()
@@ -13,6 +30,46 @@ pub fn convert_to_timestamp(time: DateTime<Utc>) -> Timestamp {
   }
 }
 
+pub fn convert_to_duration(duration: prost_types::Duration) -> Duration {
+  Duration::seconds(duration.seconds)
+    + Duration::nanoseconds(duration.nanos as _)
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, Error> {
+  name
+    .parse()
+    .map_err(|_| Error::InvalidTimezone(name.to_string()))
+}
+
+/// wall clock `time` in `tz` to UTC, times skipped by a daylight saving
+/// gap are an error and repeated ones are resolved by `ambiguity`
+pub fn convert_tz_time_to_utc(
+  time: NaiveDateTime,
+  tz: Tz,
+  ambiguity: Ambiguity,
+) -> Result<DateTime<Utc>, Error> {
+  let invalid =
+    |reason| Error::InvalidLocalTime(format!("{} {} in {}", time, reason, tz));
+  let time = match (tz.from_local_datetime(&time), ambiguity) {
+    (LocalResult::Single(time), _) => time,
+    (LocalResult::Ambiguous(time, _), Ambiguity::Earliest) => time,
+    (LocalResult::Ambiguous(_, time), Ambiguity::Latest) => time,
+    (LocalResult::Ambiguous(..), Ambiguity::Reject) => {
+      return Err(invalid("is ambiguous"))
+    }
+    (LocalResult::None, _) => return Err(invalid("does not exist")),
+  };
+
+  Ok(time.with_timezone(&Utc))
+}
+
+/// `time` in `tz` as RFC 3339, e.g. 2024-01-21T09:00:00+09:00
+pub fn format_tz_time(time: DateTime<Utc>, tz: Tz) -> String {
+  time.with_timezone(&tz).to_rfc3339()
+}
+
+/// time in the zone of the server, only meant for tests and tools, use
+/// `convert_tz_time_to_utc` for times given by users
 pub fn convert_local_time_to_utc(time: &str) -> DateTime<Utc> {
   let native_time =
     NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").unwrap();
@@ -22,3 +79,61 @@ pub fn convert_local_time_to_utc(time: &str) -> DateTime<Utc> {
     .expect("convert_local_time_to_utc error")
     .with_timezone(&Utc)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn naive(time: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").unwrap()
+  }
+
+  #[test]
+  fn convert_tz_time_should_handle_dst() {
+    let tz = parse_timezone("America/New_York").unwrap();
+
+    let time = convert_tz_time_to_utc(
+      naive("2024-07-01 09:00:00"),
+      tz,
+      Ambiguity::Reject,
+    );
+    assert_eq!(time.unwrap().to_rfc3339(), "2024-07-01T13:00:00+00:00");
+
+    // clocks jump from 02:00 to 03:00
+    let gap = naive("2024-03-10 02:30:00");
+    assert!(matches!(
+      convert_tz_time_to_utc(gap, tz, Ambiguity::Earliest),
+      Err(Error::InvalidLocalTime(_))
+    ));
+
+    // clocks go back from 02:00 to 01:00
+    let overlap = naive("2024-11-03 01:30:00");
+    let earliest =
+      convert_tz_time_to_utc(overlap, tz, Ambiguity::Earliest).unwrap();
+    let latest =
+      convert_tz_time_to_utc(overlap, tz, Ambiguity::Latest).unwrap();
+    assert_eq!(latest - earliest, Duration::hours(1));
+    assert!(matches!(
+      convert_tz_time_to_utc(overlap, tz, Ambiguity::Reject),
+      Err(Error::InvalidLocalTime(_))
+    ));
+  }
+
+  #[test]
+  fn parse_timezone_should_reject_unknown_names() {
+    assert_eq!(parse_timezone("Asia/Tokyo").unwrap(), Tz::Asia__Tokyo);
+    assert_eq!(
+      parse_timezone("Asia/Atlantis").unwrap_err(),
+      Error::InvalidTimezone("Asia/Atlantis".to_string())
+    );
+  }
+
+  #[test]
+  fn format_tz_time_should_use_local_offset() {
+    let time = "2024-01-21T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    assert_eq!(
+      format_tz_time(time, Tz::Asia__Tokyo),
+      "2024-01-21T09:00:00+09:00"
+    );
+  }
+}
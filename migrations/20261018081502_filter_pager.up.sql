@@ -0,0 +1,54 @@
+-- Add up migration script here
+-- cursor is exclusive: rows after the cursor (in the given order) are returned
+CREATE OR REPLACE FUNCTION rsvp.filter(
+    uid text,
+    rid text,
+    status rsvp.reservation_status,
+    cursor bigInt DEFAULT NULL,
+    page_size integer DEFAULT 10,
+    is_desc bool DEFAULT FALSE
+) RETURNS TABLE (LIKE rsvp.reservations) AS $$
+DECLARE
+    _sql text;
+BEGIN
+
+    IF page_size < 10 OR page_size > 100 THEN
+        page_size := 10; -- Default page size
+    END IF;
+
+    _sql := format(
+        'SELECT * FROM rsvp.reservations WHERE %s AND status = %L AND %s ORDER BY id %s LIMIT %L::integer',
+        CASE
+            WHEN cursor IS NULL OR cursor <= 0 THEN 'TRUE'
+            WHEN is_desc THEN 'id < ' || cursor
+            ELSE 'id > ' || cursor
+        END,
+        status,
+        CASE
+            WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+            WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+            WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+            ELSE 'resource_id = ' || quote_literal(rid) || ' AND user_id = ' || quote_literal(uid)
+        END,
+        CASE WHEN is_desc THEN 'DESC' ELSE 'ASC' END,
+        page_size + 1
+    );
+
+    -- log _sql;
+    RAISE NOTICE 'Executing SQL: %', _sql;
+
+    RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
+
+-- total number of reservations matching the filter, regardless of the cursor
+CREATE OR REPLACE FUNCTION rsvp.filter_count(
+    uid text,
+    rid text,
+    status rsvp.reservation_status
+) RETURNS bigint AS $$
+    SELECT COUNT(*) FROM rsvp.reservations r
+    WHERE r.status = filter_count.status
+        AND (uid IS NULL OR r.user_id = uid)
+        AND (rid IS NULL OR r.resource_id = rid);
+$$ LANGUAGE sql;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+-- postgres can't drop enum values, cancelled/expired/completed are kept
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict
+    EXCLUDE USING gist (resource_id WITH =, timespan WITH &&);
@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TYPE rsvp.reservation_status ADD VALUE 'cancelled';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'expired';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'completed';
+
+-- only active reservations hold the time slot of a resource
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict
+    EXCLUDE USING gist (resource_id WITH =, timespan WITH &&)
+    WHERE (status IN ('pending', 'confirmed', 'blocked'));
@@ -0,0 +1,19 @@
+-- Add down migration script here
+CREATE OR REPLACE FUNCTION rsvp.reservation_trigger() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op) VALUES (NEW.id, null, to_jsonb(NEW), 'create');
+    ELSIF TG_OP = 'UPDATE' THEN
+        IF OLD.status <> NEW.status THEN
+            INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op) VALUES (NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update');
+        END IF;
+   ELSIF TG_OP = 'DELETE' THEN
+        INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op)
+        VALUES (OLD.id, to_jsonb(OLD), null, 'delete');
+    END IF;
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE rsvp.reservations_changes DROP COLUMN operator;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+-- who triggered the change, taken from the transaction local `rsvp.operator` setting
+ALTER TABLE rsvp.reservations_changes ADD COLUMN operator VARCHAR(64);
+
+CREATE OR REPLACE FUNCTION rsvp.reservation_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _operator VARCHAR(64) := NULLIF(current_setting('rsvp.operator', true), '');
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op, operator) VALUES (NEW.id, null, to_jsonb(NEW), 'create', _operator);
+    ELSIF TG_OP = 'UPDATE' THEN
+        -- every update is logged for listeners, only transitions are made by
+        -- an operator
+        INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op, operator)
+        VALUES (
+            NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update',
+            CASE WHEN OLD.status <> NEW.status THEN _operator END
+        );
+   ELSIF TG_OP = 'DELETE' THEN
+        INSERT INTO rsvp.reservations_changes (reservation_id, old, new, op, operator)
+        VALUES (OLD.id, to_jsonb(OLD), null, 'delete', _operator);
+    END IF;
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,91 @@
+-- Add up migration script here
+ALTER TABLE rsvp.reservations
+    ADD COLUMN cancelled_at TIMESTAMPTZ,
+    ADD COLUMN cancelled_by VARCHAR(64),
+    ADD COLUMN reason TEXT;
+
+-- `TABLE (LIKE ...)` is resolved when the function is created, use the row
+-- type of rsvp.reservations instead so new columns are returned as well
+DROP FUNCTION rsvp.query;
+DROP FUNCTION rsvp.filter;
+
+CREATE OR REPLACE FUNCTION rsvp.query(
+    uid text,
+    rid text,
+    status rsvp.reservation_status,
+    during TSTZRANGE,
+    page integer DEFAULT 1,
+    page_size integer DEFAULT 10,
+    is_desc bool DEFAULT FALSE
+) RETURNS SETOF rsvp.reservations AS $$
+DECLARE
+    _sql text;
+BEGIN
+    IF page_size < 10 OR page_size > 100 THEN
+        page_size := 10; -- Default page size
+    END IF;
+    IF page < 1 THEN
+        page := 1;
+    END IF;
+
+    _sql := format(
+        'SELECT * FROM rsvp.reservations WHERE %L @> timespan AND status = %L AND %s ORDER BY lower(timespan) %s LIMIT %L::integer OFFSET %L::integer', 
+        during,
+        status,
+        CASE
+            WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+            WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+            WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+            ELSE 'resource_id = ' || quote_literal(rid) || ' AND user_id = ' || quote_literal(uid)
+        END,
+        CASE WHEN is_desc THEN 'DESC' ELSE 'ASC' END,
+        page_size, (page - 1) * page_size
+    );
+
+    -- log _sql;
+    RAISE NOTICE 'Executing SQL: %', _sql;
+
+    RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION rsvp.filter(
+    uid text,
+    rid text,
+    status rsvp.reservation_status,
+    cursor bigInt DEFAULT NULL,
+    page_size integer DEFAULT 10,
+    is_desc bool DEFAULT FALSE
+) RETURNS SETOF rsvp.reservations AS $$
+DECLARE
+    _sql text;
+BEGIN
+
+    IF page_size < 10 OR page_size > 100 THEN
+        page_size := 10; -- Default page size
+    END IF;
+
+    _sql := format(
+        'SELECT * FROM rsvp.reservations WHERE %s AND status = %L AND %s ORDER BY id %s LIMIT %L::integer',
+        CASE
+            WHEN cursor IS NULL OR cursor <= 0 THEN 'TRUE'
+            WHEN is_desc THEN 'id < ' || cursor
+            ELSE 'id > ' || cursor
+        END,
+        status,
+        CASE
+            WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+            WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+            WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+            ELSE 'resource_id = ' || quote_literal(rid) || ' AND user_id = ' || quote_literal(uid)
+        END,
+        CASE WHEN is_desc THEN 'DESC' ELSE 'ASC' END,
+        page_size + 1
+    );
+
+    -- log _sql;
+    RAISE NOTICE 'Executing SQL: %', _sql;
+
+    RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
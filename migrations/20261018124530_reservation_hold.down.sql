@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX rsvp.reservation_pending_hold_until_idx;
+ALTER TABLE rsvp.reservations DROP COLUMN hold_until;
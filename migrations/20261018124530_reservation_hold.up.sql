@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- pending reservations are expired once hold_until has passed, NULL holds forever
+ALTER TABLE rsvp.reservations ADD COLUMN hold_until TIMESTAMPTZ;
+
+CREATE INDEX reservation_pending_hold_until_idx ON rsvp.reservations (hold_until) WHERE status = 'pending';
@@ -0,0 +1,23 @@
+-- Add up migration script here
+CREATE TABLE rsvp.resources (
+    id VARCHAR(64) PRIMARY KEY,
+    name VARCHAR(128) NOT NULL,
+    rtype VARCHAR(32) NOT NULL DEFAULT '',
+    capacity INTEGER NOT NULL DEFAULT 1,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    attributes JSONB NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT resources_capacity_positive CHECK (capacity > 0),
+    CONSTRAINT resources_attributes_object CHECK (jsonb_typeof(attributes) = 'object')
+);
+
+-- existing reservations keep working against a catalogue entry of the same id
+INSERT INTO rsvp.resources (id, name)
+SELECT DISTINCT resource_id, resource_id FROM rsvp.reservations;
+
+ALTER TABLE rsvp.reservations
+    ADD CONSTRAINT reservations_resource_id_fkey
+    FOREIGN KEY (resource_id) REFERENCES rsvp.resources (id);
@@ -0,0 +1,48 @@
+-- Add down migration script here
+CREATE OR REPLACE FUNCTION rsvp.availability(
+    rid text,
+    during TSTZRANGE,
+    min_duration INTERVAL DEFAULT NULL,
+    granularity INTERVAL DEFAULT NULL
+) RETURNS TABLE (resource_id VARCHAR(64), start_at TIMESTAMPTZ, end_at TIMESTAMPTZ) AS $$
+    WITH busy AS (
+        SELECT COALESCE(range_agg(r.timespan), '{}'::tstzmultirange) AS spans
+        FROM rsvp.reservations r
+        WHERE r.resource_id = rid
+            AND r.timespan && during
+            AND r.status IN ('pending', 'confirmed', 'blocked')
+    ), free AS (
+        SELECT lower(slot) AS start_at, upper(slot) AS end_at
+        FROM busy, unnest(tstzmultirange(during) - busy.spans) AS slot
+    ), aligned AS (
+        SELECT
+            CASE
+                WHEN granularity IS NULL THEN f.start_at
+                WHEN date_bin(granularity, f.start_at, lower(during)) = f.start_at THEN f.start_at
+                ELSE date_bin(granularity, f.start_at, lower(during)) + granularity
+            END AS start_at,
+            CASE
+                WHEN granularity IS NULL THEN f.end_at
+                ELSE date_bin(granularity, f.end_at, lower(during))
+            END AS end_at
+        FROM free f
+    )
+    SELECT rid::VARCHAR(64), a.start_at, a.end_at
+    FROM aligned a
+    WHERE a.end_at > a.start_at
+        AND (min_duration IS NULL OR a.end_at - a.start_at >= min_duration)
+    ORDER BY a.start_at;
+$$ LANGUAGE sql STABLE;
+
+DROP TRIGGER reservation_capacity_trigger ON rsvp.reservations;
+DROP FUNCTION rsvp.reservation_capacity_trigger;
+DROP FUNCTION rsvp.capacity_used;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict
+    EXCLUDE USING gist (resource_id WITH =, timespan WITH &&)
+    WHERE (status IN ('pending', 'confirmed', 'blocked'));
+
+ALTER TABLE rsvp.reservations
+    DROP COLUMN shared,
+    DROP COLUMN quantity;
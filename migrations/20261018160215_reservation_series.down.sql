@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX rsvp.reservation_series_id_idx;
+ALTER TABLE rsvp.reservations DROP COLUMN series_id;
+DROP TABLE rsvp.reservation_series;
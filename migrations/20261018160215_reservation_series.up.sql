@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- a recurring reservation, its occurrences are rows of rsvp.reservations
+CREATE TABLE rsvp.reservation_series (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(64) NOT NULL,
+    resource_id VARCHAR(64) NOT NULL,
+    rrule TEXT NOT NULL,
+    exdates TIMESTAMPTZ[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TABLE rsvp.reservations
+    ADD COLUMN series_id BIGINT REFERENCES rsvp.reservation_series (id);
+
+CREATE INDEX reservation_series_id_idx ON rsvp.reservations (series_id, lower(timespan))
+    WHERE series_id IS NOT NULL;
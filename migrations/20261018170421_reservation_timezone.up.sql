@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- IANA timezone the reservation was made in, used to show local times
+ALTER TABLE rsvp.reservations
+    ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
+
+UPDATE rsvp.reservations r SET timezone = res.timezone
+    FROM rsvp.resources res WHERE res.id = r.resource_id;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE rsvp.blackouts;
+DROP TABLE rsvp.opening_hours;
+ALTER TABLE rsvp.resources
+    DROP COLUMN min_duration,
+    DROP COLUMN max_duration,
+    DROP COLUMN min_lead_time,
+    DROP COLUMN max_horizon;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- limits on reservations of a resource, NULL for no limit
+ALTER TABLE rsvp.resources
+    ADD COLUMN min_duration INTERVAL,
+    ADD COLUMN max_duration INTERVAL,
+    ADD COLUMN min_lead_time INTERVAL,
+    ADD COLUMN max_horizon INTERVAL;
+
+-- local times a resource is open, resources without any are always open
+CREATE TABLE rsvp.opening_hours (
+    resource_id VARCHAR(64) NOT NULL REFERENCES rsvp.resources (id) ON DELETE CASCADE,
+    -- ISO weekday, 1 for monday to 7 for sunday
+    weekday SMALLINT NOT NULL,
+    opens TIME NOT NULL,
+    -- 00:00 is midnight at the end of the day
+    closes TIME NOT NULL,
+
+    CONSTRAINT opening_hours_weekday CHECK (weekday BETWEEN 1 AND 7),
+    CONSTRAINT opening_hours_order CHECK (opens < closes OR closes = '00:00')
+);
+
+CREATE INDEX opening_hours_resource_id_idx ON rsvp.opening_hours (resource_id);
+
+-- periods a resource can't be booked, e.g. holidays
+CREATE TABLE rsvp.blackouts (
+    id BIGSERIAL PRIMARY KEY,
+    resource_id VARCHAR(64) NOT NULL REFERENCES rsvp.resources (id) ON DELETE CASCADE,
+    during TSTZRANGE NOT NULL,
+    reason TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX blackouts_resource_id_idx ON rsvp.blackouts (resource_id);
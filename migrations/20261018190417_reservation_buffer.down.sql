@@ -0,0 +1,129 @@
+-- Add down migration script here
+CREATE OR REPLACE FUNCTION rsvp.capacity_used(
+    rid text,
+    during TSTZRANGE,
+    exclude_id bigint DEFAULT 0
+) RETURNS integer AS $$
+    WITH active AS (
+        SELECT timespan, quantity FROM rsvp.reservations
+        WHERE resource_id = rid
+            AND id <> exclude_id
+            AND timespan && during
+            AND status IN ('pending', 'confirmed', 'blocked')
+    ), points AS (
+        -- usage only grows where a reservation starts
+        SELECT lower(during) AS t
+        UNION
+        SELECT lower(timespan) FROM active WHERE during @> lower(timespan)
+    )
+    SELECT COALESCE(max(used), 0)::integer FROM (
+        SELECT (SELECT sum(a.quantity) FROM active a WHERE a.timespan @> p.t) AS used
+        FROM points p
+    ) usage;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION rsvp.reservation_capacity_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _capacity integer;
+    _used integer;
+BEGIN
+    SELECT capacity INTO _capacity FROM rsvp.resources WHERE id = NEW.resource_id;
+    NEW.shared := COALESCE(_capacity, 1) > 1;
+
+    -- exclusive resources are left to reservation_conflict unless they are
+    -- asked for more than one unit
+    IF (NOT NEW.shared AND NEW.quantity = 1)
+        OR NEW.status NOT IN ('pending', 'confirmed', 'blocked') THEN
+        RETURN NEW;
+    END IF;
+
+    -- serialize writers of the same resource, so two transactions can't both
+    -- see enough capacity left
+    PERFORM pg_advisory_xact_lock(hashtext('rsvp.resources'), hashtext(NEW.resource_id));
+    -- update_resource takes the lock too, read the capacity again in case it
+    -- was changed while waiting
+    SELECT capacity INTO _capacity FROM rsvp.resources WHERE id = NEW.resource_id;
+
+    _used := rsvp.capacity_used(NEW.resource_id, NEW.timespan, NEW.id);
+    IF _used + NEW.quantity > COALESCE(_capacity, 1) THEN
+        RAISE EXCEPTION 'capacity of resource % exceeded', NEW.resource_id
+            USING ERRCODE = 'RV001',
+                  DETAIL = json_build_object(
+                      'resource_id', NEW.resource_id,
+                      'start', lower(NEW.timespan),
+                      'end', upper(NEW.timespan),
+                      'capacity', COALESCE(_capacity, 1),
+                      'available', GREATEST(COALESCE(_capacity, 1) - _used, 0),
+                      'requested', NEW.quantity
+                  )::text;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION rsvp.availability(
+    rid text,
+    during TSTZRANGE,
+    min_duration INTERVAL DEFAULT NULL,
+    granularity INTERVAL DEFAULT NULL
+) RETURNS TABLE (resource_id VARCHAR(64), start_at TIMESTAMPTZ, end_at TIMESTAMPTZ) AS $$
+    WITH active AS (
+        SELECT r.timespan, r.quantity
+        FROM rsvp.reservations r
+        WHERE r.resource_id = rid
+            AND r.timespan && during
+            AND r.status IN ('pending', 'confirmed', 'blocked')
+    ), points AS (
+        SELECT lower(during) AS t
+        UNION SELECT lower(timespan) FROM active
+        UNION SELECT upper(timespan) FROM active
+    ), segments AS (
+        SELECT tstzrange(t, lead(t) OVER (ORDER BY t)) AS seg, lead(t) OVER (ORDER BY t) AS next
+        FROM points
+    ), busy AS (
+        SELECT COALESCE(range_agg(s.seg), '{}'::tstzmultirange) AS spans
+        FROM segments s
+        WHERE s.next IS NOT NULL
+            AND (SELECT COALESCE(sum(a.quantity), 0) FROM active a WHERE a.timespan && s.seg)
+                >= COALESCE((SELECT capacity FROM rsvp.resources WHERE id = rid), 1)
+    ), free AS (
+        SELECT lower(slot) AS start_at, upper(slot) AS end_at
+        FROM busy, unnest(tstzmultirange(during) - busy.spans) AS slot
+    ), aligned AS (
+        SELECT
+            CASE
+                WHEN granularity IS NULL THEN f.start_at
+                WHEN date_bin(granularity, f.start_at, lower(during)) = f.start_at THEN f.start_at
+                ELSE date_bin(granularity, f.start_at, lower(during)) + granularity
+            END AS start_at,
+            CASE
+                WHEN granularity IS NULL THEN f.end_at
+                ELSE date_bin(granularity, f.end_at, lower(during))
+            END AS end_at
+        FROM free f
+    )
+    SELECT rid::VARCHAR(64), a.start_at, a.end_at
+    FROM aligned a
+    WHERE a.end_at > a.start_at
+        AND (min_duration IS NULL OR a.end_at - a.start_at >= min_duration)
+    ORDER BY a.start_at;
+$$ LANGUAGE sql STABLE;
+
+DROP TRIGGER reservation_buffer_trigger ON rsvp.reservations;
+DROP FUNCTION rsvp.reservation_buffer_trigger;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_buffer_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict
+    EXCLUDE USING gist (resource_id WITH =, timespan WITH &&)
+    WHERE (status IN ('pending', 'confirmed', 'blocked') AND NOT shared);
+ALTER TABLE rsvp.reservations
+    DROP COLUMN padded_span,
+    DROP COLUMN buffer_before,
+    DROP COLUMN buffer_after;
+DROP FUNCTION rsvp.pad_timespan;
+
+ALTER TABLE rsvp.resources
+    DROP CONSTRAINT resources_buffer_not_negative,
+    DROP COLUMN buffer_before,
+    DROP COLUMN buffer_after;
@@ -0,0 +1,181 @@
+-- Add up migration script here
+-- turnaround time kept free before and after every reservation of a
+-- resource, e.g. for cleaning. whole seconds, so padding a timespan with
+-- them doesn't depend on the session timezone
+ALTER TABLE rsvp.resources
+    ADD COLUMN buffer_before INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN buffer_after INTEGER NOT NULL DEFAULT 0,
+    ADD CONSTRAINT resources_buffer_not_negative CHECK (buffer_before >= 0 AND buffer_after >= 0);
+
+-- adding seconds gives the same result in every timezone, unlike days or
+-- months, so this can be used by a generated column
+CREATE OR REPLACE FUNCTION rsvp.pad_timespan(
+    timespan TSTZRANGE,
+    before_secs integer,
+    after_secs integer
+) RETURNS TSTZRANGE AS $$
+    SELECT tstzrange(
+        lower(timespan) - before_secs * interval '1 second',
+        upper(timespan) + after_secs * interval '1 second'
+    );
+$$ LANGUAGE sql IMMUTABLE;
+
+-- buffers of the resource when the reservation was made or last moved, and
+-- the span it keeps the resource busy for
+ALTER TABLE rsvp.reservations
+    ADD COLUMN buffer_before INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN buffer_after INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN padded_span TSTZRANGE
+        GENERATED ALWAYS AS (rsvp.pad_timespan(timespan, buffer_before, buffer_after)) STORED;
+
+-- padded spans cover the timespans, so this replaces reservation_conflict.
+-- whether only the buffers overlap is found out by looking the clashing
+-- reservations up
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_buffer_conflict
+    EXCLUDE USING gist (resource_id WITH =, padded_span WITH &&)
+    WHERE (status IN ('pending', 'confirmed', 'blocked') AND NOT shared);
+
+CREATE OR REPLACE FUNCTION rsvp.reservation_buffer_trigger() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT'
+        OR NEW.resource_id <> OLD.resource_id
+        OR NEW.timespan <> OLD.timespan THEN
+        NEW.buffer_before := COALESCE((SELECT buffer_before FROM rsvp.resources WHERE id = NEW.resource_id), 0);
+        NEW.buffer_after := COALESCE((SELECT buffer_after FROM rsvp.resources WHERE id = NEW.resource_id), 0);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- named to fire before reservation_capacity_trigger, which needs the buffers
+CREATE TRIGGER reservation_buffer_trigger
+    BEFORE INSERT OR UPDATE ON rsvp.reservations
+    FOR EACH ROW EXECUTE PROCEDURE rsvp.reservation_buffer_trigger();
+
+-- shared resources count a unit as taken during the buffers as well
+CREATE OR REPLACE FUNCTION rsvp.capacity_used(
+    rid text,
+    during TSTZRANGE,
+    exclude_id bigint DEFAULT 0
+) RETURNS integer AS $$
+    WITH active AS (
+        SELECT padded_span, quantity FROM rsvp.reservations
+        WHERE resource_id = rid
+            AND id <> exclude_id
+            AND padded_span && during
+            AND status IN ('pending', 'confirmed', 'blocked')
+    ), points AS (
+        -- usage only grows where a reservation starts
+        SELECT lower(during) AS t
+        UNION
+        SELECT lower(padded_span) FROM active WHERE during @> lower(padded_span)
+    )
+    SELECT COALESCE(max(used), 0)::integer FROM (
+        SELECT (SELECT sum(a.quantity) FROM active a WHERE a.padded_span @> p.t) AS used
+        FROM points p
+    ) usage;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION rsvp.reservation_capacity_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _capacity integer;
+    _used integer;
+BEGIN
+    SELECT capacity INTO _capacity FROM rsvp.resources WHERE id = NEW.resource_id;
+    NEW.shared := COALESCE(_capacity, 1) > 1;
+
+    -- exclusive resources are left to reservation_buffer_conflict unless they
+    -- are asked for more than one unit
+    IF (NOT NEW.shared AND NEW.quantity = 1)
+        OR NEW.status NOT IN ('pending', 'confirmed', 'blocked') THEN
+        RETURN NEW;
+    END IF;
+
+    -- serialize writers of the same resource, so two transactions can't both
+    -- see enough capacity left
+    PERFORM pg_advisory_xact_lock(hashtext('rsvp.resources'), hashtext(NEW.resource_id));
+    -- update_resource takes the lock too, read the capacity again in case it
+    -- was changed while waiting
+    SELECT capacity INTO _capacity FROM rsvp.resources WHERE id = NEW.resource_id;
+
+    -- generated columns aren't computed yet in BEFORE triggers
+    _used := rsvp.capacity_used(
+        NEW.resource_id,
+        rsvp.pad_timespan(NEW.timespan, NEW.buffer_before, NEW.buffer_after),
+        NEW.id
+    );
+    IF _used + NEW.quantity > COALESCE(_capacity, 1) THEN
+        RAISE EXCEPTION 'capacity of resource % exceeded', NEW.resource_id
+            USING ERRCODE = 'RV001',
+                  DETAIL = json_build_object(
+                      'resource_id', NEW.resource_id,
+                      'start', lower(NEW.timespan),
+                      'end', upper(NEW.timespan),
+                      'capacity', COALESCE(_capacity, 1),
+                      'available', GREATEST(COALESCE(_capacity, 1) - _used, 0),
+                      'requested', NEW.quantity
+                  )::text;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- a slot is free while a new reservation fits with its buffers, so busy
+-- spans are padded with the buffers of the resource the other way round
+CREATE OR REPLACE FUNCTION rsvp.availability(
+    rid text,
+    during TSTZRANGE,
+    min_duration INTERVAL DEFAULT NULL,
+    granularity INTERVAL DEFAULT NULL
+) RETURNS TABLE (resource_id VARCHAR(64), start_at TIMESTAMPTZ, end_at TIMESTAMPTZ) AS $$
+    WITH resource AS (
+        SELECT
+            COALESCE(max(capacity), 1) AS capacity,
+            COALESCE(max(buffer_before), 0) AS buffer_before,
+            COALESCE(max(buffer_after), 0) AS buffer_after
+        FROM rsvp.resources WHERE id = rid
+    ), active AS (
+        SELECT padded.timespan, r.quantity
+        FROM rsvp.reservations r, resource res,
+            LATERAL (SELECT rsvp.pad_timespan(r.padded_span, res.buffer_after, res.buffer_before) AS timespan) padded
+        WHERE r.resource_id = rid
+            AND padded.timespan && during
+            AND r.status IN ('pending', 'confirmed', 'blocked')
+    ), points AS (
+        SELECT lower(during) AS t
+        UNION SELECT lower(timespan) FROM active
+        UNION SELECT upper(timespan) FROM active
+    ), segments AS (
+        SELECT tstzrange(t, lead(t) OVER (ORDER BY t)) AS seg, lead(t) OVER (ORDER BY t) AS next
+        FROM points
+    ), busy AS (
+        SELECT COALESCE(range_agg(s.seg), '{}'::tstzmultirange) AS spans
+        FROM segments s
+        WHERE s.next IS NOT NULL
+            AND (SELECT COALESCE(sum(a.quantity), 0) FROM active a WHERE a.timespan && s.seg)
+                >= (SELECT capacity FROM resource)
+    ), free AS (
+        SELECT lower(slot) AS start_at, upper(slot) AS end_at
+        FROM busy, unnest(tstzmultirange(during) - busy.spans) AS slot
+    ), aligned AS (
+        SELECT
+            CASE
+                WHEN granularity IS NULL THEN f.start_at
+                WHEN date_bin(granularity, f.start_at, lower(during)) = f.start_at THEN f.start_at
+                ELSE date_bin(granularity, f.start_at, lower(during)) + granularity
+            END AS start_at,
+            CASE
+                WHEN granularity IS NULL THEN f.end_at
+                ELSE date_bin(granularity, f.end_at, lower(during))
+            END AS end_at
+        FROM free f
+    )
+    SELECT rid::VARCHAR(64), a.start_at, a.end_at
+    FROM aligned a
+    WHERE a.end_at > a.start_at
+        AND (min_duration IS NULL OR a.end_at - a.start_at >= min_duration)
+    ORDER BY a.start_at;
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE rsvp.waitlist;
+DROP TYPE rsvp.waitlist_status;
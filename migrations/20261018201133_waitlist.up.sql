@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TYPE rsvp.waitlist_status AS ENUM ('waiting', 'promoted', 'withdrawn');
+
+-- requests waiting for a taken window, promoted to a pending reservation in
+-- the order they joined once the window frees up
+CREATE TABLE rsvp.waitlist (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(64) NOT NULL,
+    resource_id VARCHAR(64) NOT NULL,
+    timespan TSTZRANGE NOT NULL,
+    note TEXT,
+    quantity INTEGER NOT NULL DEFAULT 1,
+    timezone VARCHAR(64) NOT NULL DEFAULT '',
+    status rsvp.waitlist_status NOT NULL DEFAULT 'waiting',
+    reservation_id BIGINT REFERENCES rsvp.reservations (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX waitlist_waiting_idx ON rsvp.waitlist USING gist (resource_id, timespan)
+    WHERE status = 'waiting';
@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- responses of requests sent with an idempotency key, a retry with the same
+-- key gets the stored response instead of running the request again
+CREATE TABLE rsvp.idempotency_keys (
+    -- keys are scoped to the user the request is made for
+    user_id VARCHAR(64) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    operation VARCHAR(64) NOT NULL,
+    -- sha256 of the request without its key, a reused key must come with the same request
+    request_hash BYTEA NOT NULL,
+    -- written in the transaction of the request, so a key is only ever kept
+    -- along with what the request changed
+    response BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX idempotency_keys_expires_at_idx ON rsvp.idempotency_keys (expires_at);
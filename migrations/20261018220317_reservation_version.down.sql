@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP TRIGGER reservation_version_trigger ON rsvp.reservations;
+DROP FUNCTION rsvp.reservation_version_trigger();
+
+ALTER TABLE rsvp.reservations
+    DROP COLUMN version,
+    DROP COLUMN updated_at;
@@ -0,0 +1,29 @@
+-- Add up migration script here
+-- bumped on every change a client can see, so a client can update a
+-- reservation only if it is still the version it read
+ALTER TABLE rsvp.reservations
+    ADD COLUMN version BIGINT NOT NULL DEFAULT 1,
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE OR REPLACE FUNCTION rsvp.reservation_version_trigger() RETURNS TRIGGER AS $$
+BEGIN
+    -- housekeeping, like moving reservations between the exclusion
+    -- constraint and the capacity check, leaves the version alone
+    IF (NEW.status, NEW.timespan, NEW.resource_id, NEW.user_id, NEW.note,
+        NEW.quantity, NEW.hold_until, NEW.timezone, NEW.series_id,
+        NEW.cancelled_at, NEW.cancelled_by, NEW.reason)
+        IS DISTINCT FROM
+       (OLD.status, OLD.timespan, OLD.resource_id, OLD.user_id, OLD.note,
+        OLD.quantity, OLD.hold_until, OLD.timezone, OLD.series_id,
+        OLD.cancelled_at, OLD.cancelled_by, OLD.reason) THEN
+        NEW.version := OLD.version + 1;
+        NEW.updated_at := now();
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reservation_version_trigger
+    BEFORE UPDATE ON rsvp.reservations
+    FOR EACH ROW EXECUTE PROCEDURE rsvp.reservation_version_trigger();
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX rsvp.reservations_changes_xid_id_idx;
+
+ALTER TABLE rsvp.reservations_changes DROP COLUMN xid;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- transaction that wrote the change. ids are handed out before commit, so
+-- listeners read changes in the order of their transactions, and only once
+-- every older transaction has ended
+ALTER TABLE rsvp.reservations_changes
+    ADD COLUMN xid xid8 NOT NULL DEFAULT pg_current_xact_id();
+
+CREATE INDEX reservations_changes_xid_id_idx ON rsvp.reservations_changes (xid, id);
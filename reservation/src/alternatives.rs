@@ -0,0 +1,286 @@
+use std::ops::Range;
+
+use crate::{Alternatives, ReservationManage};
+use abi::{convert_to_timestamp, AvailableWindow, Error, Validator};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::postgres::types::PgRange;
+
+/// how many days before and after a taken window alternatives are looked for
+const SEARCH_DAYS: i64 = 7;
+
+#[async_trait]
+impl Alternatives for ReservationManage {
+  async fn suggest_alternatives(
+    &self,
+    rsvp: &abi::Reservation,
+    limit: usize,
+    similar_resources: bool,
+  ) -> Result<Vec<AvailableWindow>, Error> {
+    rsvp.validate()?;
+    if limit == 0 {
+      return Ok(Vec::new());
+    }
+
+    let span = rsvp.get_timespan();
+    let now = Utc::now();
+    let search = (span.start - Duration::days(SEARCH_DAYS)).max(now)
+      ..span.end + Duration::days(SEARCH_DAYS);
+    if search.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let mut conn = self.pool.acquire().await?;
+    let mut resources = vec![rsvp.resource_id.clone()];
+    if similar_resources {
+      let similar: Vec<String> = sqlx::query_scalar(
+        "SELECT s.id FROM rsvp.resources r JOIN rsvp.resources s ON s.rtype = r.rtype AND s.id <> r.id WHERE r.id = $1 AND s.active AND s.capacity >= $2 ORDER BY s.id",
+      )
+      .bind(&rsvp.resource_id)
+      .bind(rsvp.quantity.max(1))
+      .fetch_all(&mut conn)
+      .await?;
+      resources.extend(similar);
+    }
+
+    let mut slots = Vec::new();
+    for resource_id in resources {
+      let rules = self.load_rules(&mut conn, &resource_id).await?;
+      let tz = self.resource_timezone(&mut conn, &resource_id).await?;
+
+      let during: PgRange<DateTime<Utc>> = search.clone().into();
+      let windows: Vec<AvailableWindow> =
+        sqlx::query_as("SELECT * FROM rsvp.availability($1, $2, $3)")
+          .bind(&resource_id)
+          .bind(during)
+          .bind(span.end - span.start)
+          .fetch_all(&mut conn)
+          .await?;
+
+      for window in windows {
+        let free = abi::convert_to_utc_time(window.start.unwrap_or_default())
+          ..abi::convert_to_utc_time(window.end.unwrap_or_default());
+        let nearest = slots_near(&free, &span)
+          .filter(|slot| {
+            rules
+              .as_ref()
+              .is_none_or(|rules| rules.violations(slot, now, tz).is_empty())
+          })
+          .take(limit)
+          .map(|slot| (resource_id.clone(), slot));
+        slots.extend(nearest);
+      }
+    }
+
+    // stable, so on a tie the requested resource comes first
+    slots.sort_by_key(|(_, slot)| (slot.start - span.start).abs());
+    slots.truncate(limit);
+
+    Ok(
+      slots
+        .into_iter()
+        .map(|(resource_id, slot)| AvailableWindow {
+          resource_id,
+          start: Some(convert_to_timestamp(slot.start)),
+          end: Some(convert_to_timestamp(slot.end)),
+        })
+        .collect(),
+    )
+  }
+}
+
+/// slots as long as `span` within `free`, nearest to it first
+fn slots_near(
+  free: &Range<DateTime<Utc>>,
+  span: &Range<DateTime<Utc>>,
+) -> impl Iterator<Item = Range<DateTime<Utc>>> {
+  let duration = span.end - span.start;
+  let (earliest, latest) = (free.start, free.end - duration);
+
+  let nearest = span.start.clamp(earliest, latest.max(earliest));
+  // keep moving away from the requested start
+  let step = if nearest < span.start {
+    -duration
+  } else {
+    duration
+  };
+  std::iter::successors(Some(nearest), move |start| Some(*start + step))
+    .take_while(move |start| earliest <= *start && *start <= latest)
+    .map(move |start| start..start + duration)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{BookingRules, OpeningHours, Reservation};
+  use chrono::Weekday;
+
+  fn time(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn slots_near_should_step_away_from_requested_start() {
+    let span = time("2030-01-07T10:00:00Z")..time("2030-01-07T11:00:00Z");
+
+    let before = time("2030-01-07T06:30:00Z")..time("2030-01-07T10:30:00Z");
+    assert_eq!(
+      slots_near(&before, &span).take(3).collect::<Vec<_>>(),
+      vec![
+        time("2030-01-07T09:30:00Z")..time("2030-01-07T10:30:00Z"),
+        time("2030-01-07T08:30:00Z")..time("2030-01-07T09:30:00Z"),
+        time("2030-01-07T07:30:00Z")..time("2030-01-07T08:30:00Z"),
+      ]
+    );
+
+    let after = time("2030-01-07T11:15:00Z")..time("2030-01-07T13:15:00Z");
+    assert_eq!(
+      slots_near(&after, &span).take(3).collect::<Vec<_>>(),
+      vec![
+        time("2030-01-07T11:15:00Z")..time("2030-01-07T12:15:00Z"),
+        time("2030-01-07T12:15:00Z")..time("2030-01-07T13:15:00Z"),
+      ]
+    );
+
+    let short = time("2030-01-07T11:15:00Z")..time("2030-01-07T12:00:00Z");
+    assert_eq!(slots_near(&short, &span).count(), 0);
+  }
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    for id in ["room-1", "room-2"] {
+      let resource = abi::Resource {
+        id: id.to_string(),
+        name: id.to_string(),
+        resource_type: "room".to_string(),
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+    }
+    let desk = abi::Resource {
+      id: "desk-1".to_string(),
+      name: "desk 1".to_string(),
+      resource_type: "desk".to_string(),
+      capacity: 1,
+      ..Default::default()
+    };
+    manager.create_resource(desk).await.unwrap();
+    manager
+  }
+
+  fn make_reservation(
+    resource_id: &str,
+    start: &str,
+    end: &str,
+  ) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      time(start),
+      time(end),
+      "",
+    )
+  }
+
+  fn windows(alternatives: &[AvailableWindow]) -> Vec<(&str, DateTime<Utc>)> {
+    alternatives
+      .iter()
+      .map(|w| {
+        (
+          w.resource_id.as_str(),
+          abi::convert_to_utc_time(w.start.unwrap()),
+        )
+      })
+      .collect()
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn suggest_alternatives_should_return_nearest_free_windows() {
+    let pool = make_manager(migrated_pool).await;
+    // 2030-01-07 is a monday, room-1 is taken 09:00 - 10:30 and 11:30 - 12:00
+    for (start, end) in [
+      ("2030-01-07T09:00:00Z", "2030-01-07T10:30:00Z"),
+      ("2030-01-07T11:30:00Z", "2030-01-07T12:00:00Z"),
+    ] {
+      pool
+        .reserve(make_reservation("room-1", start, end))
+        .await
+        .unwrap();
+    }
+    let rsvp = make_reservation(
+      "room-1",
+      "2030-01-07T10:00:00Z",
+      "2030-01-07T11:00:00Z",
+    );
+
+    let alternatives =
+      pool.suggest_alternatives(&rsvp, 3, false).await.unwrap();
+    assert_eq!(
+      windows(&alternatives),
+      vec![
+        ("room-1", time("2030-01-07T10:30:00Z")),
+        ("room-1", time("2030-01-07T08:00:00Z")),
+        ("room-1", time("2030-01-07T12:00:00Z")),
+      ]
+    );
+    assert_eq!(
+      abi::convert_to_utc_time(alternatives[1].end.unwrap()),
+      time("2030-01-07T09:00:00Z")
+    );
+
+    // the very same window on another room is nearest of all, desks aren't
+    // rooms
+    let alternatives = pool.suggest_alternatives(&rsvp, 2, true).await.unwrap();
+    assert_eq!(
+      windows(&alternatives),
+      vec![
+        ("room-2", time("2030-01-07T10:00:00Z")),
+        ("room-1", time("2030-01-07T10:30:00Z")),
+      ]
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn suggest_alternatives_should_keep_to_booking_rules() {
+    let pool = make_manager(migrated_pool).await;
+    let mut room = pool.get_resource("room-1").await.unwrap();
+    room.rules = Some(BookingRules {
+      opening_hours: vec![OpeningHours::new(Weekday::Mon, "09:00", "12:00")],
+      ..Default::default()
+    });
+    pool.update_resource(room).await.unwrap();
+    pool
+      .reserve(make_reservation(
+        "room-1",
+        "2030-01-07T09:00:00Z",
+        "2030-01-07T11:00:00Z",
+      ))
+      .await
+      .unwrap();
+
+    // only 11:00 - 12:00 is open and free that monday, the next nearest
+    // open hour is the monday before
+    let rsvp = make_reservation(
+      "room-1",
+      "2030-01-07T10:00:00Z",
+      "2030-01-07T11:00:00Z",
+    );
+    let alternatives =
+      pool.suggest_alternatives(&rsvp, 2, false).await.unwrap();
+    assert_eq!(
+      windows(&alternatives),
+      vec![
+        ("room-1", time("2030-01-07T11:00:00Z")),
+        ("room-1", time("2029-12-31T11:00:00Z")),
+      ]
+    );
+  }
+}
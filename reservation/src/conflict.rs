@@ -0,0 +1,185 @@
+use crate::ReservationManage;
+use abi::{
+  Error, ReservationConflict, ReservationConflictInfo, ReservationWindow,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{
+  postgres::{types::PgRange, PgRow},
+  FromRow, PgConnection, Row,
+};
+use std::ops::Bound;
+
+impl ReservationManage {
+  /// `error` of writing `rsvp`, with the reservations it clashes with looked
+  /// up if it is a conflict. the failed write has to be rolled back to a
+  /// savepoint first, so `conn` can still be queried
+  pub(crate) async fn identify_conflict(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+    error: Error,
+  ) -> Error {
+    let Error::ConflictReservation(info) = error else {
+      return error;
+    };
+    match self.find_conflict(conn, rsvp).await {
+      Ok(Some(conflict)) => Error::ConflictReservation(
+        ReservationConflictInfo::Parsed(Box::new(conflict)),
+      ),
+      Ok(None) => Error::ConflictReservation(info),
+      Err(e) => e,
+    }
+  }
+
+  /// the active reservations `rsvp` clashes with on its resource, including
+  /// their buffers. None if there are none (anymore)
+  pub(crate) async fn find_conflict(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+  ) -> Result<Option<ReservationConflict>, Error> {
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
+    let rows: Vec<PgRow> = sqlx::query(
+      r#"WITH new AS (
+          SELECT $2::tstzrange AS timespan,
+              rsvp.pad_timespan($2, COALESCE(max(buffer_before), 0), COALESCE(max(buffer_after), 0)) AS padded_span
+          FROM rsvp.resources WHERE id = $1
+      )
+      SELECT r.*, r.padded_span AS existing_padded, new.padded_span AS new_padded,
+          r.timespan && new.timespan AS overlaps
+      FROM rsvp.reservations r, new
+      WHERE r.resource_id = $1
+          AND r.id <> $3
+          AND r.status IN ('pending', 'confirmed', 'blocked')
+          AND NOT r.shared
+          AND r.padded_span && new.padded_span
+      ORDER BY lower(r.timespan), r.id"#,
+    )
+    .bind(&rsvp.resource_id)
+    .bind(timespan.clone())
+    .bind(rsvp.id)
+    .fetch_all(conn)
+    .await?;
+
+    let Some(first) = rows.first() else {
+      return Ok(None);
+    };
+    // buffer only conflicts report the spans including buffers
+    let buffer_only = !rows.iter().any(|row| row.get::<bool, _>("overlaps"));
+    let (new, old) = if buffer_only {
+      (first.get("new_padded"), first.get("existing_padded"))
+    } else {
+      let row = rows.iter().find(|row| row.get("overlaps")).unwrap_or(first);
+      (timespan, row.get("timespan"))
+    };
+    let existing = rows
+      .iter()
+      .map(abi::Reservation::from_row)
+      .collect::<Result<_, _>>()?;
+
+    Ok(Some(ReservationConflict {
+      new: window_of(&rsvp.resource_id, new),
+      old: window_of(&rsvp.resource_id, old),
+      buffer_only,
+      existing,
+    }))
+  }
+}
+
+fn window_of(rid: &str, range: PgRange<DateTime<Utc>>) -> ReservationWindow {
+  let time = |bound| match bound {
+    Bound::Included(time) | Bound::Excluded(time) => time,
+    Bound::Unbounded => DateTime::<Utc>::MIN_UTC,
+  };
+
+  ReservationWindow {
+    rid: rid.to_string(),
+    start: time(range.start),
+    end: time(range.end),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{Reservation, ReservationStatus};
+
+  fn make_reservation(user_id: &str, start: &str, end: &str) -> Reservation {
+    Reservation::new_pending(
+      user_id,
+      "room 7.13",
+      start.parse().unwrap(),
+      end.parse().unwrap(),
+      "",
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_conflict_should_return_every_clashing_reservation() {
+    let pool = ReservationManage::new(migrated_pool);
+    let resource = abi::Resource {
+      id: "room 7.13".to_string(),
+      name: "room 7.13".to_string(),
+      capacity: 1,
+      ..Default::default()
+    };
+    pool.create_resource(resource).await.unwrap();
+
+    let morning = pool
+      .reserve(make_reservation(
+        "aliceId",
+        "2024-01-08T09:00:00Z",
+        "2024-01-08T10:00:00Z",
+      ))
+      .await
+      .unwrap();
+    let noon = pool
+      .reserve(make_reservation(
+        "bobId",
+        "2024-01-08T11:00:00Z",
+        "2024-01-08T12:00:00Z",
+      ))
+      .await
+      .unwrap();
+    pool
+      .transition(noon.id, ReservationStatus::Confirmed, "", 0)
+      .await
+      .unwrap();
+
+    let err = pool
+      .reserve(make_reservation(
+        "carolId",
+        "2024-01-08T09:30:00Z",
+        "2024-01-08T11:30:00Z",
+      ))
+      .await
+      .unwrap_err();
+    let Error::ConflictReservation(ReservationConflictInfo::Parsed(conflict)) =
+      err
+    else {
+      panic!("expect parsed conflict, got {:?}", err);
+    };
+    assert_eq!(conflict.old.rid, "room 7.13");
+    assert_eq!(
+      conflict.old.start,
+      "2024-01-08T09:00:00Z".parse::<DateTime<Utc>>().unwrap()
+    );
+
+    let existing: Vec<_> = conflict
+      .existing
+      .iter()
+      .map(|rsvp| (rsvp.id, rsvp.user_id.as_str(), rsvp.status))
+      .collect();
+    assert_eq!(
+      existing,
+      vec![
+        (morning.id, "aliceId", ReservationStatus::Pending as i32),
+        (noon.id, "bobId", ReservationStatus::Confirmed as i32),
+      ]
+    );
+  }
+}
@@ -0,0 +1,115 @@
+use crate::{CalendarExchange, ReservationManage, Rsvp};
+use abi::{Error, ReservationStatus};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+
+#[async_trait]
+impl CalendarExchange for ReservationManage {
+  async fn export_ics(
+    &self,
+    query: abi::ReservationQuery,
+  ) -> Result<String, Error> {
+    let rsvps: Vec<abi::Reservation> =
+      self.query(query).await?.try_collect().await?;
+
+    Ok(abi::to_ics(&rsvps, Utc::now()))
+  }
+
+  async fn import_ics(
+    &self,
+    ics: &str,
+    user_id: &str,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let rsvps = abi::from_ics(ics)?
+      .into_iter()
+      // nothing to reserve for events that no longer hold their slot
+      .filter(|rsvp| {
+        ReservationStatus::try_from(rsvp.status)
+          .is_ok_and(|status| status.is_active())
+      })
+      .map(|mut rsvp| {
+        if !user_id.is_empty() {
+          rsvp.user_id = user_id.to_string();
+        }
+        rsvp
+      })
+      .collect();
+
+    self.reserve_batch(rsvps).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ResourceCatalogue;
+  use abi::{convert_local_time_to_utc, Reservation, ReservationQueryBuilder};
+  use prost_types::Timestamp;
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    for id in ["room-1", "room-2"] {
+      let resource = abi::Resource {
+        id: id.to_string(),
+        name: id.to_string(),
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+    }
+    manager
+  }
+
+  fn make_reservation(resource_id: &str, note: &str) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      note,
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn export_then_import_ics_should_work() {
+    let pool = make_manager(migrated_pool).await;
+    let rsvp = pool
+      .reserve(make_reservation("room-1", "team dinner, table 3"))
+      .await
+      .unwrap();
+    let cancelled = pool
+      .reserve(make_reservation("room-2", "cancelled"))
+      .await
+      .unwrap();
+    pool
+      .cancel(cancelled.id, "xiaozhangId", "", 0)
+      .await
+      .unwrap();
+
+    let query = ReservationQueryBuilder::default()
+      .user_id("xiaozhangId")
+      .status(ReservationStatus::Pending as i32)
+      .start("2024-01-01T00:00:00Z".parse::<Timestamp>().unwrap())
+      .end("2024-12-31T00:00:00Z".parse::<Timestamp>().unwrap())
+      .build()
+      .unwrap();
+    let ics = pool.export_ics(query).await.unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains(&format!("UID:{}@reservation", rsvp.id)));
+
+    // the slot is still taken by the exported reservation
+    let err = pool.import_ics(&ics, "xiaonanId").await.unwrap_err();
+    assert!(matches!(err, Error::BatchConflict(_)));
+
+    pool.cancel(rsvp.id, "xiaozhangId", "", 0).await.unwrap();
+    let imported = pool.import_ics(&ics, "xiaonanId").await.unwrap();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].user_id, "xiaonanId");
+    assert_eq!(imported[0].note, rsvp.note);
+    assert_eq!((imported[0].start, imported[0].end), (rsvp.start, rsvp.end));
+  }
+}
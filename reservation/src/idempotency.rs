@@ -0,0 +1,467 @@
+use crate::{Idempotency, ReservationManage, RsvpTx};
+use abi::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use prost::Message;
+use sqlx::PgConnection;
+use std::future::Future;
+
+/// longest key rsvp.idempotency_keys can store
+const MAX_KEY_LEN: usize = 255;
+/// how long to wait for the row of a key another request holds before the
+/// key is in use
+const CLAIM_TIMEOUT: &str = "1s";
+
+#[async_trait]
+impl Idempotency for ReservationManage {
+  async fn idempotent<R, M, E, F, Fut>(
+    &self,
+    user_id: &str,
+    key: &str,
+    operation: &str,
+    request: R,
+    op: F,
+  ) -> Result<M, E>
+  where
+    R: Message,
+    M: Message + Default,
+    E: From<Error> + Send,
+    F: FnOnce(RsvpTx, R) -> Fut + Send,
+    Fut: Future<Output = Result<(RsvpTx, M), E>> + Send,
+  {
+    if key.len() > MAX_KEY_LEN {
+      return Err(Error::InvalidIdempotencyKey(key.to_string()).into());
+    }
+
+    let mut tx = self.pool.begin().await.map_err(Error::from)?;
+    let encoded = request.encode_to_vec();
+    if !key.is_empty() {
+      // end the transaction before answering, so the key is free at once for
+      // the next request on another connection
+      match self
+        .claim_key(&mut tx, user_id, key, operation, &encoded)
+        .await
+      {
+        Ok(None) => {}
+        Ok(Some(response)) => {
+          tx.rollback().await.map_err(Error::from)?;
+          return Ok(response);
+        }
+        Err(e) => {
+          tx.rollback().await.map_err(Error::from)?;
+          return Err(e.into());
+        }
+      }
+    }
+
+    // a failed run rolls back its changes along with the claim
+    let (mut tx, response) = op(tx, request).await?;
+    if !key.is_empty() {
+      self
+        .answer_key(&mut tx, user_id, key, operation, &encoded, &response)
+        .await?;
+    }
+    tx.commit().await.map_err(Error::from)?;
+
+    Ok(response)
+  }
+
+  async fn purge_idempotency_keys(
+    &self,
+    now: DateTime<Utc>,
+  ) -> Result<u64, Error> {
+    let purged =
+      sqlx::query("DELETE FROM rsvp.idempotency_keys WHERE expires_at <= $1")
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+    Ok(purged)
+  }
+}
+
+impl ReservationManage {
+  /// claim `key` of `user_id` for running `request` until `conn` ends, or get
+  /// the response of the run that used it before. the row of the key is
+  /// locked, a new key gets one that is only ever committed with its
+  /// response. a key still being run is in use, an expired one is free again
+  async fn claim_key<M: Message + Default>(
+    &self,
+    conn: &mut PgConnection,
+    user_id: &str,
+    key: &str,
+    operation: &str,
+    request: &[u8],
+  ) -> Result<Option<M>, Error> {
+    sqlx::query("SELECT set_config('lock_timeout', $1, true)")
+      .bind(CLAIM_TIMEOUT)
+      .execute(&mut *conn)
+      .await?;
+
+    let inserted = sqlx::query(
+      r#"INSERT INTO rsvp.idempotency_keys (user_id, key, operation, request_hash, response, expires_at)
+      VALUES ($1, $2, $3, sha256($4), '', now() + $5)
+      ON CONFLICT (user_id, key) DO NOTHING"#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(operation)
+    .bind(request)
+    .bind(self.idempotency_ttl)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| key_in_use(e, key))?
+    .rows_affected();
+
+    // expired keys are locked as well, so only one request runs them again
+    let stored: Option<(String, bool, Vec<u8>, bool)> = if inserted == 0 {
+      sqlx::query_as(
+        "SELECT operation, request_hash = sha256($3), response, expires_at <= now() FROM rsvp.idempotency_keys WHERE user_id = $1 AND key = $2 FOR UPDATE",
+      )
+      .bind(user_id)
+      .bind(key)
+      .bind(request)
+      .fetch_optional(&mut *conn)
+      .await
+      .map_err(|e| key_in_use(e, key))?
+    } else {
+      None
+    };
+
+    sqlx::query("SET LOCAL lock_timeout TO DEFAULT")
+      .execute(conn)
+      .await?;
+
+    match stored {
+      None | Some((_, _, _, true)) => Ok(None),
+      Some((stored, true, response, _)) if stored == operation => {
+        let response = M::decode(response.as_slice())
+          .map_err(|e| Error::DbError(sqlx::Error::Decode(e.into())))?;
+        Ok(Some(response))
+      }
+      Some(_) => Err(Error::IdempotencyKeyMismatch(key.to_string())),
+    }
+  }
+
+  /// store the response to `request` under the claimed `key`, replacing its
+  /// placeholder or the expired run that used it before
+  async fn answer_key(
+    &self,
+    conn: &mut PgConnection,
+    user_id: &str,
+    key: &str,
+    operation: &str,
+    request: &[u8],
+    response: &impl Message,
+  ) -> Result<(), Error> {
+    sqlx::query(
+      r#"INSERT INTO rsvp.idempotency_keys (user_id, key, operation, request_hash, response, expires_at)
+      VALUES ($1, $2, $3, sha256($4), $5, now() + $6)
+      ON CONFLICT (user_id, key) DO UPDATE
+      SET operation = excluded.operation,
+          request_hash = excluded.request_hash,
+          response = excluded.response,
+          created_at = now(),
+          expires_at = excluded.expires_at"#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(operation)
+    .bind(request)
+    .bind(response.encode_to_vec())
+    .bind(self.idempotency_ttl)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+}
+
+/// `IdempotencyKeyInUse` if waiting for the row of `key` timed out
+fn key_in_use(e: sqlx::Error, key: &str) -> Error {
+  match e {
+    // lock_not_available
+    sqlx::Error::Database(e) if e.code().as_deref() == Some("55P03") => {
+      Error::IdempotencyKeyInUse(key.to_string())
+    }
+    e => e.into(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{Reservation, ReservationConflictInfo, ReservationStatus};
+  use chrono::Duration;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    let resource = abi::Resource {
+      id: "room-1".to_string(),
+      name: "room 1".to_string(),
+      capacity: 1,
+      ..Default::default()
+    };
+    manager.create_resource(resource).await.unwrap();
+    manager
+  }
+
+  fn make_reservation(start: &str, end: &str) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      "room-1",
+      start.parse().unwrap(),
+      end.parse().unwrap(),
+      "",
+    )
+  }
+
+  /// ids of the reservations `err` conflicts with
+  fn conflicting_ids(err: Error) -> Vec<i64> {
+    let Error::ConflictReservation(ReservationConflictInfo::Parsed(conflict)) =
+      err
+    else {
+      panic!("expect parsed conflict, got {:?}", err);
+    };
+    conflict.existing.iter().map(|rsvp| rsvp.id).collect()
+  }
+
+  /// reserve in the transaction of an idempotent request
+  async fn reserve_in(
+    pool: &ReservationManage,
+    mut tx: RsvpTx,
+    rsvp: Reservation,
+  ) -> Result<(RsvpTx, Reservation), Error> {
+    let rsvp = pool.reserve_in(&mut tx, rsvp).await?;
+    Ok((tx, rsvp))
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn retry_with_same_key_should_get_first_response() {
+    let pool = &make_manager(migrated_pool).await;
+    let runs = AtomicUsize::new(0);
+    let reserve = |tx, rsvp| {
+      runs.fetch_add(1, Ordering::SeqCst);
+      reserve_in(pool, tx, rsvp)
+    };
+
+    let rsvp = make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+    let first = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", rsvp.clone(), reserve)
+      .await
+      .unwrap();
+    let retry = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", rsvp.clone(), reserve)
+      .await
+      .unwrap();
+    assert_eq!(retry, first);
+    assert_eq!(first.status, ReservationStatus::Pending as i32);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    // the same key for something else is rejected, whatever the operation
+    let other =
+      make_reservation("2024-01-08T11:00:00Z", "2024-01-08T12:00:00Z");
+    let err = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", other, reserve)
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::IdempotencyKeyMismatch("key-1".to_string()));
+    let err = pool
+      .idempotent("xiaozhangId", "key-1", "update", rsvp.clone(), reserve)
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::IdempotencyKeyMismatch("key-1".to_string()));
+
+    // without a key every request runs
+    let err = pool
+      .idempotent("xiaozhangId", "", "reserve", rsvp, reserve)
+      .await
+      .unwrap_err();
+    assert_eq!(conflicting_ids(err), vec![first.id]);
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn failed_or_expired_requests_should_run_again() {
+    let pool = &make_manager(migrated_pool)
+      .await
+      .with_idempotency_ttl(Duration::zero());
+    let rsvp = make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+    let existing = pool.reserve(rsvp.clone()).await.unwrap();
+
+    let err = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", rsvp, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap_err();
+    assert_eq!(conflicting_ids(err), vec![existing.id]);
+    assert_eq!(pool.purge_idempotency_keys(Utc::now()).await.unwrap(), 0);
+
+    // the key of the failed request is free again, the response of this one
+    // expires at once so the retry runs again and conflicts with it
+    let other =
+      make_reservation("2024-01-08T11:00:00Z", "2024-01-08T12:00:00Z");
+    let reserved = pool
+      .idempotent(
+        "xiaozhangId",
+        "key-1",
+        "reserve",
+        other.clone(),
+        |tx, rsvp| reserve_in(pool, tx, rsvp),
+      )
+      .await
+      .unwrap();
+    assert_eq!(pool.purge_idempotency_keys(Utc::now()).await.unwrap(), 1);
+    let err = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", other, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap_err();
+    assert_eq!(conflicting_ids(err), vec![reserved.id]);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn retry_while_first_request_runs_should_reject() {
+    let pool = &make_manager(migrated_pool).await;
+    let rsvp = make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+
+    let err = pool
+      .idempotent(
+        "xiaozhangId",
+        "key-1",
+        "reserve",
+        rsvp.clone(),
+        |tx, rsvp| async move {
+          let rsvp = pool
+            .idempotent("xiaozhangId", "key-1", "reserve", rsvp, |tx, rsvp| {
+              reserve_in(pool, tx, rsvp)
+            })
+            .await?;
+          Ok::<_, Error>((tx, rsvp))
+        },
+      )
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::IdempotencyKeyInUse("key-1".to_string()));
+
+    let key = "k".repeat(MAX_KEY_LEN + 1);
+    let err = pool
+      .idempotent("xiaozhangId", &key, "reserve", rsvp, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::InvalidIdempotencyKey(key));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn other_keys_should_run_while_one_is_in_use() {
+    let pool = &make_manager(migrated_pool).await;
+    let first =
+      make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+    let other =
+      make_reservation("2024-01-08T11:00:00Z", "2024-01-08T12:00:00Z");
+
+    let rsvp = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", first, |tx, rsvp| {
+        async move {
+          let (tx, rsvp) = reserve_in(pool, tx, rsvp).await?;
+          // key-1 is still claimed by this run
+          let nested = pool
+            .idempotent("xiaozhangId", "key-2", "reserve", other, |tx, rsvp| {
+              reserve_in(pool, tx, rsvp)
+            })
+            .await?;
+          assert_eq!(nested.status, ReservationStatus::Pending as i32);
+          Ok::<_, Error>((tx, rsvp))
+        }
+      })
+      .await
+      .unwrap();
+    assert_eq!(rsvp.status, ReservationStatus::Pending as i32);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn failed_run_should_roll_back_its_changes_and_key() {
+    let pool = &make_manager(migrated_pool).await;
+    let rsvp = make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+
+    // the reservation is made, but the request fails after it
+    let err = pool
+      .idempotent(
+        "xiaozhangId",
+        "key-1",
+        "reserve",
+        rsvp.clone(),
+        |tx, rsvp| async move {
+          reserve_in(pool, tx, rsvp).await?;
+          Err::<(RsvpTx, Reservation), _>(Error::NotFound)
+        },
+      )
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::NotFound);
+    let count: i64 =
+      sqlx::query_scalar("SELECT count(*) FROM rsvp.reservations")
+        .fetch_one(&pool.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+
+    // so the retry runs again instead of replaying a response never kept
+    let retry = pool
+      .idempotent("xiaozhangId", "key-1", "reserve", rsvp, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap();
+    assert_eq!(retry.status, ReservationStatus::Pending as i32);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn keys_of_other_users_should_not_clash() {
+    let pool = &make_manager(migrated_pool).await;
+    let first =
+      make_reservation("2024-01-08T09:00:00Z", "2024-01-08T10:00:00Z");
+    let mut other =
+      make_reservation("2024-01-08T11:00:00Z", "2024-01-08T12:00:00Z");
+    other.user_id = "xiaoliId".to_string();
+
+    pool
+      .idempotent("xiaozhangId", "key-1", "reserve", first, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap();
+    let rsvp = pool
+      .idempotent("xiaoliId", "key-1", "reserve", other, |tx, rsvp| {
+        reserve_in(pool, tx, rsvp)
+      })
+      .await
+      .unwrap();
+    assert_eq!(rsvp.user_id, "xiaoliId");
+  }
+}
@@ -1,10 +1,36 @@
+mod alternatives;
+mod conflict;
+mod ical;
+mod idempotency;
 mod manage;
+mod quota;
+mod resource;
+mod rules;
+mod series;
+mod stream;
+mod waitlist;
 use abi::Error;
 use async_trait::async_trait;
-use sqlx::PgPool;
+use chrono::{DateTime, Duration, Utc};
+use prost::Message;
+use prost_types::FieldMask;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::future::Future;
 
+pub use stream::RsvpStream;
+
+/// transaction an idempotent request runs in, its response is stored with it
+pub type RsvpTx = Transaction<'static, Postgres>;
+
+#[derive(Clone)]
 pub struct ReservationManage {
   pool: PgPool,
+  /// how long a pending reservation without hold_until is held
+  hold_ttl: Option<Duration>,
+  /// booking quotas every user is held to
+  quota: abi::QuotaConfig,
+  /// how long the response of a request with an idempotency key is kept
+  idempotency_ttl: Duration,
 }
 
 #[async_trait]
@@ -13,29 +39,198 @@ pub trait Rsvp {
     &self,
     rsvp: abi::Reservation,
   ) -> Result<abi::Reservation, Error>;
-  async fn change_status(
+  async fn reserve_batch(
+    &self,
+    rsvps: Vec<abi::Reservation>,
+  ) -> Result<Vec<abi::Reservation>, Error>;
+  /// a non zero `expected_version` makes this, update_note, update, cancel and
+  /// restore fail with VersionMismatch if the reservation has been updated
+  /// since
+  async fn transition(
     &self,
     id: abi::ReservationId,
+    to: abi::ReservationStatus,
+    operator: &str,
+    expected_version: i64,
   ) -> Result<abi::Reservation, Error>;
   async fn update_note(
     &self,
     id: abi::ReservationId,
     note: String,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error>;
+  async fn update(
+    &self,
+    id: abi::ReservationId,
+    update: abi::Reservation,
+    mask: FieldMask,
+    expected_version: i64,
   ) -> Result<abi::Reservation, Error>;
   async fn get(
     &self,
     id: abi::ReservationId,
   ) -> Result<abi::Reservation, Error>;
-  async fn delete(
+  async fn cancel(
     &self,
     id: abi::ReservationId,
+    operator: &str,
+    reason: &str,
+    expected_version: i64,
   ) -> Result<abi::Reservation, Error>;
+  /// book a cancelled reservation again with the status it had, it has to
+  /// keep to the same checks as a new one
+  async fn restore(
+    &self,
+    id: abi::ReservationId,
+    operator: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error>;
+  /// one page of reservations found by `query`, streamed to the caller as
+  /// they arrive from the db
   async fn query(
     &self,
     query: abi::ReservationQuery,
-  ) -> Result<Vec<abi::Reservation>, Error>;
+  ) -> Result<RsvpStream, Error>;
   async fn filter(
     &self,
     filter: abi::ReservationFilter,
   ) -> Result<(abi::FilterPager, Vec<abi::Reservation>), Error>;
+  async fn find_availability(
+    &self,
+    query: abi::AvailabilityQuery,
+  ) -> Result<Vec<abi::AvailableWindow>, Error>;
+  async fn listen(
+    &self,
+    last_change_id: i64,
+  ) -> Result<RsvpStream<abi::ListenResponse>, Error>;
+  /// expire at most `limit` pending reservations whose hold ended before `now`
+  async fn expire(
+    &self,
+    now: DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<abi::Reservation>, Error>;
+}
+
+#[async_trait]
+pub trait ResourceCatalogue {
+  async fn create_resource(
+    &self,
+    resource: abi::Resource,
+  ) -> Result<abi::Resource, Error>;
+  async fn get_resource(&self, id: &str) -> Result<abi::Resource, Error>;
+  async fn update_resource(
+    &self,
+    resource: abi::Resource,
+  ) -> Result<abi::Resource, Error>;
+  async fn delete_resource(&self, id: &str) -> Result<abi::Resource, Error>;
+  async fn list_resources(
+    &self,
+    include_inactive: bool,
+  ) -> Result<Vec<abi::Resource>, Error>;
+}
+
+#[async_trait]
+pub trait RecurringRsvp {
+  /// reserve every occurrence of the series, returns the series id
+  async fn reserve_series(
+    &self,
+    series: abi::ReservationSeries,
+  ) -> Result<(i64, Vec<abi::Reservation>), Error>;
+  /// update active occurrences starting at or after `from`
+  async fn update_series(
+    &self,
+    series_id: i64,
+    from: DateTime<Utc>,
+    update: abi::Reservation,
+    mask: FieldMask,
+  ) -> Result<Vec<abi::Reservation>, Error>;
+  /// cancel active occurrences starting at or after `from`
+  async fn cancel_series(
+    &self,
+    series_id: i64,
+    from: DateTime<Utc>,
+    operator: &str,
+    reason: &str,
+  ) -> Result<Vec<abi::Reservation>, Error>;
+  async fn get_series(
+    &self,
+    series_id: i64,
+  ) -> Result<Vec<abi::Reservation>, Error>;
+}
+
+#[async_trait]
+pub trait Quotas {
+  /// usage of every configured quota of a user at `at`
+  async fn get_quota(
+    &self,
+    user_id: &str,
+    at: DateTime<Utc>,
+  ) -> Result<Vec<abi::QuotaUsage>, Error>;
+}
+
+#[async_trait]
+pub trait Alternatives {
+  /// up to `limit` free windows as long as the reservation nearest to it, on
+  /// its resource and with `similar_resources` on active ones of the same type
+  async fn suggest_alternatives(
+    &self,
+    rsvp: &abi::Reservation,
+    limit: usize,
+    similar_resources: bool,
+  ) -> Result<Vec<abi::AvailableWindow>, Error>;
+}
+
+#[async_trait]
+pub trait Waitlist {
+  /// wait for the window of a reservation to free up, it is reserved right
+  /// away if it already has
+  async fn join_waitlist(
+    &self,
+    rsvp: abi::Reservation,
+  ) -> Result<abi::WaitlistEntry, Error>;
+  /// leave the waitlist, only waiting entries can
+  async fn leave_waitlist(&self, id: i64) -> Result<abi::WaitlistEntry, Error>;
+}
+
+#[async_trait]
+pub trait Idempotency {
+  /// run `op` on `request` once per `key` of `user_id`, a retry with the same
+  /// key and request gets the response of the first run until it expires.
+  /// `op` makes its changes in the transaction it is given and hands it back,
+  /// the response is committed along with them. failed runs aren't kept,
+  /// requests without a key always run
+  async fn idempotent<R, M, E, F, Fut>(
+    &self,
+    user_id: &str,
+    key: &str,
+    operation: &str,
+    request: R,
+    op: F,
+  ) -> Result<M, E>
+  where
+    R: Message,
+    M: Message + Default,
+    E: From<Error> + Send,
+    F: FnOnce(RsvpTx, R) -> Fut + Send,
+    Fut: Future<Output = Result<(RsvpTx, M), E>> + Send;
+  /// delete keys expired before `now`, returns how many were deleted
+  async fn purge_idempotency_keys(
+    &self,
+    now: DateTime<Utc>,
+  ) -> Result<u64, Error>;
+}
+
+#[async_trait]
+pub trait CalendarExchange {
+  /// render the reservations found by `query` as an iCalendar file
+  async fn export_ics(
+    &self,
+    query: abi::ReservationQuery,
+  ) -> Result<String, Error>;
+  /// reserve every active VEVENT of an iCalendar file, all or nothing
+  async fn import_ics(
+    &self,
+    ics: &str,
+    user_id: &str,
+  ) -> Result<Vec<abi::Reservation>, Error>;
 }
@@ -1,50 +1,69 @@
-use crate::{ReservationManage, Rsvp};
-use abi::{DbConfig, Error, ReservationId, ReservationStatus, Validator};
+use crate::{ReservationManage, Rsvp, RsvpStream};
+use abi::{
+  convert_to_timestamp, convert_to_utc_time, BatchConflict, DbConfig, Error,
+  ReservationId, ReservationStatus, Validator,
+};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use chrono_tz::Tz;
+use futures::StreamExt;
+use prost_types::FieldMask;
 use sqlx::{
-  postgres::{types::PgRange, PgPoolOptions},
-  PgPool, Row,
+  postgres::{types::PgRange, PgListener, PgPoolOptions},
+  Acquire, FromRow, PgConnection, PgPool, Postgres, Row, Transaction,
 };
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// max reservations buffered between the db fetch and the consumer
+const QUERY_CHANNEL_SIZE: usize = 128;
+
+/// channel notified by rsvp.reservation_trigger
+const RESERVATION_UPDATE_CHANNEL: &str = "reservation_update";
+
+/// how often listeners look again at changes waiting for older transactions
+const LISTEN_RECHECK_INTERVAL: std::time::Duration =
+  std::time::Duration::from_millis(100);
+
+/// operator recorded in the changes log for expired reservations
+const EXPIRY_OPERATOR: &str = "system:expiry";
 
 #[async_trait]
 impl Rsvp for ReservationManage {
   async fn reserve(
     &self,
-    mut rsvp: abi::Reservation,
+    rsvp: abi::Reservation,
   ) -> Result<abi::Reservation, Error> {
-    rsvp.validate()?;
-
-    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
-
-    let status = ReservationStatus::try_from(rsvp.status)
-      .unwrap_or(ReservationStatus::Pending);
+    // quotas are counted under a lock held until commit
+    let mut tx = self.pool.begin().await?;
+    let rsvp = self.reserve_in(&mut tx, rsvp).await?;
+    tx.commit().await?;
 
-    let id: i64 = sqlx::query(
-      "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note, status) VALUES ($1,$2,$3,$4,$5::rsvp.reservation_status) RETURNING id",
-    )
-    .bind(rsvp.user_id.clone())
-    .bind(rsvp.resource_id.clone())
-    .bind(timespan)
-    .bind(rsvp.note.clone())
-    .bind(status.to_string())
-    .fetch_one(&self.pool)
-    .await?
-    .get("id");
+    Ok(rsvp)
+  }
 
-    rsvp.id = id;
+  async fn reserve_batch(
+    &self,
+    rsvps: Vec<abi::Reservation>,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let mut tx = self.pool.begin().await?;
+    let reserved = self.insert_all(&mut tx, rsvps).await?;
+    tx.commit().await?;
 
-    Ok(rsvp)
+    Ok(reserved)
   }
 
-  async fn change_status(
+  async fn transition(
     &self,
     id: ReservationId,
+    to: ReservationStatus,
+    operator: &str,
+    expected_version: i64,
   ) -> Result<abi::Reservation, Error> {
-    id.validate()?;
-
-    let rsvp: abi::Reservation = sqlx::query_as("UPDATE rsvp.reservations SET status = 'confirmed' WHERE id = $1 AND STATUS = 'pending' RETURNING *")
-    .bind(id).fetch_one(&self.pool).await?;
+    let mut tx = self.pool.begin().await?;
+    let rsvp = self
+      .transition_in(&mut tx, id, to, operator, expected_version)
+      .await?;
+    tx.commit().await?;
 
     Ok(rsvp)
   }
@@ -53,16 +72,29 @@ impl Rsvp for ReservationManage {
     &self,
     id: ReservationId,
     note: String,
+    expected_version: i64,
   ) -> Result<abi::Reservation, Error> {
-    id.validate()?;
+    let mut tx = self.pool.begin().await?;
+    let rsvp = self
+      .update_note_in(&mut tx, id, note, expected_version)
+      .await?;
+    tx.commit().await?;
 
-    let rsvp: abi::Reservation = sqlx::query_as(
-      "UPDATE rsvp.reservations SET note = $1 WHERE id = $2 RETURNING *",
-    )
-    .bind(note)
-    .bind(id)
-    .fetch_one(&self.pool)
-    .await?;
+    Ok(rsvp)
+  }
+
+  async fn update(
+    &self,
+    id: ReservationId,
+    update: abi::Reservation,
+    mask: FieldMask,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    let mut tx = self.pool.begin().await?;
+    let rsvp = self
+      .update_in(&mut tx, id, update, mask, expected_version)
+      .await?;
+    tx.commit().await?;
 
     Ok(rsvp)
   }
@@ -79,14 +111,79 @@ impl Rsvp for ReservationManage {
     Ok(rsvp)
   }
 
-  async fn delete(&self, id: ReservationId) -> Result<abi::Reservation, Error> {
+  async fn cancel(
+    &self,
+    id: ReservationId,
+    operator: &str,
+    reason: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    let mut tx = self.pool.begin().await?;
+    let rsvp = self
+      .cancel_in(&mut tx, id, operator, reason, expected_version)
+      .await?;
+    tx.commit().await?;
+
+    Ok(rsvp)
+  }
+
+  async fn restore(
+    &self,
+    id: ReservationId,
+    operator: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
     id.validate()?;
 
-    let rsvp: abi::Reservation =
-      sqlx::query_as("DELETE FROM rsvp.reservations WHERE id = $1 RETURNING *")
-        .bind(id)
-        .fetch_one(&self.pool)
-        .await?;
+    let mut tx = self.pool.begin().await?;
+    let mut rsvp = self
+      .lock_reservation(&mut tx, id, operator, expected_version)
+      .await?;
+
+    let from = status_of(&rsvp);
+    if from != ReservationStatus::Cancelled {
+      return Err(Error::NotCancelled(from));
+    }
+
+    // restore the status the reservation had before it was cancelled
+    let to: Option<String> = sqlx::query(
+      "SELECT old->>'status' AS status FROM rsvp.reservations_changes WHERE reservation_id = $1 AND op = 'update' AND new->>'status' = 'cancelled' AND old->>'status' <> 'cancelled' ORDER BY id DESC LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(&mut tx)
+    .await?
+    .map(|row| row.get("status"));
+    let to = to
+      .and_then(|status| status.parse().ok())
+      .unwrap_or(ReservationStatus::Pending);
+
+    // it is booked again, so it has to be bookable like a new one
+    rsvp.status = to as i32;
+    self.check_resource(&mut tx, &rsvp.resource_id).await?;
+    self.check_rules(&mut tx, &rsvp).await?;
+    self.check_quota(&mut tx, &rsvp).await?;
+
+    // fails with a conflict if the time slot was taken in the meantime
+    let mut savepoint = tx.begin().await?;
+    let restored = sqlx::query_as(
+      "UPDATE rsvp.reservations SET status = $1::rsvp.reservation_status, cancelled_at = NULL, cancelled_by = NULL, reason = NULL WHERE id = $2 RETURNING *",
+    )
+    .bind(to.to_string())
+    .bind(id)
+    .fetch_one(&mut savepoint)
+    .await;
+    let rsvp: abi::Reservation = match restored {
+      Ok(restored) => {
+        savepoint.commit().await?;
+        restored
+      }
+      Err(e) => {
+        savepoint.rollback().await?;
+        return Err(self.identify_conflict(&mut tx, &rsvp, e.into()).await);
+      }
+    };
+
+    tx.commit().await?;
 
     Ok(rsvp)
   }
@@ -94,25 +191,14 @@ impl Rsvp for ReservationManage {
   async fn query(
     &self,
     query: abi::ReservationQuery,
-  ) -> Result<Vec<abi::Reservation>, Error> {
-    let user_id = str_to_option(&query.user_id);
-    let resource_id = str_to_option(&query.resource_id);
-    let status = ReservationStatus::try_from(query.status)
-      .unwrap_or(ReservationStatus::Pending);
-    let range = query.get_timespan();
-    let rsvps =
-      sqlx::query_as("SELECT * FROM rsvp.query($1, $2, $3::rsvp.reservation_status, $4, $5, $6, $7)")
-        .bind(user_id)
-        .bind(resource_id)
-        .bind(status.to_string())
-        .bind(range)
-        .bind(query.page)
-        .bind(query.page_size)
-        .bind(query.desc)
-        .fetch_all(&self.pool)
-        .await?;
+  ) -> Result<RsvpStream, Error> {
+    query.validate()?;
+    let timezone = query.get_timezone()?;
 
-    Ok(rsvps)
+    let (tx, rx) = mpsc::channel(QUERY_CHANNEL_SIZE);
+    self.spawn_query(query, timezone, tx);
+
+    Ok(RsvpStream::new(rx))
   }
   async fn filter(
     &self,
@@ -127,40 +213,234 @@ impl Rsvp for ReservationManage {
     } else {
       query.page_size
     };
-    let rsvps: Vec<abi::Reservation> =
+    let cursor = if query.cursor > 0 {
+      Some(query.cursor)
+    } else {
+      None
+    };
+
+    let mut rsvps: Vec<abi::Reservation> =
       sqlx::query_as("SELECT * FROM rsvp.filter($1, $2, $3::rsvp.reservation_status, $4, $5, $6)")
         .bind(user_id)
         .bind(resource_id)
         .bind(status.to_string())
-        .bind(query.cursor)
+        .bind(cursor)
         .bind(page_size)
         .bind(query.desc)
         .fetch_all(&self.pool)
         .await?;
 
-    let len = rsvps.len();
+    let next = if rsvps.len() as i32 > page_size {
+      rsvps.truncate(page_size as usize);
+      rsvps.last().map(|rsvp| rsvp.id).unwrap_or(-1)
+    } else {
+      -1
+    };
+
+    let prev = match cursor {
+      Some(cursor) => {
+        // walk backwards from the cursor (inclusive) to find the cursor
+        // of the previous page
+        let (cursor, desc) = if query.desc {
+          (cursor - 1, false)
+        } else {
+          (cursor.saturating_add(1), true)
+        };
+        let rsvps: Vec<abi::Reservation> =
+          sqlx::query_as("SELECT * FROM rsvp.filter($1, $2, $3::rsvp.reservation_status, $4, $5, $6)")
+            .bind(user_id)
+            .bind(resource_id)
+            .bind(status.to_string())
+            .bind(cursor)
+            .bind(page_size)
+            .bind(desc)
+            .fetch_all(&self.pool)
+            .await?;
+
+        match rsvps.len() {
+          0 => -1,
+          len if len as i32 > page_size => rsvps[page_size as usize].id,
+          // the previous page is the first page
+          _ => 0,
+        }
+      }
+      None => -1,
+    };
+
+    let total: i64 = sqlx::query(
+      "SELECT rsvp.filter_count($1, $2, $3::rsvp.reservation_status) AS total",
+    )
+    .bind(user_id)
+    .bind(resource_id)
+    .bind(status.to_string())
+    .fetch_one(&self.pool)
+    .await?
+    .get("total");
 
-    let has_prev = len > 0 && rsvps[0].id == query.cursor;
-    let start = if has_prev { 1 } else { 0 };
+    let pager = abi::FilterPager { prev, next, total };
 
-    let has_end = (len - start) as i32 > page_size;
-    let end = if has_end { len - 1 } else { len };
+    Ok((pager, rsvps))
+  }
 
-    let result = rsvps[start..end].to_vec();
+  async fn find_availability(
+    &self,
+    query: abi::AvailabilityQuery,
+  ) -> Result<Vec<abi::AvailableWindow>, Error> {
+    query.validate()?;
+
+    let windows: Vec<abi::AvailableWindow> =
+      sqlx::query_as("SELECT * FROM rsvp.availability($1, $2, $3, $4)")
+        .bind(&query.resource_id)
+        .bind(query.get_timespan())
+        .bind(query.get_min_duration())
+        .bind(query.get_granularity())
+        .fetch_all(&self.pool)
+        .await?;
 
-    let prev = if has_prev { rsvps[start - 1].id } else { -1 };
+    Ok(windows)
+  }
 
-    let next = if has_end { rsvps[end - 1].id } else { -1 };
-    let pager = abi::FilterPager {
-      prev,
-      next,
-      total: 0,
+  async fn listen(
+    &self,
+    last_change_id: i64,
+  ) -> Result<RsvpStream<abi::ListenResponse>, Error> {
+    // start listening before reading the changes, so nothing is missed
+    let mut listener = PgListener::connect_with(&self.pool).await?;
+    listener.listen(RESERVATION_UPDATE_CHANNEL).await?;
+
+    // changes are sent in the order of the transactions that wrote them,
+    // (xid, id) of the last one sent
+    let mut last: (i64, i64) = if last_change_id > 0 {
+      let xid: i64 = sqlx::query_scalar(
+        "SELECT xid::text::bigint FROM rsvp.reservations_changes WHERE id = $1",
+      )
+      .bind(last_change_id)
+      .fetch_optional(&self.pool)
+      .await?
+      .ok_or(Error::NotFound)?;
+      (xid, last_change_id)
+    } else {
+      // transactions that may still be running at this point are new
+      let xmin: i64 = sqlx::query_scalar(
+        "SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint",
+      )
+      .fetch_one(&self.pool)
+      .await?;
+      (xmin, 0)
     };
 
-    Ok((pager, result))
+    let pool = self.pool.clone();
+    let (tx, rx) = mpsc::channel(QUERY_CHANNEL_SIZE);
+
+    tokio::spawn(async move {
+      loop {
+        // a change is settled once every older transaction has ended, no
+        // change can be committed before it after that
+        let rows = sqlx::query(
+          r#"SELECT *, xid::text::bigint AS tx,
+              xid < pg_snapshot_xmin(pg_current_snapshot()) AS settled
+          FROM rsvp.reservations_changes
+          WHERE (xid, id) > ($1::bigint::text::xid8, $2)
+          ORDER BY xid, id"#,
+        )
+        .bind(last.0)
+        .bind(last.1)
+        .fetch_all(&pool)
+        .await;
+
+        let mut unsettled = false;
+        match rows {
+          Ok(rows) => {
+            for row in rows {
+              if !row.get::<bool, _>("settled") {
+                unsettled = true;
+                break;
+              }
+              let change = match abi::ListenResponse::from_row(&row) {
+                Ok(change) => change,
+                Err(e) => {
+                  let _ = tx.send(Err(e.into())).await;
+                  return;
+                }
+              };
+              last = (row.get("tx"), change.id);
+              if tx.send(Ok(change)).await.is_err() {
+                return;
+              }
+            }
+          }
+          Err(e) => {
+            let _ = tx.send(Err(e.into())).await;
+            return;
+          }
+        }
+
+        // wait for the next change, stop once the receiver is gone. older
+        // transactions may end without a change, so unsettled changes are
+        // checked again after a while
+        let recheck = async {
+          if unsettled {
+            tokio::time::sleep(LISTEN_RECHECK_INTERVAL).await
+          } else {
+            std::future::pending().await
+          }
+        };
+        tokio::select! {
+          ret = listener.recv() => {
+            if let Err(e) = ret {
+              let _ = tx.send(Err(e.into())).await;
+              return;
+            }
+          }
+          _ = recheck => {}
+          _ = tx.closed() => return,
+        }
+      }
+    });
+
+    Ok(RsvpStream::new(rx))
+  }
+
+  async fn expire(
+    &self,
+    now: DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let mut tx = self.pool.begin().await?;
+
+    sqlx::query("SELECT set_config('rsvp.operator', $1, true)")
+      .bind(EXPIRY_OPERATOR)
+      .execute(&mut tx)
+      .await?;
+
+    // skip rows locked by a concurrent transition, they'll be retried later
+    let rsvps: Vec<abi::Reservation> = sqlx::query_as(
+      "UPDATE rsvp.reservations SET status = 'expired' WHERE id IN (SELECT id FROM rsvp.reservations WHERE status = 'pending' AND hold_until <= $1 ORDER BY hold_until LIMIT $2 FOR UPDATE SKIP LOCKED) RETURNING *",
+    )
+    .bind(now)
+    .bind(limit)
+    .fetch_all(&mut tx)
+    .await?;
+    self.promote_waitlist(&mut tx, &rsvps).await?;
+
+    tx.commit().await?;
+
+    Ok(rsvps)
   }
 }
 
+fn status_of(rsvp: &abi::Reservation) -> ReservationStatus {
+  ReservationStatus::try_from(rsvp.status).unwrap_or(ReservationStatus::Unknown)
+}
+
+/// whether `mask` updates any of `fields`
+fn masks_any(mask: &FieldMask, fields: &[&str]) -> bool {
+  mask
+    .paths
+    .iter()
+    .any(|path| fields.contains(&path.as_str()))
+}
+
 fn str_to_option(s: &str) -> Option<&str> {
   if s.is_empty() {
     None
@@ -171,8 +451,399 @@ fn str_to_option(s: &str) -> Option<&str> {
 
 impl ReservationManage {
   pub fn new(pool: PgPool) -> Self {
-    Self { pool }
+    Self {
+      pool,
+      hold_ttl: None,
+      quota: Default::default(),
+      idempotency_ttl: Duration::days(1),
+    }
+  }
+
+  /// hold pending reservations without hold_until for `ttl`
+  pub fn with_hold_ttl(mut self, ttl: Duration) -> Self {
+    self.hold_ttl = Some(ttl);
+    self
+  }
+
+  /// hold every user to `quota` when reserving
+  pub fn with_quota(mut self, quota: abi::QuotaConfig) -> Self {
+    self.quota = quota;
+    self
+  }
+
+  /// keep responses of requests with an idempotency key for `ttl`
+  pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+    self.idempotency_ttl = ttl;
+    self
+  }
+
+  /// [`Rsvp::reserve`] inside `conn`, which is left for the caller to commit
+  pub async fn reserve_in(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: abi::Reservation,
+  ) -> Result<abi::Reservation, Error> {
+    rsvp.validate()?;
+
+    self.insert_reservation(conn, rsvp).await
+  }
+
+  /// [`Rsvp::transition`] inside `conn`, which is left for the caller to commit
+  pub async fn transition_in(
+    &self,
+    conn: &mut PgConnection,
+    id: ReservationId,
+    to: ReservationStatus,
+    operator: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    id.validate()?;
+
+    if to == ReservationStatus::Cancelled {
+      return self
+        .cancel_in(conn, id, operator, "", expected_version)
+        .await;
+    }
+
+    let rsvp = self
+      .lock_reservation(conn, id, operator, expected_version)
+      .await?;
+
+    let from = status_of(&rsvp);
+    if !from.can_transition_to(to) {
+      return Err(Error::InvalidTransition { from, to });
+    }
+
+    let rsvp: abi::Reservation = sqlx::query_as(
+      "UPDATE rsvp.reservations SET status = $1::rsvp.reservation_status WHERE id = $2 RETURNING *",
+    )
+    .bind(to.to_string())
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !to.is_active() {
+      self
+        .promote_waitlist(conn, std::slice::from_ref(&rsvp))
+        .await?;
+    }
+
+    Ok(rsvp)
+  }
+
+  /// [`Rsvp::update_note`] inside `conn`, which is left for the caller to
+  /// commit
+  pub async fn update_note_in(
+    &self,
+    conn: &mut PgConnection,
+    id: ReservationId,
+    note: String,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    id.validate()?;
+
+    self
+      .lock_reservation(conn, id, "", expected_version)
+      .await?;
+    let rsvp = sqlx::query_as(
+      "UPDATE rsvp.reservations SET note = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(note)
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(rsvp)
+  }
+
+  /// [`Rsvp::update`] inside `conn`, which is left for the caller to commit
+  pub async fn update_in(
+    &self,
+    conn: &mut PgConnection,
+    id: ReservationId,
+    update: abi::Reservation,
+    mask: FieldMask,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    id.validate()?;
+
+    let mut rsvp = self
+      .lock_reservation(conn, id, "", expected_version)
+      .await?;
+    rsvp.apply_update(update, &mask)?;
+
+    self.save_update(conn, &rsvp, &mask).await
+  }
+
+  /// [`Rsvp::cancel`] inside `conn`, which is left for the caller to commit
+  pub async fn cancel_in(
+    &self,
+    conn: &mut PgConnection,
+    id: ReservationId,
+    operator: &str,
+    reason: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    id.validate()?;
+
+    let rsvp = self
+      .lock_reservation(conn, id, operator, expected_version)
+      .await?;
+
+    let from = status_of(&rsvp);
+    let to = ReservationStatus::Cancelled;
+    if !from.can_transition_to(to) {
+      return Err(Error::InvalidTransition { from, to });
+    }
+
+    // keep the row, the exclusion constraint ignores cancelled reservations
+    let rsvp: abi::Reservation = sqlx::query_as(
+      "UPDATE rsvp.reservations SET status = 'cancelled', cancelled_at = now(), cancelled_by = NULLIF($1, ''), reason = NULLIF($2, '') WHERE id = $3 RETURNING *",
+    )
+    .bind(operator)
+    .bind(reason)
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+    self
+      .promote_waitlist(conn, std::slice::from_ref(&rsvp))
+      .await?;
+
+    Ok(rsvp)
+  }
+
+  /// insert every reservation inside `tx`, failing with each one that is
+  /// invalid, conflicts or breaks a booking rule
+  pub(crate) async fn insert_all(
+    &self,
+    tx: &mut Transaction<'_, Postgres>,
+    rsvps: Vec<abi::Reservation>,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let mut reserved = Vec::with_capacity(rsvps.len());
+    let mut conflicts = Vec::new();
+
+    for (index, rsvp) in rsvps.into_iter().enumerate() {
+      if let Err(error) = rsvp.validate() {
+        conflicts.push(BatchConflict { index, error });
+        continue;
+      }
+      // a failed insert aborts the transaction, so give every item its own
+      // savepoint and keep going to collect all the conflicts
+      let mut savepoint = tx.begin().await?;
+      match self.insert_reservation(&mut savepoint, rsvp).await {
+        Ok(rsvp) => {
+          savepoint.commit().await?;
+          reserved.push(rsvp);
+        }
+        Err(
+          error @ (Error::ConflictReservation(_)
+          | Error::CapacityExceeded(_)
+          | Error::RuleViolation(_)),
+        ) => {
+          savepoint.rollback().await?;
+          conflicts.push(BatchConflict { index, error });
+        }
+        Err(e) => return Err(e),
+      }
+    }
+
+    if !conflicts.is_empty() {
+      return Err(Error::BatchConflict(conflicts));
+    }
+
+    Ok(reserved)
+  }
+
+  /// write the fields of a locked reservation updated by `mask` back
+  pub(crate) async fn save_update(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+    mask: &FieldMask,
+  ) -> Result<abi::Reservation, Error> {
+    rsvp.validate()?;
+    self.check_resource(conn, &rsvp.resource_id).await?;
+    // lead time and horizon only apply to when it is booked, not to editing
+    // the note of a booking that starts soon
+    if masks_any(mask, &["start", "end", "resource_id"]) {
+      self.check_rules(conn, rsvp).await?;
+    }
+    if status_of(rsvp).is_active()
+      && masks_any(mask, &["start", "end", "resource_id", "user_id"])
+    {
+      self.check_quota(conn, rsvp).await?;
+    }
+
+    // moving the window or resource is checked by reservation_buffer_conflict,
+    // or the capacity trigger for shared resources
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
+    let mut savepoint = conn.begin().await?;
+    let updated = sqlx::query_as(
+      "UPDATE rsvp.reservations SET user_id = $1, resource_id = $2, timespan = $3, note = $4, quantity = $5 WHERE id = $6 RETURNING *",
+    )
+    .bind(&rsvp.user_id)
+    .bind(&rsvp.resource_id)
+    .bind(timespan)
+    .bind(&rsvp.note)
+    .bind(rsvp.quantity)
+    .bind(rsvp.id)
+    .fetch_one(&mut savepoint)
+    .await;
+
+    match updated {
+      Ok(updated) => {
+        savepoint.commit().await?;
+        Ok(updated)
+      }
+      Err(e) => {
+        savepoint.rollback().await?;
+        Err(self.identify_conflict(conn, rsvp, e.into()).await)
+      }
+    }
+  }
+
+  pub(crate) async fn insert_reservation(
+    &self,
+    conn: &mut PgConnection,
+    mut rsvp: abi::Reservation,
+  ) -> Result<abi::Reservation, Error> {
+    self.check_resource(conn, &rsvp.resource_id).await?;
+    self.check_rules(conn, &rsvp).await?;
+    self.check_quota(conn, &rsvp).await?;
+    rsvp.get_timezone()?;
+
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
+
+    let status = ReservationStatus::try_from(rsvp.status)
+      .unwrap_or(ReservationStatus::Pending);
+
+    if status == ReservationStatus::Pending && rsvp.hold_until.is_none() {
+      // postgres only keeps microseconds, truncate so we return what's stored
+      rsvp.hold_until = self.hold_ttl.map(|ttl| {
+        let until = Utc::now() + ttl;
+        convert_to_timestamp(
+          until
+            .duration_trunc(Duration::microseconds(1))
+            .unwrap_or(until),
+        )
+      });
+    }
+    let hold_until = rsvp.hold_until.map(convert_to_utc_time);
+    rsvp.quantity = rsvp.quantity.max(1);
+
+    // reservations without a timezone take the one of their resource. it's
+    // inserted in a savepoint, so clashing reservations can be looked up
+    let mut savepoint = conn.begin().await?;
+    let row = sqlx::query(
+      "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note, status, hold_until, quantity, series_id, timezone) VALUES ($1,$2,$3,$4,$5::rsvp.reservation_status,$6,$7,$8,COALESCE(NULLIF($9, ''), (SELECT timezone FROM rsvp.resources WHERE id = $2))) RETURNING id, timezone, version, updated_at",
+    )
+    .bind(rsvp.user_id.clone())
+    .bind(rsvp.resource_id.clone())
+    .bind(timespan)
+    .bind(rsvp.note.clone())
+    .bind(status.to_string())
+    .bind(hold_until)
+    .bind(rsvp.quantity)
+    .bind((rsvp.series_id > 0).then_some(rsvp.series_id))
+    .bind(rsvp.timezone.clone())
+    .fetch_one(&mut savepoint)
+    .await;
+    let row = match row {
+      Ok(row) => {
+        savepoint.commit().await?;
+        row
+      }
+      Err(e) => {
+        savepoint.rollback().await?;
+        return Err(self.identify_conflict(conn, &rsvp, e.into()).await);
+      }
+    };
+
+    rsvp.id = row.get("id");
+    rsvp.timezone = row.get("timezone");
+    rsvp.version = row.get("version");
+    rsvp.updated_at = Some(convert_to_timestamp(row.get("updated_at")));
+    if let Some(tz) = rsvp.get_timezone()? {
+      rsvp.set_local_times(tz);
+    }
+
+    Ok(rsvp)
+  }
+
+  /// send the reservations found by `query` to `tx` as they are fetched,
+  /// stopping early once the receiver is gone. rsvp.query is a plpgsql
+  /// `RETURN QUERY`, so postgres builds the whole page (at most 100 rows)
+  /// before handing it over; only the way to the client is streamed
+  fn spawn_query(
+    &self,
+    query: abi::ReservationQuery,
+    timezone: Option<Tz>,
+    tx: mpsc::Sender<Result<abi::Reservation, Error>>,
+  ) -> JoinHandle<()> {
+    let pool = self.pool.clone();
+    tokio::spawn(async move {
+      let user_id = str_to_option(&query.user_id);
+      let resource_id = str_to_option(&query.resource_id);
+      let status = ReservationStatus::try_from(query.status)
+        .unwrap_or(ReservationStatus::Pending);
+      let range = query.get_timespan();
+      let mut rsvps =
+        sqlx::query_as("SELECT * FROM rsvp.query($1, $2, $3::rsvp.reservation_status, $4, $5, $6, $7)")
+          .bind(user_id)
+          .bind(resource_id)
+          .bind(status.to_string())
+          .bind(range)
+          .bind(query.page)
+          .bind(query.page_size)
+          .bind(query.desc)
+          .fetch(&pool);
+
+      while let Some(ret) = rsvps.next().await {
+        let ret = ret.map_err(Error::from).map(|mut rsvp: abi::Reservation| {
+          if let Some(tz) = timezone {
+            rsvp.set_local_times(tz);
+          }
+          rsvp
+        });
+        let is_err = ret.is_err();
+        // the receiver is gone (e.g. client disconnected), stop fetching
+        if tx.send(ret).await.is_err() || is_err {
+          break;
+        }
+      }
+    })
+  }
+
+  /// lock the reservation for update until `conn` commits, `operator` is
+  /// recorded in the changes log by rsvp.reservation_trigger. fails if
+  /// `expected_version` isn't 0 and the reservation is at another version
+  async fn lock_reservation(
+    &self,
+    conn: &mut PgConnection,
+    id: ReservationId,
+    operator: &str,
+    expected_version: i64,
+  ) -> Result<abi::Reservation, Error> {
+    sqlx::query("SELECT set_config('rsvp.operator', $1, true)")
+      .bind(operator)
+      .execute(&mut *conn)
+      .await?;
+
+    let rsvp: abi::Reservation = sqlx::query_as(
+      "SELECT * FROM rsvp.reservations WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+    if expected_version != 0 && rsvp.version != expected_version {
+      return Err(Error::VersionMismatch {
+        expected: expected_version,
+        actual: rsvp.version,
+      });
+    }
+
+    Ok(rsvp)
   }
+
   pub async fn from_config(config: &DbConfig) -> Result<PgPool, Error> {
     let pool = PgPoolOptions::default()
       .max_connections(config.max_connections)
@@ -187,18 +858,38 @@ impl ReservationManage {
 mod tests {
 
   use super::*;
+  use crate::ResourceCatalogue;
   use abi::{
-    convert_local_time_to_utc, Reservation, ReservationConflict,
+    convert_local_time_to_utc, convert_to_timestamp, Reservation,
     ReservationConflictInfo, ReservationFilterBuilder, ReservationQueryBuilder,
-    ReservationWindow,
+    ReservationUpdateType, ReservationWindow,
   };
   use prost_types::Timestamp;
+
+  /// manager with the resources the tests reserve against
+  async fn make_manager(pool: PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    let ids = ["ocean-view-room-713", "testResourceId", "projector-1"]
+      .into_iter()
+      .map(String::from)
+      .chain((0..30).map(|i| format!("room-{}", i)));
+    for id in ids {
+      let resource = abi::Resource {
+        name: id.clone(),
+        id,
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+    }
+    manager
+  }
   #[sqlx_database_tester::test(pool(
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
   async fn reserve_should_work_for_valid_window() {
-    let pool = ReservationManage::new(migrated_pool);
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "xiaozhangId",
@@ -219,14 +910,70 @@ mod tests {
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
-  async fn reserve_conflict_reservation_should_reject() {
-    let pool = ReservationManage::new(migrated_pool);
+  async fn reserve_should_show_local_times_of_resource_timezone() {
+    let pool = make_manager(migrated_pool).await;
+    pool
+      .create_resource(abi::Resource {
+        id: "tokyo-room".to_string(),
+        name: "tokyo room".to_string(),
+        capacity: 1,
+        timezone: "Asia/Tokyo".to_string(),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
 
     let rsvp = Reservation::new_pending(
       "xiaozhangId",
-      "ocean-view-room-713",
-      convert_local_time_to_utc("2024-01-21 19:00:00"),
-      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "tokyo-room",
+      "2024-01-21T00:00:00Z".parse().unwrap(),
+      "2024-01-21T01:00:00Z".parse().unwrap(),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    assert_eq!(rsvp.timezone, "Asia/Tokyo");
+    assert_eq!(rsvp.local_start, "2024-01-21T09:00:00+09:00");
+
+    let query = ReservationQueryBuilder::default()
+      .resource_id("tokyo-room")
+      .start("2024-01-20T00:00:00Z".parse::<Timestamp>().unwrap())
+      .end("2024-01-22T00:00:00Z".parse::<Timestamp>().unwrap())
+      .status(ReservationStatus::Pending as i32)
+      .timezone("Europe/Berlin")
+      .build()
+      .unwrap();
+    let mut stream = pool.query(query).await.unwrap();
+    let found = stream.next().await.unwrap().unwrap();
+    assert_eq!(found.timezone, "Asia/Tokyo");
+    assert_eq!(found.local_start, "2024-01-21T01:00:00+01:00");
+    assert_eq!(found.local_end, "2024-01-21T02:00:00+01:00");
+
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "tokyo-room",
+      "2024-01-22T00:00:00Z".parse().unwrap(),
+      "2024-01-22T01:00:00Z".parse().unwrap(),
+      "",
+    );
+    rsvp.timezone = "Tokyo".to_string();
+    assert_eq!(
+      pool.reserve(rsvp).await.unwrap_err(),
+      Error::InvalidTimezone("Tokyo".to_string())
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_conflict_reservation_should_reject() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "ocean-view-room-713",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
       "test_reserve_should_work_for_valid_window",
     );
 
@@ -238,31 +985,193 @@ mod tests {
       "test_reserve_should_work_for_valid_window",
     );
 
-    let _rsvp1 = pool.reserve(rsvp).await.unwrap();
+    let existing = pool.reserve(rsvp).await.unwrap();
     let reserve_conflict = pool.reserve(rsvp1).await.unwrap_err();
 
-    let info = ReservationConflictInfo::Parsed(ReservationConflict {
-      new: ReservationWindow {
-        rid: "ocean-view-room-713".to_string(),
-        start: convert_local_time_to_utc("2024-01-21 19:00:00"),
-        end: convert_local_time_to_utc("2024-01-22 12:00:00"),
-      },
-      old: ReservationWindow {
+    let Error::ConflictReservation(ReservationConflictInfo::Parsed(conflict)) =
+      reserve_conflict
+    else {
+      panic!("expect parsed conflict, got {:?}", reserve_conflict);
+    };
+    assert_eq!(
+      conflict.new,
+      ReservationWindow {
         rid: "ocean-view-room-713".to_string(),
         start: convert_local_time_to_utc("2024-01-22 8:00:00"),
         end: convert_local_time_to_utc("2024-01-23 12:00:00"),
-      },
-    });
+      }
+    );
+    assert_eq!(
+      conflict.old,
+      ReservationWindow {
+        rid: "ocean-view-room-713".to_string(),
+        start: convert_local_time_to_utc("2024-01-21 19:00:00"),
+        end: convert_local_time_to_utc("2024-01-22 12:00:00"),
+      }
+    );
+    assert!(!conflict.buffer_only);
+    assert_eq!(conflict.existing.len(), 1);
+    assert_eq!(conflict.existing[0].id, existing.id);
+    assert_eq!(conflict.existing[0].user_id, "xiaozhangId");
+  }
 
-    assert_eq!(reserve_conflict, Error::ConflictReservation(info))
+  fn make_batch_item(resource_id: &str, start: &str, end: &str) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      convert_local_time_to_utc(start),
+      convert_local_time_to_utc(end),
+      "",
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_batch_should_work() {
+    let pool = make_manager(migrated_pool).await;
+    let rsvps = pool
+      .reserve_batch(vec![
+        make_batch_item("room-1", "2024-01-21 19:00:00", "2024-01-22 12:00:00"),
+        make_batch_item(
+          "projector-1",
+          "2024-01-21 19:00:00",
+          "2024-01-22 12:00:00",
+        ),
+      ])
+      .await
+      .unwrap();
+
+    assert_eq!(rsvps.len(), 2);
+    for rsvp in rsvps {
+      assert!(rsvp.id != 0);
+      assert_eq!(pool.get(rsvp.id).await.unwrap(), rsvp);
+    }
   }
 
   #[sqlx_database_tester::test(pool(
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
-  async fn reserve_change_status_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+  async fn reserve_batch_conflict_should_report_all_and_rollback() {
+    let pool = make_manager(migrated_pool).await;
+    pool
+      .reserve(make_batch_item(
+        "room-1",
+        "2024-01-21 19:00:00",
+        "2024-01-22 12:00:00",
+      ))
+      .await
+      .unwrap();
+
+    let err = pool
+      .reserve_batch(vec![
+        make_batch_item("room-2", "2024-01-21 19:00:00", "2024-01-22 12:00:00"),
+        make_batch_item("room-1", "2024-01-22 08:00:00", "2024-01-23 12:00:00"),
+        make_batch_item("room-3", "2024-01-21 19:00:00", "2024-01-22 12:00:00"),
+        // conflicts with another item of the same batch
+        make_batch_item("room-2", "2024-01-22 08:00:00", "2024-01-23 12:00:00"),
+      ])
+      .await
+      .unwrap_err();
+
+    let Error::BatchConflict(conflicts) = err else {
+      panic!("expect batch conflict, got {:?}", err);
+    };
+    let indexes: Vec<_> = conflicts.iter().map(|c| c.index).collect();
+    assert_eq!(indexes, vec![1, 3]);
+    assert!(conflicts.iter().all(|c| matches!(
+      c.error,
+      Error::ConflictReservation(ReservationConflictInfo::Parsed(_))
+    )));
+
+    let count: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM rsvp.reservations")
+        .fetch_one(&pool.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_batch_should_report_invalid_items_per_index() {
+    let pool = make_manager(migrated_pool).await;
+    let mut no_user =
+      make_batch_item("room-2", "2024-01-21 19:00:00", "2024-01-22 12:00:00");
+    no_user.user_id = String::new();
+
+    let err = pool
+      .reserve_batch(vec![
+        make_batch_item("room-1", "2024-01-21 19:00:00", "2024-01-22 12:00:00"),
+        no_user,
+        make_batch_item("room-3", "2024-01-22 12:00:00", "2024-01-21 19:00:00"),
+      ])
+      .await
+      .unwrap_err();
+
+    let Error::BatchConflict(conflicts) = err else {
+      panic!("expect batch conflict, got {:?}", err);
+    };
+    let indexes: Vec<_> = conflicts.iter().map(|c| c.index).collect();
+    assert_eq!(indexes, vec![1, 2]);
+    assert_eq!(conflicts[0].error, Error::InvalidUserId(String::new()));
+    assert_eq!(conflicts[1].error, Error::InvalidTime);
+
+    let count: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM rsvp.reservations")
+        .fetch_one(&pool.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_on_cancelled_slot_should_work() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "ocean-view-room-713",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+
+    sqlx::query(
+      "UPDATE rsvp.reservations SET status = 'cancelled' WHERE id = $1",
+    )
+    .bind(rsvp.id)
+    .execute(&pool.pool)
+    .await
+    .unwrap();
+    let cancelled = pool.get(rsvp.id).await.unwrap();
+    assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+
+    let rsvp1 = Reservation::new_pending(
+      "xiaonanId",
+      "ocean-view-room-713",
+      convert_local_time_to_utc("2024-01-22 8:00:00"),
+      convert_local_time_to_utc("2024-01-23 12:00:00"),
+      "",
+    );
+    let rsvp1 = pool.reserve(rsvp1).await.unwrap();
+    assert!(rsvp1.id != 0);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn transition_reservation_should_work() {
+    let pool = make_manager(migrated_pool).await;
 
     // test change status
     let rsvp = Reservation::new_pending(
@@ -270,13 +1179,16 @@ mod tests {
       "testResourceId",
       convert_local_time_to_utc("2024-01-21 19:00:00"),
       convert_local_time_to_utc("2024-01-22 12:00:00"),
-      "test_change_status_reservation_should_work",
+      "test_transition_reservation_should_work",
     );
 
     let rsvp = pool.reserve(rsvp).await.unwrap();
 
     // 将状态从Pending改为Confirmed
-    let updated_rsvp = pool.change_status(rsvp.id).await.unwrap();
+    let updated_rsvp = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 0)
+      .await
+      .unwrap();
 
     assert_eq!(updated_rsvp.status, ReservationStatus::Confirmed as i32);
   }
@@ -285,8 +1197,8 @@ mod tests {
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
-  async fn reserve_change_status_not_pending_should_donothing() {
-    let pool = ReservationManage::new(migrated_pool);
+  async fn transition_not_allowed_should_reject() {
+    let pool = make_manager(migrated_pool).await;
 
     // test change status
     let rsvp = Reservation::new_pending(
@@ -294,14 +1206,40 @@ mod tests {
       "testResourceId",
       convert_local_time_to_utc("2024-01-21 19:00:00"),
       convert_local_time_to_utc("2024-01-22 12:00:00"),
-      "test_change_status_not_pending_should_donothing",
+      "test_transition_not_allowed_should_reject",
     );
 
     let rsvp = pool.reserve(rsvp).await.unwrap();
-    let rsvp = pool.change_status(rsvp.id).await.unwrap();
+    let rsvp = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 0)
+      .await
+      .unwrap();
+
+    let ret = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 0)
+      .await;
+    assert_eq!(
+      ret,
+      Err(Error::InvalidTransition {
+        from: ReservationStatus::Confirmed,
+        to: ReservationStatus::Confirmed,
+      })
+    );
 
-    let ret = pool.change_status(rsvp.id).await;
+    let ret = pool
+      .transition(rsvp.id, ReservationStatus::Expired, "admin", 0)
+      .await;
+    assert_eq!(
+      ret,
+      Err(Error::InvalidTransition {
+        from: ReservationStatus::Confirmed,
+        to: ReservationStatus::Expired,
+      })
+    );
 
+    let ret = pool
+      .transition(rsvp.id + 1, ReservationStatus::Confirmed, "admin", 0)
+      .await;
     assert_eq!(ret, Err(Error::NotFound))
   }
 
@@ -310,7 +1248,7 @@ mod tests {
     migrations = "../migrations"
   ))]
   async fn reserve_update_note_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "testUserId",
@@ -325,6 +1263,7 @@ mod tests {
       .update_note(
         rsvp.id,
         "test_update_note_reservation_should_work".to_string(),
+        0,
       )
       .await
       .unwrap();
@@ -334,12 +1273,177 @@ mod tests {
     )
   }
 
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn stale_expected_version_should_reject() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "testUserId",
+      "testResourceId",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    assert_eq!(rsvp.version, 1);
+    assert_eq!(pool.get(rsvp.id).await.unwrap(), rsvp);
+
+    // two admins read version 1, the second one to write loses
+    let first = pool
+      .update_note(rsvp.id, "first".to_string(), rsvp.version)
+      .await
+      .unwrap();
+    assert_eq!(first.version, 2);
+    assert!(
+      convert_to_utc_time(first.updated_at.unwrap())
+        >= convert_to_utc_time(rsvp.updated_at.unwrap())
+    );
+    let err = pool
+      .update_note(rsvp.id, "second".to_string(), rsvp.version)
+      .await
+      .unwrap_err();
+    let stale = Error::VersionMismatch {
+      expected: 1,
+      actual: 2,
+    };
+    assert_eq!(err, stale);
+    let err = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 1)
+      .await
+      .unwrap_err();
+    assert_eq!(err, stale);
+    let err = pool.cancel(rsvp.id, "admin", "", 1).await.unwrap_err();
+    assert_eq!(err, stale);
+    assert_eq!(pool.get(rsvp.id).await.unwrap().note, "first");
+
+    let cancelled = pool
+      .cancel(rsvp.id, "admin", "", first.version)
+      .await
+      .unwrap();
+    assert_eq!(cancelled.version, 3);
+  }
+
+  fn update_mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+      paths: paths.iter().map(|p| p.to_string()).collect(),
+    }
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_reservation_window_and_resource_should_work() {
+    let pool = make_manager(migrated_pool).await;
+    let rsvp = pool
+      .reserve(Reservation::new_pending(
+        "xiaozhangId",
+        "room-1",
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "keep me",
+      ))
+      .await
+      .unwrap();
+
+    let update = Reservation::new_pending(
+      "ignored",
+      "room-2",
+      convert_local_time_to_utc("2024-01-23 19:00:00"),
+      convert_local_time_to_utc("2024-01-24 12:00:00"),
+      "ignored",
+    );
+    let updated = pool
+      .update(
+        rsvp.id,
+        update.clone(),
+        update_mask(&["resource_id", "start", "end"]),
+        0,
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(updated.id, rsvp.id);
+    assert_eq!(updated.user_id, "xiaozhangId");
+    assert_eq!(updated.note, "keep me");
+    assert_eq!(updated.resource_id, "room-2");
+    assert_eq!((updated.start, updated.end), (update.start, update.end));
+    assert_eq!(pool.get(rsvp.id).await.unwrap(), updated);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_reservation_into_taken_slot_should_reject() {
+    let pool = make_manager(migrated_pool).await;
+    let make = |resource_id: &str| {
+      Reservation::new_pending(
+        "xiaozhangId",
+        resource_id,
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      )
+    };
+    pool.reserve(make("room-1")).await.unwrap();
+    let rsvp = pool.reserve(make("room-2")).await.unwrap();
+
+    let err = pool
+      .update(rsvp.id, make("room-1"), update_mask(&["resource_id"]), 0)
+      .await
+      .unwrap_err();
+    assert!(matches!(
+      err,
+      Error::ConflictReservation(ReservationConflictInfo::Parsed(_))
+    ));
+    assert_eq!(pool.get(rsvp.id).await.unwrap(), rsvp);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_reservation_with_invalid_fields_should_reject() {
+    let pool = make_manager(migrated_pool).await;
+    let rsvp = pool
+      .reserve(Reservation::new_pending(
+        "xiaozhangId",
+        "room-1",
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      ))
+      .await
+      .unwrap();
+
+    let err = pool
+      .update(rsvp.id, Reservation::default(), update_mask(&["status"]), 0)
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::InvalidUpdateMask("status".to_string()));
+
+    // end before start
+    let mut update = rsvp.clone();
+    update.end = Some(convert_to_timestamp(convert_local_time_to_utc(
+      "2024-01-20 12:00:00",
+    )));
+    let err = pool
+      .update(rsvp.id, update, update_mask(&["end"]), 0)
+      .await
+      .unwrap_err();
+    assert_eq!(err, Error::InvalidTime);
+  }
+
   #[sqlx_database_tester::test(pool(
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
   async fn reserve_get_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "testUserId",
@@ -350,7 +1454,7 @@ mod tests {
     );
 
     let rsvp = pool.reserve(rsvp).await.unwrap();
-    let rsvp1 = pool.get(rsvp.id.clone()).await.unwrap();
+    let rsvp1 = pool.get(rsvp.id).await.unwrap();
 
     assert_eq!(rsvp1, rsvp)
   }
@@ -359,8 +1463,8 @@ mod tests {
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
-  async fn reserve_delete_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+  async fn cancel_reservation_should_keep_row() {
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "testUserId",
@@ -371,10 +1475,148 @@ mod tests {
     );
 
     let rsvp = pool.reserve(rsvp).await.unwrap();
-    let rsvp1 = pool.delete(rsvp.id.clone()).await.unwrap();
+    let rsvp1 = pool
+      .cancel(rsvp.id, "admin", "double booked", 0)
+      .await
+      .unwrap();
 
-    let ret = pool.get(rsvp1.id.clone()).await;
-    assert_eq!(ret, Err(Error::NotFound))
+    assert_eq!(rsvp1.status, ReservationStatus::Cancelled as i32);
+    assert!(rsvp1.cancelled_at.is_some());
+    assert_eq!(rsvp1.cancelled_by, "admin");
+    assert_eq!(rsvp1.reason, "double booked");
+    assert_eq!(pool.get(rsvp1.id).await.unwrap(), rsvp1);
+
+    let ret = pool.cancel(rsvp.id, "admin", "", 0).await;
+    assert_eq!(
+      ret,
+      Err(Error::InvalidTransition {
+        from: ReservationStatus::Cancelled,
+        to: ReservationStatus::Cancelled,
+      })
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn restore_reservation_should_work() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "testUserId",
+      "testResourceId",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let confirmed = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 0)
+      .await
+      .unwrap();
+    pool.cancel(rsvp.id, "admin", "mistake", 0).await.unwrap();
+
+    // back as it was, but cancelling and restoring are updates as well
+    let restored = pool.restore(rsvp.id, "admin", 0).await.unwrap();
+    assert_eq!(restored.version, confirmed.version + 2);
+    assert_eq!(
+      Reservation {
+        version: confirmed.version,
+        updated_at: confirmed.updated_at,
+        ..restored
+      },
+      confirmed
+    );
+
+    let ret = pool.restore(rsvp.id, "admin", 0).await;
+    assert_eq!(ret, Err(Error::NotCancelled(ReservationStatus::Confirmed)));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn restore_should_check_like_a_new_reservation() {
+    let quota = abi::QuotaConfig {
+      max_active: 1,
+      ..Default::default()
+    };
+    let pool = make_manager(migrated_pool).await.with_quota(quota);
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "room-1",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let cancelled = pool.cancel(rsvp.id, "xiaozhangId", "", 0).await.unwrap();
+    let other = Reservation::new_pending(
+      "xiaozhangId",
+      "room-2",
+      convert_local_time_to_utc("2024-01-23 19:00:00"),
+      convert_local_time_to_utc("2024-01-24 12:00:00"),
+      "",
+    );
+    let other = pool.reserve(other).await.unwrap();
+
+    let ret = pool.restore(rsvp.id, "xiaozhangId", rsvp.version).await;
+    assert_eq!(
+      ret,
+      Err(Error::VersionMismatch {
+        expected: rsvp.version,
+        actual: cancelled.version,
+      })
+    );
+    let ret = pool
+      .restore(rsvp.id, "xiaozhangId", cancelled.version)
+      .await
+      .unwrap_err();
+    assert!(matches!(ret, Error::QuotaExceeded(_)));
+
+    pool.cancel(other.id, "xiaozhangId", "", 0).await.unwrap();
+    let mut resource = pool.get_resource("room-1").await.unwrap();
+    resource.active = false;
+    pool.update_resource(resource).await.unwrap();
+    let ret = pool.restore(rsvp.id, "xiaozhangId", 0).await;
+    assert_eq!(ret, Err(Error::ResourceDisabled("room-1".to_string())));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn restore_taken_slot_should_reject() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "ocean-view-room-713",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    pool.cancel(rsvp.id, "xiaozhangId", "", 0).await.unwrap();
+
+    let rsvp1 = Reservation::new_pending(
+      "xiaonanId",
+      "ocean-view-room-713",
+      convert_local_time_to_utc("2024-01-22 8:00:00"),
+      convert_local_time_to_utc("2024-01-23 12:00:00"),
+      "",
+    );
+    pool.reserve(rsvp1).await.unwrap();
+
+    let ret = pool.restore(rsvp.id, "xiaozhangId", 0).await.unwrap_err();
+    assert!(matches!(
+      ret,
+      Error::ConflictReservation(ReservationConflictInfo::Parsed(_))
+    ));
+    let rsvp = pool.get(rsvp.id).await.unwrap();
+    assert_eq!(rsvp.status, ReservationStatus::Cancelled as i32);
   }
 
   #[sqlx_database_tester::test(pool(
@@ -382,7 +1624,7 @@ mod tests {
     migrations = "../migrations"
   ))]
   async fn query_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "xiaozhangId",
@@ -402,16 +1644,69 @@ mod tests {
       .build()
       .unwrap();
 
-    let result = pool.query(query).await.unwrap();
+    let result: Vec<_> = pool.query(query).await.unwrap().collect().await;
 
     assert_eq!(result.len(), 1);
+    assert!(result[0].is_ok());
   }
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn query_reservation_should_stream_in_order() {
+    let pool = make_manager(migrated_pool).await;
+
+    for day in 21..24 {
+      let rsvp = Reservation::new_pending(
+        "xiaozhangId",
+        "testResourceId",
+        convert_local_time_to_utc(&format!("2024-01-{} 08:00:00", day)),
+        convert_local_time_to_utc(&format!("2024-01-{} 12:00:00", day)),
+        "",
+      );
+      pool.reserve(rsvp).await.unwrap();
+    }
+
+    let query = ReservationQueryBuilder::default()
+      .resource_id("testResourceId")
+      .start("2024-01-20 00:00:00".parse::<Timestamp>().unwrap())
+      .end("2024-01-25 00:00:00".parse::<Timestamp>().unwrap())
+      .status(ReservationStatus::Pending as i32)
+      .desc(true)
+      .build()
+      .unwrap();
+
+    let mut stream = pool.query(query.clone()).await.unwrap();
+    for day in (21..24).rev() {
+      let rsvp = stream.next().await.unwrap().unwrap();
+      assert_eq!(
+        rsvp.start.unwrap(),
+        convert_to_timestamp(convert_local_time_to_utc(&format!(
+          "2024-01-{} 08:00:00",
+          day
+        )))
+      );
+    }
+    // the stream ends once the background fetch is done
+    assert!(stream.next().await.is_none());
+
+    // a receiver gone early should stop the background fetch
+    let (tx, mut rx) = mpsc::channel(1);
+    let handle = pool.spawn_query(query, None, tx);
+    assert!(rx.recv().await.unwrap().is_ok());
+    drop(rx);
+    tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+      .await
+      .unwrap()
+      .unwrap();
+  }
+
   #[sqlx_database_tester::test(pool(
     variable = "migrated_pool",
     migrations = "../migrations"
   ))]
   async fn filter_reservation_should_work() {
-    let pool = ReservationManage::new(migrated_pool);
+    let pool = make_manager(migrated_pool).await;
 
     let rsvp = Reservation::new_pending(
       "xiaozhangId",
@@ -438,4 +1733,511 @@ mod tests {
 
     assert_eq!(rsvps.len(), 1);
   }
+
+  async fn make_busy_day(pool: &ReservationManage) {
+    let spans = [
+      ("room-1", "2024-01-21 10:10:00", "2024-01-21 11:00:00"),
+      ("room-1", "2024-01-21 13:00:00", "2024-01-21 14:00:00"),
+      ("room-1", "2024-01-21 15:00:00", "2024-01-21 16:00:00"),
+      ("room-2", "2024-01-21 09:00:00", "2024-01-21 17:00:00"),
+    ];
+    for (resource_id, start, end) in spans {
+      pool
+        .reserve(make_batch_item(resource_id, start, end))
+        .await
+        .unwrap();
+    }
+    // cancelled reservations don't take the slot
+    pool.cancel(3, "xiaozhangId", "", 0).await.unwrap();
+  }
+
+  fn availability_query(
+    min_duration: Option<Duration>,
+    granularity: Option<Duration>,
+  ) -> abi::AvailabilityQuery {
+    let to_duration = |d: Duration| prost_types::Duration {
+      seconds: d.num_seconds(),
+      nanos: 0,
+    };
+    abi::AvailabilityQuery {
+      resource_id: "room-1".to_string(),
+      start: Some(convert_to_timestamp(convert_local_time_to_utc(
+        "2024-01-21 09:00:00",
+      ))),
+      end: Some(convert_to_timestamp(convert_local_time_to_utc(
+        "2024-01-21 17:00:00",
+      ))),
+      min_duration: min_duration.map(to_duration),
+      granularity: granularity.map(to_duration),
+    }
+  }
+
+  fn window_of(
+    window: &abi::AvailableWindow,
+  ) -> (DateTime<Utc>, DateTime<Utc>) {
+    (
+      convert_to_utc_time(window.start.unwrap()),
+      convert_to_utc_time(window.end.unwrap()),
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn find_availability_should_return_free_slots() {
+    let pool = make_manager(migrated_pool).await;
+    make_busy_day(&pool).await;
+
+    let windows = pool
+      .find_availability(availability_query(None, None))
+      .await
+      .unwrap();
+    let windows: Vec<_> = windows.iter().map(window_of).collect();
+    let t = convert_local_time_to_utc;
+    assert_eq!(
+      windows,
+      vec![
+        (t("2024-01-21 09:00:00"), t("2024-01-21 10:10:00")),
+        (t("2024-01-21 11:00:00"), t("2024-01-21 13:00:00")),
+        (t("2024-01-21 14:00:00"), t("2024-01-21 17:00:00")),
+      ]
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn find_availability_should_honour_duration_and_granularity() {
+    let pool = make_manager(migrated_pool).await;
+    make_busy_day(&pool).await;
+
+    let query = availability_query(
+      Some(Duration::minutes(90)),
+      Some(Duration::minutes(30)),
+    );
+    let windows = pool.find_availability(query).await.unwrap();
+    let windows: Vec<_> = windows.iter().map(window_of).collect();
+    let t = convert_local_time_to_utc;
+    // 09:00-10:10 is cut to 09:00-10:00, shorter than 90 minutes
+    assert_eq!(
+      windows,
+      vec![
+        (t("2024-01-21 11:00:00"), t("2024-01-21 13:00:00")),
+        (t("2024-01-21 14:00:00"), t("2024-01-21 17:00:00")),
+      ]
+    );
+
+    let mut query = availability_query(None, None);
+    query.resource_id = "room-2".to_string();
+    assert!(pool.find_availability(query).await.unwrap().is_empty());
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_should_keep_buffers_free() {
+    let pool = make_manager(migrated_pool).await;
+    let mut resource = pool.get_resource("room-1").await.unwrap();
+    resource.buffer_before = Some(prost_types::Duration {
+      seconds: 15 * 60,
+      nanos: 0,
+    });
+    resource.buffer_after = Some(prost_types::Duration {
+      seconds: 30 * 60,
+      nanos: 0,
+    });
+    pool.update_resource(resource).await.unwrap();
+    make_busy_day(&pool).await;
+
+    // 11:00 plus 30 minutes after meets 11:20 minus 15 minutes before
+    let rsvp =
+      make_batch_item("room-1", "2024-01-21 11:20:00", "2024-01-21 12:00:00");
+    let err = pool.reserve(rsvp).await.unwrap_err();
+    assert!(
+      matches!(&err, Error::ConflictReservation(info) if info.is_buffer_only())
+    );
+
+    let rsvp =
+      make_batch_item("room-1", "2024-01-21 10:30:00", "2024-01-21 11:30:00");
+    let err = pool.reserve(rsvp).await.unwrap_err();
+    assert!(
+      matches!(&err, Error::ConflictReservation(info) if !info.is_buffer_only())
+    );
+
+    let windows = pool
+      .find_availability(availability_query(None, None))
+      .await
+      .unwrap();
+    let windows: Vec<_> = windows.iter().map(window_of).collect();
+    let t = convert_local_time_to_utc;
+    assert_eq!(
+      windows,
+      vec![
+        (t("2024-01-21 09:00:00"), t("2024-01-21 09:25:00")),
+        (t("2024-01-21 11:45:00"), t("2024-01-21 12:15:00")),
+        (t("2024-01-21 14:45:00"), t("2024-01-21 17:00:00")),
+      ]
+    );
+
+    let rsvp =
+      make_batch_item("room-1", "2024-01-21 11:45:00", "2024-01-21 12:15:00");
+    pool.reserve(rsvp).await.unwrap();
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn listen_should_receive_changes() {
+    let pool = make_manager(migrated_pool).await;
+    let mut stream = pool.listen(0).await.unwrap();
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "testResourceId",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "test_listen_should_receive_changes",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let confirmed = pool
+      .transition(rsvp.id, ReservationStatus::Confirmed, "admin", 0)
+      .await
+      .unwrap();
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Create as i32);
+    assert_eq!(change.old, None);
+    assert_eq!(change.new, Some(rsvp.clone()));
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Update as i32);
+    assert_eq!(change.old, Some(rsvp));
+    assert_eq!(change.new, Some(confirmed));
+    assert_eq!(change.operator, "admin");
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn listen_should_receive_updates_keeping_the_status() {
+    let pool = make_manager(migrated_pool).await;
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "room-1",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let mut stream = pool.listen(1).await.unwrap();
+
+    let noted = pool
+      .update_note(rsvp.id, "moved".to_string(), 0)
+      .await
+      .unwrap();
+    let update = Reservation::new_pending(
+      "ignored",
+      "room-2",
+      convert_local_time_to_utc("2024-01-23 19:00:00"),
+      convert_local_time_to_utc("2024-01-24 12:00:00"),
+      "ignored",
+    );
+    let moved = pool
+      .update(
+        rsvp.id,
+        update,
+        update_mask(&["resource_id", "start", "end"]),
+        0,
+      )
+      .await
+      .unwrap();
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Update as i32);
+    assert_eq!(change.old, Some(rsvp));
+    assert_eq!(change.new, Some(noted.clone()));
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.old, Some(noted));
+    assert_eq!(change.new, Some(moved));
+    // no transition, so no operator
+    assert_eq!(change.operator, "");
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn listen_should_receive_changes_in_commit_order() {
+    let pool = make_manager(migrated_pool).await;
+    let mut stream = pool.listen(0).await.unwrap();
+
+    // the first change gets the lower id but is committed last
+    let mut tx = pool.pool.begin().await.unwrap();
+    let first = pool
+      .reserve_in(
+        &mut tx,
+        Reservation::new_pending(
+          "xiaozhangId",
+          "room-1",
+          convert_local_time_to_utc("2024-01-21 19:00:00"),
+          convert_local_time_to_utc("2024-01-22 12:00:00"),
+          "",
+        ),
+      )
+      .await
+      .unwrap();
+    let second = pool
+      .reserve(Reservation::new_pending(
+        "xiaozhangId",
+        "room-2",
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      ))
+      .await
+      .unwrap();
+    tx.commit().await.unwrap();
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.new, Some(first));
+    let first_change_id = change.id;
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.new, Some(second.clone()));
+    assert!(change.id > first_change_id);
+
+    // resuming after the first change doesn't skip the second one
+    let mut stream = pool.listen(first_change_id).await.unwrap();
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.new, Some(second));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn listen_should_resume_from_last_change_id() {
+    let pool = make_manager(migrated_pool).await;
+
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "testResourceId",
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let cancelled = pool.cancel(rsvp.id, "admin", "", 0).await.unwrap();
+
+    let mut stream = pool.listen(1).await.unwrap();
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.id, 2);
+    assert_eq!(change.op, ReservationUpdateType::Update as i32);
+    assert_eq!(change.old, Some(rsvp));
+    assert_eq!(change.new, Some(cancelled));
+  }
+
+  async fn make_reservations(pool: &ReservationManage, count: usize) {
+    for i in 0..count {
+      let rsvp = Reservation::new_pending(
+        "xiaozhangId",
+        format!("room-{}", i),
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      );
+      pool.reserve(rsvp).await.unwrap();
+    }
+  }
+
+  async fn filter_page(
+    pool: &ReservationManage,
+    cursor: i64,
+    desc: bool,
+  ) -> (abi::FilterPager, Vec<i64>) {
+    let filter = ReservationFilterBuilder::default()
+      .user_id("xiaozhangId")
+      .status(ReservationStatus::Pending as i32)
+      .cursor(cursor)
+      .desc(desc)
+      .build()
+      .unwrap();
+    let (pager, rsvps) = pool.filter(filter).await.unwrap();
+
+    (pager, rsvps.iter().map(|rsvp| rsvp.id).collect())
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn filter_should_page_forward_and_backward() {
+    let pool = make_manager(migrated_pool).await;
+    make_reservations(&pool, 25).await;
+
+    let (pager, ids) = filter_page(&pool, 0, false).await;
+    assert_eq!(ids, (1..=10).collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next, pager.total), (-1, 10, 25));
+
+    let (pager, ids) = filter_page(&pool, pager.next, false).await;
+    assert_eq!(ids, (11..=20).collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next, pager.total), (0, 20, 25));
+
+    let (pager, ids) = filter_page(&pool, pager.next, false).await;
+    assert_eq!(ids, (21..=25).collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next, pager.total), (10, -1, 25));
+
+    let (pager, ids) = filter_page(&pool, pager.prev, false).await;
+    assert_eq!(ids, (11..=20).collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next), (0, 20));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn filter_should_page_in_desc_order() {
+    let pool = make_manager(migrated_pool).await;
+    make_reservations(&pool, 25).await;
+
+    let (pager, ids) = filter_page(&pool, 0, true).await;
+    assert_eq!(ids, (16..=25).rev().collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next, pager.total), (-1, 16, 25));
+
+    let (pager, ids) = filter_page(&pool, pager.next, true).await;
+    assert_eq!(ids, (6..=15).rev().collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next), (0, 6));
+
+    let (pager, ids) = filter_page(&pool, pager.next, true).await;
+    assert_eq!(ids, (1..=5).rev().collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next), (16, -1));
+
+    let (pager, ids) = filter_page(&pool, pager.prev, true).await;
+    assert_eq!(ids, (6..=15).rev().collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next), (0, 6));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn filter_should_work_when_cursor_row_deleted() {
+    let pool = make_manager(migrated_pool).await;
+    make_reservations(&pool, 25).await;
+    sqlx::query("DELETE FROM rsvp.reservations WHERE id = 10")
+      .execute(&pool.pool)
+      .await
+      .unwrap();
+
+    let (pager, ids) = filter_page(&pool, 10, false).await;
+    assert_eq!(ids, (11..=20).collect::<Vec<_>>());
+    assert_eq!((pager.prev, pager.next, pager.total), (0, 20, 24));
+
+    let (pager, ids) = filter_page(&pool, 0, false).await;
+    assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 11]);
+    assert_eq!((pager.prev, pager.next), (-1, 11));
+  }
+
+  fn make_hold(
+    resource_id: &str,
+    hold_until: Option<DateTime<Utc>>,
+  ) -> Reservation {
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    );
+    rsvp.hold_until = hold_until.map(convert_to_timestamp);
+    rsvp
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_with_hold_ttl_should_set_hold_until() {
+    let pool = make_manager(migrated_pool)
+      .await
+      .with_hold_ttl(Duration::minutes(15));
+
+    let rsvp = pool.reserve(make_hold("room-1", None)).await.unwrap();
+    let hold_until = convert_to_utc_time(rsvp.hold_until.unwrap());
+    assert!(hold_until > Utc::now() + Duration::minutes(14));
+    assert_eq!(pool.get(rsvp.id).await.unwrap(), rsvp);
+
+    let mut blocked = make_hold("room-2", None);
+    blocked.status = ReservationStatus::Blocked as i32;
+    let blocked = pool.reserve(blocked).await.unwrap();
+    assert_eq!(blocked.hold_until, None);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn expire_should_only_expire_stale_pending_holds() {
+    let pool = make_manager(migrated_pool).await;
+    let now = convert_local_time_to_utc("2024-01-20 12:00:00");
+
+    let stale = pool
+      .reserve(make_hold("room-1", Some(now - Duration::minutes(1))))
+      .await
+      .unwrap();
+    let fresh = pool
+      .reserve(make_hold("room-2", Some(now + Duration::minutes(1))))
+      .await
+      .unwrap();
+    let forever = pool.reserve(make_hold("room-3", None)).await.unwrap();
+    let confirmed = pool
+      .reserve(make_hold("room-4", Some(now - Duration::minutes(1))))
+      .await
+      .unwrap();
+    pool
+      .transition(confirmed.id, ReservationStatus::Confirmed, "admin", 0)
+      .await
+      .unwrap();
+
+    let expired = pool.expire(now, 10).await.unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].id, stale.id);
+    assert_eq!(expired[0].status, ReservationStatus::Expired as i32);
+
+    for rsvp in [fresh, forever] {
+      let rsvp = pool.get(rsvp.id).await.unwrap();
+      assert_eq!(rsvp.status, ReservationStatus::Pending as i32);
+    }
+
+    let expired = pool.expire(now + Duration::minutes(2), 10).await.unwrap();
+    assert_eq!(expired.len(), 1);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn expire_should_work_in_batches() {
+    let pool = make_manager(migrated_pool).await;
+    let now = convert_local_time_to_utc("2024-01-20 12:00:00");
+
+    for i in 0..5 {
+      let hold_until = now - Duration::minutes(i);
+      pool
+        .reserve(make_hold(&format!("room-{}", i), Some(hold_until)))
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(pool.expire(now, 2).await.unwrap().len(), 2);
+    assert_eq!(pool.expire(now, 2).await.unwrap().len(), 2);
+    assert_eq!(pool.expire(now, 2).await.unwrap().len(), 1);
+    assert_eq!(pool.expire(now, 2).await.unwrap().len(), 0);
+  }
 }
@@ -0,0 +1,514 @@
+use crate::{Quotas, ReservationManage};
+use abi::{Error, QuotaKind, QuotaUsage};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use sqlx::{postgres::types::PgRange, PgConnection};
+use std::ops::Bound;
+
+#[async_trait]
+impl Quotas for ReservationManage {
+  async fn get_quota(
+    &self,
+    user_id: &str,
+    at: DateTime<Utc>,
+  ) -> Result<Vec<QuotaUsage>, Error> {
+    if user_id.is_empty() {
+      return Err(Error::InvalidUserId(user_id.to_string()));
+    }
+
+    let mut conn = self.pool.acquire().await?;
+    let mut quotas = Vec::new();
+
+    if self.quota.max_active > 0 {
+      let used = active_count(&mut conn, user_id, 0).await?;
+      quotas.push(usage(
+        QuotaKind::ActiveReservations,
+        "",
+        self.quota.max_active,
+        used,
+      ));
+    }
+    if self.quota.max_concurrent > 0 {
+      let moment = PgRange {
+        start: Bound::Included(at),
+        end: Bound::Included(at),
+      };
+      let used = concurrent_count(&mut conn, user_id, moment, 0).await?;
+      quotas.push(usage(
+        QuotaKind::ConcurrentReservations,
+        "",
+        self.quota.max_concurrent,
+        used,
+      ));
+    }
+    for (resource_type, hours) in &self.quota.weekly_hours {
+      if *hours <= 0 {
+        continue;
+      }
+      let week = week_of(at);
+      let used = weekly_seconds(
+        &mut conn,
+        user_id,
+        resource_type,
+        week,
+        week + Duration::weeks(1),
+        0,
+      )
+      .await?
+      .first()
+      .map_or(0, |(_, used)| *used);
+      quotas.push(usage(
+        QuotaKind::WeeklyHours,
+        resource_type,
+        hours * 3600,
+        used,
+      ));
+    }
+
+    Ok(quotas)
+  }
+}
+
+impl ReservationManage {
+  /// make sure reserving `rsvp` keeps its user within every quota. the user
+  /// stays locked until the transaction ends, so reservations made at the
+  /// same time are counted as well. an updated reservation doesn't count
+  /// what it used before the update
+  pub(crate) async fn check_quota(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+  ) -> Result<(), Error> {
+    if self.quota.is_empty() {
+      return Ok(());
+    }
+    sqlx::query(
+      "SELECT pg_advisory_xact_lock(hashtext('rsvp.quota'), hashtext($1))",
+    )
+    .bind(&rsvp.user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let span = rsvp.get_timespan();
+    if self.quota.max_active > 0 {
+      let used = active_count(&mut *conn, &rsvp.user_id, rsvp.id).await?;
+      check(
+        QuotaKind::ActiveReservations,
+        "",
+        self.quota.max_active,
+        used,
+        1,
+      )?;
+    }
+    if self.quota.max_concurrent > 0 {
+      let during = span.clone().into();
+      let used =
+        concurrent_count(&mut *conn, &rsvp.user_id, during, rsvp.id).await?;
+      check(
+        QuotaKind::ConcurrentReservations,
+        "",
+        self.quota.max_concurrent,
+        used,
+        1,
+      )?;
+    }
+
+    if self.quota.weekly_hours.is_empty() {
+      return Ok(());
+    }
+    let resource_type: String =
+      sqlx::query_scalar("SELECT rtype FROM rsvp.resources WHERE id = $1")
+        .bind(&rsvp.resource_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .unwrap_or_default();
+    let Some(hours) = self
+      .quota
+      .weekly_hours
+      .get(&resource_type)
+      .filter(|hours| **hours > 0)
+    else {
+      return Ok(());
+    };
+
+    // a reservation over the weekend counts towards both weeks
+    let weeks = weekly_seconds(
+      &mut *conn,
+      &rsvp.user_id,
+      &resource_type,
+      span.start,
+      span.end,
+      rsvp.id,
+    )
+    .await?;
+    for (week, used) in weeks {
+      let next = week + Duration::weeks(1);
+      let requested = (span.end.min(next) - span.start.max(week)).num_seconds();
+      check(
+        QuotaKind::WeeklyHours,
+        &resource_type,
+        hours * 3600,
+        used,
+        requested,
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+fn check(
+  kind: QuotaKind,
+  resource_type: &str,
+  limit: i64,
+  used: i64,
+  requested: i64,
+) -> Result<(), Error> {
+  if used + requested > limit {
+    return Err(Error::QuotaExceeded(usage(
+      kind,
+      resource_type,
+      limit,
+      used,
+    )));
+  }
+  Ok(())
+}
+
+fn usage(
+  kind: QuotaKind,
+  resource_type: &str,
+  limit: i64,
+  used: i64,
+) -> QuotaUsage {
+  QuotaUsage {
+    kind: kind as i32,
+    resource_type: resource_type.to_string(),
+    limit,
+    used,
+    remaining: (limit - used).max(0),
+  }
+}
+
+/// monday 00:00 UTC of the week `time` is in
+fn week_of(time: DateTime<Utc>) -> DateTime<Utc> {
+  let date = time.date_naive();
+  let monday =
+    date - Duration::days(date.weekday().num_days_from_monday() as i64);
+
+  monday.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// active reservations of the user, but the one with id `except`
+async fn active_count(
+  conn: &mut PgConnection,
+  user_id: &str,
+  except: i64,
+) -> Result<i64, Error> {
+  let count = sqlx::query_scalar(
+    "SELECT count(*) FROM rsvp.reservations WHERE user_id = $1 AND id <> $2 AND status IN ('pending', 'confirmed', 'blocked')",
+  )
+  .bind(user_id)
+  .bind(except)
+  .fetch_one(conn)
+  .await?;
+
+  Ok(count)
+}
+
+/// peak number of active reservations of the user, but the one with id
+/// `except`, overlapping at any time during `during`
+async fn concurrent_count(
+  conn: &mut PgConnection,
+  user_id: &str,
+  during: PgRange<DateTime<Utc>>,
+  except: i64,
+) -> Result<i64, Error> {
+  let count = sqlx::query_scalar(
+    r#"WITH active AS (
+        SELECT timespan FROM rsvp.reservations
+        WHERE user_id = $1
+            AND id <> $3
+            AND timespan && $2
+            AND status IN ('pending', 'confirmed', 'blocked')
+    ), points AS (
+        SELECT lower($2::tstzrange) AS t
+        UNION
+        SELECT lower(timespan) FROM active WHERE $2 @> lower(timespan)
+    )
+    SELECT COALESCE(max(used), 0) FROM (
+        SELECT (SELECT count(*) FROM active a WHERE a.timespan @> p.t) AS used
+        FROM points p
+    ) usage"#,
+  )
+  .bind(user_id)
+  .bind(during)
+  .bind(except)
+  .fetch_one(conn)
+  .await?;
+
+  Ok(count)
+}
+
+/// seconds of active reservations of the user on resources of the type, but
+/// the one with id `except`, within every week overlapping `start..end`, by
+/// the monday starting it
+async fn weekly_seconds(
+  conn: &mut PgConnection,
+  user_id: &str,
+  resource_type: &str,
+  start: DateTime<Utc>,
+  end: DateTime<Utc>,
+  except: i64,
+) -> Result<Vec<(DateTime<Utc>, i64)>, Error> {
+  let weeks = sqlx::query_as(
+    r#"SELECT lower(w.week), COALESCE(sum(EXTRACT(EPOCH FROM upper(r.timespan * w.week) - lower(r.timespan * w.week))), 0)::bigint
+    FROM (
+        SELECT tstzrange(monday, monday + interval '7 days') AS week
+        FROM generate_series($3::timestamptz, $4::timestamptz, interval '7 days') AS monday
+        WHERE monday < $4
+    ) w
+    LEFT JOIN (
+        rsvp.reservations r JOIN rsvp.resources res ON res.id = r.resource_id
+    ) ON r.user_id = $1
+        AND r.id <> $5
+        AND res.rtype = $2
+        AND r.timespan && w.week
+        AND r.status IN ('pending', 'confirmed', 'blocked')
+    GROUP BY w.week
+    ORDER BY w.week"#,
+  )
+  .bind(user_id)
+  .bind(resource_type)
+  .bind(week_of(start))
+  .bind(end)
+  .bind(except)
+  .fetch_all(conn)
+  .await?;
+
+  Ok(weeks)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{QuotaConfig, Reservation};
+  use prost_types::FieldMask;
+
+  async fn make_manager(
+    pool: sqlx::PgPool,
+    quota: QuotaConfig,
+  ) -> ReservationManage {
+    let manager = ReservationManage::new(pool).with_quota(quota);
+    for (id, resource_type) in
+      [("room-1", "room"), ("room-2", "room"), ("desk-1", "desk")]
+    {
+      let resource = abi::Resource {
+        id: id.to_string(),
+        name: id.to_string(),
+        resource_type: resource_type.to_string(),
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+    }
+    manager
+  }
+
+  fn make_reservation(
+    resource_id: &str,
+    start: &str,
+    end: &str,
+  ) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      start.parse().unwrap(),
+      end.parse().unwrap(),
+      "",
+    )
+  }
+
+  fn time(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_should_keep_to_active_and_concurrent_quotas() {
+    let quota = QuotaConfig {
+      max_active: 3,
+      max_concurrent: 1,
+      ..Default::default()
+    };
+    let pool = make_manager(migrated_pool, quota).await;
+
+    let rsvp = make_reservation(
+      "room-1",
+      "2024-01-22T09:00:00Z",
+      "2024-01-22T10:00:00Z",
+    );
+    pool.reserve(rsvp).await.unwrap();
+
+    let rsvp = make_reservation(
+      "room-2",
+      "2024-01-22T09:30:00Z",
+      "2024-01-22T10:30:00Z",
+    );
+    let err = pool.reserve(rsvp).await.unwrap_err();
+    let Error::QuotaExceeded(exceeded) = err else {
+      panic!("expect quota exceeded, got {:?}", err);
+    };
+    assert_eq!(exceeded.kind, QuotaKind::ConcurrentReservations as i32);
+    assert_eq!((exceeded.limit, exceeded.used), (1, 1));
+
+    for day in 23..25 {
+      let rsvp = make_reservation(
+        "room-2",
+        &format!("2024-01-{}T09:00:00Z", day),
+        &format!("2024-01-{}T10:00:00Z", day),
+      );
+      pool.reserve(rsvp).await.unwrap();
+    }
+    let rsvp = make_reservation(
+      "room-2",
+      "2024-01-25T09:00:00Z",
+      "2024-01-25T10:00:00Z",
+    );
+    let err = pool.reserve(rsvp).await.unwrap_err();
+    assert!(matches!(
+      err,
+      Error::QuotaExceeded(QuotaUsage {
+        limit: 3,
+        used: 3,
+        ..
+      })
+    ));
+
+    let quotas = pool
+      .get_quota("xiaozhangId", time("2024-01-22T09:15:00Z"))
+      .await
+      .unwrap();
+    assert_eq!(
+      quotas,
+      vec![
+        usage(QuotaKind::ActiveReservations, "", 3, 3),
+        usage(QuotaKind::ConcurrentReservations, "", 1, 1),
+      ]
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_should_keep_to_weekly_hours_by_resource_type() {
+    let quota = QuotaConfig {
+      weekly_hours: [("room".to_string(), 4)].into(),
+      ..Default::default()
+    };
+    let pool = make_manager(migrated_pool, quota).await;
+
+    // 2024-01-22 is a monday, 3 hours of the weekend before count for last
+    // week
+    let rsvp = make_reservation(
+      "room-1",
+      "2024-01-21T21:00:00Z",
+      "2024-01-22T02:00:00Z",
+    );
+    pool.reserve(rsvp).await.unwrap();
+    let rsvp = make_reservation(
+      "room-2",
+      "2024-01-23T09:00:00Z",
+      "2024-01-23T11:00:00Z",
+    );
+    pool.reserve(rsvp).await.unwrap();
+
+    let rsvp = make_reservation(
+      "room-1",
+      "2024-01-24T09:00:00Z",
+      "2024-01-24T10:30:00Z",
+    );
+    let err = pool.reserve(rsvp).await.unwrap_err();
+    let Error::QuotaExceeded(exceeded) = err else {
+      panic!("expect quota exceeded, got {:?}", err);
+    };
+    assert_eq!(
+      exceeded,
+      usage(QuotaKind::WeeklyHours, "room", 4 * 3600, 4 * 3600)
+    );
+
+    // desks have no weekly limit
+    let rsvp = make_reservation(
+      "desk-1",
+      "2024-01-24T09:00:00Z",
+      "2024-01-24T18:00:00Z",
+    );
+    pool.reserve(rsvp).await.unwrap();
+
+    let quotas = pool
+      .get_quota("xiaozhangId", time("2024-01-28T12:00:00Z"))
+      .await
+      .unwrap();
+    assert_eq!(
+      quotas,
+      vec![usage(QuotaKind::WeeklyHours, "room", 4 * 3600, 4 * 3600)]
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_should_keep_to_weekly_hours() {
+    let quota = QuotaConfig {
+      weekly_hours: [("room".to_string(), 4)].into(),
+      ..Default::default()
+    };
+    let pool = make_manager(migrated_pool, quota).await;
+
+    let full = make_reservation(
+      "room-1",
+      "2024-01-23T09:00:00Z",
+      "2024-01-23T13:00:00Z",
+    );
+    pool.reserve(full).await.unwrap();
+    let rsvp = make_reservation(
+      "room-2",
+      "2024-01-30T09:00:00Z",
+      "2024-01-30T11:00:00Z",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    let mask = FieldMask {
+      paths: vec!["start".to_string(), "end".to_string()],
+    };
+
+    // what it used before the update isn't counted twice
+    let longer = make_reservation(
+      "room-2",
+      "2024-01-30T09:00:00Z",
+      "2024-01-30T13:00:00Z",
+    );
+    pool.update(rsvp.id, longer, mask.clone(), 0).await.unwrap();
+
+    // but it can't move into a week that is full already
+    let moved = make_reservation(
+      "room-2",
+      "2024-01-24T09:00:00Z",
+      "2024-01-24T10:00:00Z",
+    );
+    let err = pool.update(rsvp.id, moved, mask, 0).await.unwrap_err();
+    assert_eq!(
+      err,
+      Error::QuotaExceeded(usage(
+        QuotaKind::WeeklyHours,
+        "room",
+        4 * 3600,
+        4 * 3600
+      ))
+    );
+  }
+}
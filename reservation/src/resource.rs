@@ -0,0 +1,468 @@
+use crate::{ReservationManage, ResourceCatalogue};
+use abi::{parse_timezone, Error, Validator};
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use sqlx::PgConnection;
+
+#[async_trait]
+impl ResourceCatalogue for ReservationManage {
+  async fn create_resource(
+    &self,
+    resource: abi::Resource,
+  ) -> Result<abi::Resource, Error> {
+    resource.validate()?;
+    let (buffer_before, buffer_after) = resource.get_buffers()?;
+
+    let mut tx = self.pool.begin().await?;
+
+    let mut created: abi::Resource = sqlx::query_as(
+      "INSERT INTO rsvp.resources (id, name, rtype, capacity, timezone, attributes, buffer_before, buffer_after) VALUES ($1, $2, $3, $4, COALESCE(NULLIF($5, ''), 'UTC'), $6, $7, $8) RETURNING *",
+    )
+    .bind(&resource.id)
+    .bind(&resource.name)
+    .bind(&resource.resource_type)
+    .bind(resource.capacity)
+    .bind(&resource.timezone)
+    .bind(resource.get_attributes()?)
+    .bind(buffer_before)
+    .bind(buffer_after)
+    .fetch_one(&mut tx)
+    .await?;
+
+    self
+      .save_rules(&mut tx, &created.id, resource.rules.as_ref())
+      .await?;
+    created.rules = self.load_rules(&mut tx, &created.id).await?;
+
+    tx.commit().await?;
+
+    Ok(created)
+  }
+
+  async fn get_resource(&self, id: &str) -> Result<abi::Resource, Error> {
+    let mut conn = self.pool.acquire().await?;
+
+    let mut resource: abi::Resource =
+      sqlx::query_as("SELECT * FROM rsvp.resources WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut conn)
+        .await?
+        .ok_or_else(|| Error::UnknownResource(id.to_string()))?;
+    resource.rules = self.load_rules(&mut conn, id).await?;
+
+    Ok(resource)
+  }
+
+  async fn update_resource(
+    &self,
+    resource: abi::Resource,
+  ) -> Result<abi::Resource, Error> {
+    resource.validate()?;
+    let (buffer_before, buffer_after) = resource.get_buffers()?;
+
+    let mut tx = self.pool.begin().await?;
+
+    // the lock the capacity trigger takes, so no reservation of the resource
+    // is written while the new capacity is checked against what is reserved
+    sqlx::query(
+      "SELECT pg_advisory_xact_lock(hashtext('rsvp.resources'), hashtext($1))",
+    )
+    .bind(&resource.id)
+    .execute(&mut tx)
+    .await?;
+
+    let mut updated: abi::Resource = sqlx::query_as(
+      "UPDATE rsvp.resources SET name = $2, rtype = $3, capacity = $4, timezone = COALESCE(NULLIF($5, ''), 'UTC'), attributes = $6, active = $7, buffer_before = $8, buffer_after = $9, updated_at = now() WHERE id = $1 RETURNING *",
+    )
+    .bind(&resource.id)
+    .bind(&resource.name)
+    .bind(&resource.resource_type)
+    .bind(resource.capacity)
+    .bind(&resource.timezone)
+    .bind(resource.get_attributes()?)
+    .bind(resource.active)
+    .bind(buffer_before)
+    .bind(buffer_after)
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or_else(|| Error::UnknownResource(resource.id.clone()))?;
+
+    self
+      .save_rules(&mut tx, &updated.id, resource.rules.as_ref())
+      .await?;
+    updated.rules = self.load_rules(&mut tx, &updated.id).await?;
+
+    let used: i32 = sqlx::query_scalar(
+      "SELECT COALESCE(max(rsvp.capacity_used(resource_id, padded_span)), 0) FROM rsvp.reservations WHERE resource_id = $1 AND status IN ('pending', 'confirmed', 'blocked')",
+    )
+    .bind(&resource.id)
+    .fetch_one(&mut tx)
+    .await?;
+    if used > resource.capacity {
+      return Err(Error::InvalidResource(format!(
+        "capacity {} is below the {} units already reserved",
+        resource.capacity, used
+      )));
+    }
+
+    // let the capacity trigger move reservations between the exclusion
+    // constraint and the capacity check
+    sqlx::query(
+      "UPDATE rsvp.reservations SET shared = $2 WHERE resource_id = $1 AND shared <> $2",
+    )
+    .bind(&resource.id)
+    .bind(resource.capacity > 1)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
+  async fn delete_resource(&self, id: &str) -> Result<abi::Resource, Error> {
+    let ret =
+      sqlx::query_as("DELETE FROM rsvp.resources WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+
+    match ret {
+      Ok(resource) => {
+        resource.ok_or_else(|| Error::UnknownResource(id.to_string()))
+      }
+      // reservations_resource_id_fkey still references it
+      Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+        Err(Error::ResourceInUse(id.to_string()))
+      }
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  async fn list_resources(
+    &self,
+    include_inactive: bool,
+  ) -> Result<Vec<abi::Resource>, Error> {
+    let mut conn = self.pool.acquire().await?;
+
+    let mut resources: Vec<abi::Resource> = sqlx::query_as(
+      "SELECT * FROM rsvp.resources WHERE active OR $1 ORDER BY id",
+    )
+    .bind(include_inactive)
+    .fetch_all(&mut conn)
+    .await?;
+    for resource in resources.iter_mut() {
+      resource.rules = self.load_rules(&mut conn, &resource.id).await?;
+    }
+
+    Ok(resources)
+  }
+}
+
+impl ReservationManage {
+  /// make sure the resource exists and is active, the row is locked until
+  /// the transaction ends so it can't be disabled in the meantime
+  pub(crate) async fn check_resource(
+    &self,
+    conn: &mut PgConnection,
+    id: &str,
+  ) -> Result<(), Error> {
+    let active: Option<bool> = sqlx::query_scalar(
+      "SELECT active FROM rsvp.resources WHERE id = $1 FOR SHARE",
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+
+    match active {
+      Some(true) => Ok(()),
+      Some(false) => Err(Error::ResourceDisabled(id.to_string())),
+      None => Err(Error::UnknownResource(id.to_string())),
+    }
+  }
+
+  /// timezone of the resource, reservations made without one use it
+  pub(crate) async fn resource_timezone(
+    &self,
+    conn: &mut PgConnection,
+    id: &str,
+  ) -> Result<Tz, Error> {
+    let timezone: String =
+      sqlx::query_scalar("SELECT timezone FROM rsvp.resources WHERE id = $1")
+        .bind(id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| Error::UnknownResource(id.to_string()))?;
+
+    parse_timezone(&timezone)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Rsvp;
+  use abi::{convert_local_time_to_utc, convert_to_timestamp, Reservation};
+
+  fn make_resource(id: &str) -> abi::Resource {
+    abi::Resource {
+      id: id.to_string(),
+      name: format!("Room {}", id),
+      resource_type: "room".to_string(),
+      capacity: 1,
+      timezone: String::new(),
+      attributes: r#"{"floor":3}"#.to_string(),
+      active: false,
+      rules: None,
+      buffer_before: None,
+      buffer_after: None,
+    }
+  }
+
+  fn make_reservation(resource_id: &str) -> Reservation {
+    Reservation::new_pending(
+      "xiaozhangId",
+      resource_id,
+      convert_local_time_to_utc("2024-01-21 19:00:00"),
+      convert_local_time_to_utc("2024-01-22 12:00:00"),
+      "",
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn resource_crud_should_work() {
+    let pool = ReservationManage::new(migrated_pool);
+
+    let resource = pool.create_resource(make_resource("room-1")).await.unwrap();
+    assert!(resource.active);
+    assert_eq!(resource.timezone, "UTC");
+    assert_eq!(resource.attributes, r#"{"floor":3}"#);
+    assert_eq!(pool.get_resource("room-1").await.unwrap(), resource);
+
+    let mut update = resource.clone();
+    update.capacity = 4;
+    update.active = false;
+    let updated = pool.update_resource(update.clone()).await.unwrap();
+    assert_eq!(updated, update);
+
+    pool.create_resource(make_resource("room-2")).await.unwrap();
+    let ids = |resources: Vec<abi::Resource>| {
+      resources.into_iter().map(|r| r.id).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(pool.list_resources(false).await.unwrap()), ["room-2"]);
+    assert_eq!(
+      ids(pool.list_resources(true).await.unwrap()),
+      ["room-1", "room-2"]
+    );
+
+    pool.delete_resource("room-1").await.unwrap();
+    assert_eq!(
+      pool.get_resource("room-1").await.unwrap_err(),
+      Error::UnknownResource("room-1".to_string())
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_unknown_or_disabled_resource_should_reject() {
+    let pool = ReservationManage::new(migrated_pool);
+
+    let err = pool.reserve(make_reservation("room-1")).await.unwrap_err();
+    assert_eq!(err, Error::UnknownResource("room-1".to_string()));
+
+    let mut resource =
+      pool.create_resource(make_resource("room-1")).await.unwrap();
+    resource.active = false;
+    pool.update_resource(resource).await.unwrap();
+
+    let err = pool.reserve(make_reservation("room-1")).await.unwrap_err();
+    assert_eq!(err, Error::ResourceDisabled("room-1".to_string()));
+  }
+
+  async fn make_hall(pool: &ReservationManage, capacity: i32) {
+    let mut resource = make_resource("hall-1");
+    resource.capacity = capacity;
+    pool.create_resource(resource).await.unwrap();
+  }
+
+  fn make_seats(start: &str, end: &str, quantity: i32) -> Reservation {
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "hall-1",
+      convert_local_time_to_utc(start),
+      convert_local_time_to_utc(end),
+      "",
+    );
+    rsvp.quantity = quantity;
+    rsvp
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_shared_resource_up_to_capacity_should_work() {
+    let pool = ReservationManage::new(migrated_pool);
+    make_hall(&pool, 3).await;
+
+    pool
+      .reserve(make_seats("2024-01-21 09:00:00", "2024-01-21 12:00:00", 2))
+      .await
+      .unwrap();
+    // doesn't overlap the first one, all 3 units are free
+    pool
+      .reserve(make_seats("2024-01-21 12:00:00", "2024-01-21 14:00:00", 3))
+      .await
+      .unwrap();
+    let rsvp = pool
+      .reserve(make_seats("2024-01-21 10:00:00", "2024-01-21 11:00:00", 0))
+      .await
+      .unwrap();
+    assert_eq!(rsvp.quantity, 1);
+
+    let err = pool
+      .reserve(make_seats("2024-01-21 08:00:00", "2024-01-21 13:00:00", 1))
+      .await
+      .unwrap_err();
+    let Error::CapacityExceeded(conflict) = err else {
+      panic!("expect capacity exceeded, got {:?}", err);
+    };
+    assert_eq!(conflict.resource_id, "hall-1");
+    assert_eq!(
+      conflict.start,
+      convert_local_time_to_utc("2024-01-21 08:00:00")
+    );
+    assert_eq!(
+      conflict.end,
+      convert_local_time_to_utc("2024-01-21 13:00:00")
+    );
+    assert_eq!(
+      (conflict.capacity, conflict.available, conflict.requested),
+      (3, 0, 1)
+    );
+
+    // cancelled reservations give their units back
+    pool.cancel(rsvp.id, "xiaozhangId", "", 0).await.unwrap();
+    pool
+      .reserve(make_seats("2024-01-21 08:00:00", "2024-01-21 10:30:00", 1))
+      .await
+      .unwrap();
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_more_than_one_unit_of_exclusive_resource_should_reject() {
+    let pool = ReservationManage::new(migrated_pool);
+    make_hall(&pool, 1).await;
+
+    let err = pool
+      .reserve(make_seats("2024-01-21 09:00:00", "2024-01-21 12:00:00", 2))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, Error::CapacityExceeded(c) if c.available == 1));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn shared_resource_is_available_until_full() {
+    let pool = ReservationManage::new(migrated_pool);
+    make_hall(&pool, 2).await;
+    pool
+      .reserve(make_seats("2024-01-21 09:00:00", "2024-01-21 12:00:00", 1))
+      .await
+      .unwrap();
+    pool
+      .reserve(make_seats("2024-01-21 10:00:00", "2024-01-21 11:00:00", 1))
+      .await
+      .unwrap();
+
+    let t = |s| Some(convert_to_timestamp(convert_local_time_to_utc(s)));
+    let query = abi::AvailabilityQuery {
+      resource_id: "hall-1".to_string(),
+      start: t("2024-01-21 08:00:00"),
+      end: t("2024-01-21 13:00:00"),
+      ..Default::default()
+    };
+    let windows = pool.find_availability(query).await.unwrap();
+    let windows: Vec<_> =
+      windows.into_iter().map(|w| (w.start, w.end)).collect();
+    assert_eq!(
+      windows,
+      vec![
+        (t("2024-01-21 08:00:00"), t("2024-01-21 10:00:00")),
+        (t("2024-01-21 11:00:00"), t("2024-01-21 13:00:00")),
+      ]
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_capacity_should_respect_reserved_units() {
+    let pool = ReservationManage::new(migrated_pool);
+    make_hall(&pool, 3).await;
+    pool
+      .reserve(make_seats("2024-01-21 09:00:00", "2024-01-21 12:00:00", 1))
+      .await
+      .unwrap();
+    pool
+      .reserve(make_seats("2024-01-21 10:00:00", "2024-01-21 11:00:00", 1))
+      .await
+      .unwrap();
+
+    let mut hall = pool.get_resource("hall-1").await.unwrap();
+    hall.capacity = 1;
+    let err = pool.update_resource(hall.clone()).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidResource(_)));
+
+    hall.capacity = 2;
+    pool.update_resource(hall).await.unwrap();
+    let err = pool
+      .reserve(make_seats("2024-01-21 10:30:00", "2024-01-21 11:30:00", 1))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, Error::CapacityExceeded(_)));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_capacity_should_keep_reservation_versions() {
+    let pool = ReservationManage::new(migrated_pool);
+    make_hall(&pool, 2).await;
+    let rsvp = pool
+      .reserve(make_seats("2024-01-21 09:00:00", "2024-01-21 12:00:00", 1))
+      .await
+      .unwrap();
+
+    // no longer shared, the reservation moves to the exclusion constraint
+    let mut hall = pool.get_resource("hall-1").await.unwrap();
+    hall.capacity = 1;
+    pool.update_resource(hall).await.unwrap();
+    assert_eq!(pool.get(rsvp.id).await.unwrap().version, rsvp.version);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn delete_resource_in_use_should_reject() {
+    let pool = ReservationManage::new(migrated_pool);
+    pool.create_resource(make_resource("room-1")).await.unwrap();
+    pool.reserve(make_reservation("room-1")).await.unwrap();
+
+    let err = pool.delete_resource("room-1").await.unwrap_err();
+    assert_eq!(err, Error::ResourceInUse("room-1".to_string()));
+  }
+}
@@ -0,0 +1,371 @@
+use crate::ReservationManage;
+use abi::{
+  convert_to_duration, convert_to_timestamp, Blackout, BookingRules, Error,
+  OpeningHours,
+};
+use chrono::{DateTime, NaiveTime, Utc};
+use sqlx::{
+  postgres::types::{PgInterval, PgRange},
+  PgConnection, Row,
+};
+
+const HOURS_FORMAT: &str = "%H:%M";
+
+impl ReservationManage {
+  /// booking rules of a resource, None if it has none
+  pub(crate) async fn load_rules(
+    &self,
+    conn: &mut PgConnection,
+    id: &str,
+  ) -> Result<Option<BookingRules>, Error> {
+    let row = sqlx::query(
+      "SELECT min_duration, max_duration, min_lead_time, max_horizon FROM rsvp.resources WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(row) = row else {
+      return Ok(None);
+    };
+    let duration = |column: &str| {
+      row
+        .get::<Option<PgInterval>, _>(column)
+        .map(convert_interval)
+    };
+
+    let opening_hours = sqlx::query(
+      "SELECT weekday, opens, closes FROM rsvp.opening_hours WHERE resource_id = $1 ORDER BY weekday, opens",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| OpeningHours {
+      weekday: row.get::<i16, _>("weekday") as i32,
+      opens: row.get::<NaiveTime, _>("opens").format(HOURS_FORMAT).to_string(),
+      closes: row
+        .get::<NaiveTime, _>("closes")
+        .format(HOURS_FORMAT)
+        .to_string(),
+    })
+    .collect();
+
+    let blackouts = sqlx::query(
+      "SELECT lower(during) AS start, upper(during) AS end, reason FROM rsvp.blackouts WHERE resource_id = $1 ORDER BY lower(during)",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| Blackout {
+      start: Some(convert_to_timestamp(row.get("start"))),
+      end: Some(convert_to_timestamp(row.get("end"))),
+      reason: row.get("reason"),
+    })
+    .collect();
+
+    let rules = BookingRules {
+      opening_hours,
+      blackouts,
+      min_duration: duration("min_duration"),
+      max_duration: duration("max_duration"),
+      min_lead_time: duration("min_lead_time"),
+      max_horizon: duration("max_horizon"),
+    };
+    Ok((rules != BookingRules::default()).then_some(rules))
+  }
+
+  /// replace the booking rules of a resource, None removes them
+  pub(crate) async fn save_rules(
+    &self,
+    conn: &mut PgConnection,
+    id: &str,
+    rules: Option<&BookingRules>,
+  ) -> Result<(), Error> {
+    let default = BookingRules::default();
+    let rules = rules.unwrap_or(&default);
+
+    sqlx::query(
+      "UPDATE rsvp.resources SET min_duration = $2, max_duration = $3, min_lead_time = $4, max_horizon = $5 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(rules.min_duration.map(convert_to_duration))
+    .bind(rules.max_duration.map(convert_to_duration))
+    .bind(rules.min_lead_time.map(convert_to_duration))
+    .bind(rules.max_horizon.map(convert_to_duration))
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM rsvp.opening_hours WHERE resource_id = $1")
+      .bind(id)
+      .execute(&mut *conn)
+      .await?;
+    for hours in &rules.opening_hours {
+      let parse = |time: &str| {
+        NaiveTime::parse_from_str(time, HOURS_FORMAT).map_err(|_| {
+          Error::InvalidResource(format!("invalid opening time {}", time))
+        })
+      };
+      sqlx::query(
+        "INSERT INTO rsvp.opening_hours (resource_id, weekday, opens, closes) VALUES ($1, $2, $3, $4)",
+      )
+      .bind(id)
+      .bind(hours.weekday as i16)
+      .bind(parse(&hours.opens)?)
+      .bind(parse(&hours.closes)?)
+      .execute(&mut *conn)
+      .await?;
+    }
+
+    sqlx::query("DELETE FROM rsvp.blackouts WHERE resource_id = $1")
+      .bind(id)
+      .execute(&mut *conn)
+      .await?;
+    for blackout in &rules.blackouts {
+      let during: PgRange<DateTime<Utc>> = blackout.get_timespan().into();
+      sqlx::query(
+        "INSERT INTO rsvp.blackouts (resource_id, during, reason) VALUES ($1, $2, $3)",
+      )
+      .bind(id)
+      .bind(during)
+      .bind(&blackout.reason)
+      .execute(&mut *conn)
+      .await?;
+    }
+
+    Ok(())
+  }
+
+  /// make sure the reservation keeps to the booking rules of its resource,
+  /// every broken rule is reported at once
+  pub(crate) async fn check_rules(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+  ) -> Result<(), Error> {
+    let Some(rules) = self.load_rules(&mut *conn, &rsvp.resource_id).await?
+    else {
+      return Ok(());
+    };
+    let tz = self.resource_timezone(conn, &rsvp.resource_id).await?;
+
+    let violations = rules.violations(&rsvp.get_timespan(), Utc::now(), tz);
+    if !violations.is_empty() {
+      return Err(Error::RuleViolation(violations));
+    }
+    Ok(())
+  }
+}
+
+/// intervals are only written from durations, so they have no months
+fn convert_interval(interval: PgInterval) -> prost_types::Duration {
+  let micros = interval.days as i64 * 86_400_000_000 + interval.microseconds;
+
+  prost_types::Duration {
+    seconds: micros.div_euclid(1_000_000),
+    nanos: (micros.rem_euclid(1_000_000) * 1000) as i32,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{Reservation, RuleViolation};
+  use chrono::{Duration, DurationRound, Weekday};
+  use prost_types::FieldMask;
+
+  fn hours(hours: i64) -> Option<prost_types::Duration> {
+    Some(prost_types::Duration {
+      seconds: hours * 3600,
+      nanos: 0,
+    })
+  }
+
+  fn make_rules() -> BookingRules {
+    BookingRules {
+      opening_hours: vec![
+        OpeningHours::new(Weekday::Mon, "09:00", "12:00"),
+        OpeningHours::new(Weekday::Mon, "13:00", "18:00"),
+      ],
+      blackouts: vec![Blackout {
+        start: Some("2024-01-15T00:00:00Z".parse().unwrap()),
+        end: Some("2024-01-16T00:00:00Z".parse().unwrap()),
+        reason: "holiday".to_string(),
+      }],
+      max_duration: hours(2),
+      ..Default::default()
+    }
+  }
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    manager
+      .create_resource(abi::Resource {
+        id: "room-1".to_string(),
+        name: "room 1".to_string(),
+        capacity: 1,
+        timezone: "Asia/Tokyo".to_string(),
+        rules: Some(make_rules()),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    manager
+  }
+
+  fn make_reservation(start: &str, end: &str) -> Reservation {
+    Reservation::new_pending_in(
+      "xiaozhangId",
+      "room-1",
+      start.parse().unwrap(),
+      end.parse().unwrap(),
+      "Asia/Tokyo",
+      "",
+    )
+    .unwrap()
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn rules_should_be_stored_with_resource() {
+    let pool = make_manager(migrated_pool).await;
+
+    let resource = pool.get_resource("room-1").await.unwrap();
+    assert_eq!(resource.rules, Some(make_rules()));
+
+    let mut update = resource.clone();
+    update.rules = None;
+    pool.update_resource(update).await.unwrap();
+    assert_eq!(pool.get_resource("room-1").await.unwrap().rules, None);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_should_keep_to_booking_rules() {
+    let pool = make_manager(migrated_pool).await;
+
+    // 2024-01-08 is a monday
+    let rsvp = make_reservation("2024-01-08T10:00:00", "2024-01-08T12:00:00");
+    pool.reserve(rsvp).await.unwrap();
+
+    // over the lunch break and too long
+    let rsvp = make_reservation("2024-01-08T11:00:00", "2024-01-08T14:00:00");
+    let Error::RuleViolation(violations) =
+      pool.reserve(rsvp).await.unwrap_err()
+    else {
+      panic!("expect rule violation");
+    };
+    assert_eq!(
+      violations,
+      vec![
+        RuleViolation::OutsideOpeningHours,
+        RuleViolation::TooLong {
+          max_duration: Duration::hours(2)
+        },
+      ]
+    );
+
+    let rsvp = make_reservation("2024-01-15T10:00:00", "2024-01-15T11:00:00");
+    assert!(matches!(
+      pool.reserve(rsvp).await.unwrap_err(),
+      Error::RuleViolation(violations)
+        if matches!(violations[..], [RuleViolation::Blackout { .. }])
+    ));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_batch_should_report_rule_violations_per_item() {
+    let pool = make_manager(migrated_pool).await;
+
+    let err = pool
+      .reserve_batch(vec![
+        make_reservation("2024-01-08T09:00:00", "2024-01-08T10:00:00"),
+        make_reservation("2024-01-15T10:00:00", "2024-01-15T11:00:00"),
+        make_reservation("2024-01-08T10:00:00", "2024-01-08T11:00:00"),
+        make_reservation("2024-01-08T19:00:00", "2024-01-08T20:00:00"),
+      ])
+      .await
+      .unwrap_err();
+
+    let Error::BatchConflict(conflicts) = err else {
+      panic!("expect batch conflict, got {:?}", err);
+    };
+    let indexes: Vec<_> = conflicts.iter().map(|c| c.index).collect();
+    assert_eq!(indexes, vec![1, 3]);
+    assert!(matches!(
+      &conflicts[0].error,
+      Error::RuleViolation(violations)
+        if matches!(violations[..], [RuleViolation::Blackout { .. }])
+    ));
+    assert!(matches!(
+      &conflicts[1].error,
+      Error::RuleViolation(violations)
+        if violations[..] == [RuleViolation::OutsideOpeningHours]
+    ));
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn note_update_should_skip_booking_rules() {
+    let pool = make_manager(migrated_pool).await;
+    let resource = abi::Resource {
+      id: "room-2".to_string(),
+      name: "room 2".to_string(),
+      capacity: 1,
+      ..Default::default()
+    };
+    let mut resource = pool.create_resource(resource).await.unwrap();
+
+    // booked before the lead time was required, starts within it now
+    let start = Utc::now().duration_trunc(Duration::minutes(1)).unwrap()
+      + Duration::hours(1);
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "room-2",
+      start,
+      start + Duration::hours(1),
+      "",
+    );
+    let rsvp = pool.reserve(rsvp).await.unwrap();
+    resource.rules = Some(BookingRules {
+      min_lead_time: hours(24),
+      ..Default::default()
+    });
+    pool.update_resource(resource).await.unwrap();
+
+    let update = Reservation {
+      note: "bring the slides".to_string(),
+      ..rsvp.clone()
+    };
+    let mask = FieldMask {
+      paths: vec!["note".to_string()],
+    };
+    let updated = pool.update(rsvp.id, update, mask, 0).await.unwrap();
+    assert_eq!(updated.note, "bring the slides");
+
+    // moving it is booking it again
+    let update = Reservation {
+      start: Some(convert_to_timestamp(start + Duration::minutes(30))),
+      ..rsvp.clone()
+    };
+    let mask = FieldMask {
+      paths: vec!["start".to_string()],
+    };
+    let err = pool.update(rsvp.id, update, mask, 0).await.unwrap_err();
+    assert!(matches!(
+      err,
+      Error::RuleViolation(violations)
+        if matches!(violations[..], [RuleViolation::TooSoon { .. }])
+    ));
+  }
+}
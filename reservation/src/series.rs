@@ -0,0 +1,397 @@
+use crate::{RecurringRsvp, ReservationManage};
+use abi::{
+  convert_to_timestamp, convert_to_utc_time, BatchConflict, Error,
+  RecurrenceRule, Validator,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use prost_types::FieldMask;
+use sqlx::Acquire;
+
+#[async_trait]
+impl RecurringRsvp for ReservationManage {
+  async fn reserve_series(
+    &self,
+    series: abi::ReservationSeries,
+  ) -> Result<(i64, Vec<abi::Reservation>), Error> {
+    let mut template = series.reservation.ok_or_else(|| {
+      Error::InvalidRecurrence("reservation is required".to_string())
+    })?;
+    template.validate()?;
+
+    let rule: RecurrenceRule = series.rrule.parse()?;
+    let exdates: Vec<DateTime<Utc>> = series
+      .exdates
+      .into_iter()
+      .map(convert_to_utc_time)
+      .collect();
+    let span = template.get_timespan();
+    let duration = span.end - span.start;
+
+    let mut tx = self.pool.begin().await?;
+
+    // expand in local time so occurrences keep their wall clock time
+    // across daylight saving changes
+    let tz = match template.get_timezone()? {
+      Some(tz) => tz,
+      None => {
+        self
+          .resource_timezone(&mut tx, &template.resource_id)
+          .await?
+      }
+    };
+    template.timezone = tz.name().to_string();
+    let starts = rule.occurrences(&span.start.with_timezone(&tz), &exdates)?;
+
+    let series_id: i64 = sqlx::query_scalar(
+      "INSERT INTO rsvp.reservation_series (user_id, resource_id, rrule, exdates) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(&template.user_id)
+    .bind(&template.resource_id)
+    .bind(&series.rrule)
+    .bind(&exdates)
+    .fetch_one(&mut tx)
+    .await?;
+
+    let rsvps = starts
+      .into_iter()
+      .map(|start| abi::Reservation {
+        start: Some(convert_to_timestamp(start)),
+        end: Some(convert_to_timestamp(start + duration)),
+        series_id,
+        ..template.clone()
+      })
+      .collect();
+    let reserved = self.insert_all(&mut tx, rsvps).await?;
+
+    tx.commit().await?;
+
+    Ok((series_id, reserved))
+  }
+
+  async fn update_series(
+    &self,
+    series_id: i64,
+    from: DateTime<Utc>,
+    update: abi::Reservation,
+    mask: FieldMask,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let mut tx = self.pool.begin().await?;
+
+    let rsvps: Vec<abi::Reservation> = sqlx::query_as(
+      "SELECT * FROM rsvp.reservations WHERE series_id = $1 AND lower(timespan) >= $2 AND status IN ('pending', 'confirmed', 'blocked') ORDER BY lower(timespan) FOR UPDATE",
+    )
+    .bind(series_id)
+    .bind(from)
+    .fetch_all(&mut tx)
+    .await?;
+    let Some(first) = rsvps.first() else {
+      return Err(Error::NotFound);
+    };
+
+    // start and end of the first occurrence move every following one by the
+    // same offset, so a "9:00 -> 10:00" edit keeps each occurrence's date
+    let first = first.get_timespan();
+    let start_offset = update
+      .start
+      .map(|start| convert_to_utc_time(start) - first.start);
+    let end_offset = update.end.map(|end| convert_to_utc_time(end) - first.end);
+
+    let mut updated = Vec::with_capacity(rsvps.len());
+    let mut conflicts = Vec::new();
+
+    for (index, mut rsvp) in rsvps.into_iter().enumerate() {
+      let span = rsvp.get_timespan();
+      let occurrence = abi::Reservation {
+        start: start_offset.map(|d| convert_to_timestamp(span.start + d)),
+        end: end_offset.map(|d| convert_to_timestamp(span.end + d)),
+        ..update.clone()
+      };
+      rsvp.apply_update(occurrence, &mask)?;
+
+      let mut savepoint = tx.begin().await?;
+      match self.save_update(&mut savepoint, &rsvp, &mask).await {
+        Ok(rsvp) => {
+          savepoint.commit().await?;
+          updated.push(rsvp);
+        }
+        Err(
+          error @ (Error::ConflictReservation(_)
+          | Error::CapacityExceeded(_)
+          | Error::RuleViolation(_)),
+        ) => {
+          savepoint.rollback().await?;
+          conflicts.push(BatchConflict { index, error });
+        }
+        Err(e) => return Err(e),
+      }
+    }
+
+    if !conflicts.is_empty() {
+      return Err(Error::BatchConflict(conflicts));
+    }
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
+  async fn cancel_series(
+    &self,
+    series_id: i64,
+    from: DateTime<Utc>,
+    operator: &str,
+    reason: &str,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let mut tx = self.pool.begin().await?;
+
+    sqlx::query("SELECT set_config('rsvp.operator', $1, true)")
+      .bind(operator)
+      .execute(&mut tx)
+      .await?;
+
+    let mut rsvps: Vec<abi::Reservation> = sqlx::query_as(
+      "UPDATE rsvp.reservations SET status = 'cancelled', cancelled_at = now(), cancelled_by = NULLIF($3, ''), reason = NULLIF($4, '') WHERE series_id = $1 AND lower(timespan) >= $2 AND status IN ('pending', 'confirmed', 'blocked') RETURNING *",
+    )
+    .bind(series_id)
+    .bind(from)
+    .bind(operator)
+    .bind(reason)
+    .fetch_all(&mut tx)
+    .await?;
+    self.promote_waitlist(&mut tx, &rsvps).await?;
+
+    tx.commit().await?;
+
+    rsvps.sort_by_key(|rsvp| rsvp.start.map(|t| (t.seconds, t.nanos)));
+    Ok(rsvps)
+  }
+
+  async fn get_series(
+    &self,
+    series_id: i64,
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    let rsvps = sqlx::query_as(
+      "SELECT * FROM rsvp.reservations WHERE series_id = $1 ORDER BY lower(timespan)",
+    )
+    .bind(series_id)
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(rsvps)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{convert_local_time_to_utc, Reservation, ReservationStatus};
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    for id in ["room-1", "room-2"] {
+      let resource = abi::Resource {
+        id: id.to_string(),
+        name: id.to_string(),
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+    }
+    manager
+  }
+
+  fn make_series(rrule: &str) -> abi::ReservationSeries {
+    abi::ReservationSeries {
+      // 2024-01-01 is a monday
+      reservation: Some(Reservation::new_pending(
+        "xiaozhangId",
+        "room-1",
+        convert_local_time_to_utc("2024-01-01 09:00:00"),
+        convert_local_time_to_utc("2024-01-01 10:00:00"),
+        "standup",
+      )),
+      rrule: rrule.to_string(),
+      exdates: vec![],
+    }
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_series_should_keep_wall_clock_across_dst() {
+    let pool = make_manager(migrated_pool).await;
+    let mut series = make_series("FREQ=WEEKLY;COUNT=2");
+    // daylight saving starts in New York on 2024-03-10
+    series.reservation = Some(
+      Reservation::new_pending_in(
+        "xiaozhangId",
+        "room-1",
+        "2024-03-04T09:00:00".parse().unwrap(),
+        "2024-03-04T10:00:00".parse().unwrap(),
+        "America/New_York",
+        "standup",
+      )
+      .unwrap(),
+    );
+
+    let (_, rsvps) = pool.reserve_series(series).await.unwrap();
+    assert_eq!(
+      starts(&rsvps),
+      vec![
+        "2024-03-04T14:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        "2024-03-11T13:00:00Z".parse().unwrap(),
+      ]
+    );
+    assert_eq!(rsvps[1].local_start, "2024-03-11T09:00:00-04:00");
+  }
+
+  fn starts(rsvps: &[Reservation]) -> Vec<DateTime<Utc>> {
+    rsvps
+      .iter()
+      .map(|rsvp| convert_to_utc_time(rsvp.start.unwrap()))
+      .collect()
+  }
+
+  fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+      paths: paths.iter().map(|p| p.to_string()).collect(),
+    }
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_series_should_expand_occurrences() {
+    let pool = make_manager(migrated_pool).await;
+    let mut series = make_series("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4");
+    series.exdates = vec![convert_to_timestamp(convert_local_time_to_utc(
+      "2024-01-03 09:00:00",
+    ))];
+
+    let (series_id, rsvps) = pool.reserve_series(series).await.unwrap();
+    let t = convert_local_time_to_utc;
+    assert_eq!(
+      starts(&rsvps),
+      vec![
+        t("2024-01-01 09:00:00"),
+        t("2024-01-08 09:00:00"),
+        t("2024-01-10 09:00:00"),
+      ]
+    );
+    assert!(rsvps.iter().all(|rsvp| rsvp.series_id == series_id));
+    assert_eq!(pool.get_series(series_id).await.unwrap(), rsvps);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn reserve_series_conflict_should_report_occurrences() {
+    let pool = make_manager(migrated_pool).await;
+    pool
+      .reserve(Reservation::new_pending(
+        "xiaonanId",
+        "room-1",
+        convert_local_time_to_utc("2024-01-03 09:30:00"),
+        convert_local_time_to_utc("2024-01-03 11:00:00"),
+        "",
+      ))
+      .await
+      .unwrap();
+
+    let err = pool
+      .reserve_series(make_series("FREQ=DAILY;COUNT=3"))
+      .await
+      .unwrap_err();
+    let Error::BatchConflict(conflicts) = err else {
+      panic!("expect batch conflict, got {:?}", err);
+    };
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].index, 2);
+
+    let count: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM rsvp.reservation_series")
+        .fetch_one(&pool.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn update_series_should_change_this_and_following() {
+    let pool = make_manager(migrated_pool).await;
+    let (series_id, _) = pool
+      .reserve_series(make_series("FREQ=DAILY;COUNT=4"))
+      .await
+      .unwrap();
+
+    // move the 3rd and 4th occurrence an hour later, to another room
+    let t = convert_local_time_to_utc;
+    let update = Reservation::new_pending(
+      "",
+      "room-2",
+      t("2024-01-03 10:00:00"),
+      t("2024-01-03 11:00:00"),
+      "",
+    );
+    let updated = pool
+      .update_series(
+        series_id,
+        t("2024-01-03 00:00:00"),
+        update,
+        mask(&["start", "end", "resource_id"]),
+      )
+      .await
+      .unwrap();
+    assert_eq!(
+      starts(&updated),
+      vec![t("2024-01-03 10:00:00"), t("2024-01-04 10:00:00")]
+    );
+    assert!(updated.iter().all(|rsvp| rsvp.resource_id == "room-2"));
+
+    let rsvps = pool.get_series(series_id).await.unwrap();
+    assert_eq!(rsvps[1].resource_id, "room-1");
+    assert_eq!(
+      convert_to_utc_time(rsvps[1].start.unwrap()),
+      t("2024-01-02 09:00:00")
+    );
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn cancel_series_should_cancel_this_and_following() {
+    let pool = make_manager(migrated_pool).await;
+    let (series_id, _) = pool
+      .reserve_series(make_series("FREQ=DAILY;COUNT=4"))
+      .await
+      .unwrap();
+
+    let t = convert_local_time_to_utc;
+    let cancelled = pool
+      .cancel_series(
+        series_id,
+        t("2024-01-02 09:00:00"),
+        "xiaozhangId",
+        "moved",
+      )
+      .await
+      .unwrap();
+    assert_eq!(cancelled.len(), 3);
+    assert!(cancelled
+      .iter()
+      .all(|rsvp| rsvp.status == ReservationStatus::Cancelled as i32
+        && rsvp.reason == "moved"));
+
+    let rsvps = pool.get_series(series_id).await.unwrap();
+    assert_eq!(rsvps[0].status, ReservationStatus::Pending as i32);
+  }
+}
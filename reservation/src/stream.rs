@@ -0,0 +1,30 @@
+use std::{
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+use abi::Error;
+use futures::Stream;
+use tokio::sync::mpsc;
+
+/// items produced by a background task, received one by one
+pub struct RsvpStream<T = abi::Reservation> {
+  inner: mpsc::Receiver<Result<T, Error>>,
+}
+
+impl<T> RsvpStream<T> {
+  pub fn new(inner: mpsc::Receiver<Result<T, Error>>) -> Self {
+    Self { inner }
+  }
+}
+
+impl<T> Stream for RsvpStream<T> {
+  type Item = Result<T, Error>;
+
+  fn poll_next(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Self::Item>> {
+    self.inner.poll_recv(cx)
+  }
+}
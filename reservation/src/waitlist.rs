@@ -0,0 +1,292 @@
+use crate::{ReservationManage, Waitlist};
+use abi::{Error, ReservationStatus, Validator, WaitlistEntry};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::types::PgRange, Connection, PgConnection};
+
+const WAITLIST_OPERATOR: &str = "system:waitlist";
+
+#[async_trait]
+impl Waitlist for ReservationManage {
+  async fn join_waitlist(
+    &self,
+    rsvp: abi::Reservation,
+  ) -> Result<WaitlistEntry, Error> {
+    let mut tx = self.pool.begin().await?;
+    let entry = self.join_waitlist_in(&mut tx, rsvp).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+  }
+
+  async fn leave_waitlist(&self, id: i64) -> Result<WaitlistEntry, Error> {
+    let entry = sqlx::query_as(
+      "UPDATE rsvp.waitlist SET status = 'withdrawn' WHERE id = $1 AND status = 'waiting' RETURNING *",
+    )
+    .bind(id)
+    .fetch_one(&self.pool)
+    .await?;
+
+    Ok(entry)
+  }
+}
+
+impl ReservationManage {
+  /// [`Waitlist::join_waitlist`] inside `conn`, which is left for the caller
+  /// to commit
+  pub async fn join_waitlist_in(
+    &self,
+    conn: &mut PgConnection,
+    rsvp: abi::Reservation,
+  ) -> Result<WaitlistEntry, Error> {
+    rsvp.validate()?;
+
+    // a request that can never be reserved shouldn't wait for it
+    self.check_resource(conn, &rsvp.resource_id).await?;
+    self.check_rules(conn, &rsvp).await?;
+
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
+    let id: i64 = sqlx::query_scalar(
+      "INSERT INTO rsvp.waitlist (user_id, resource_id, timespan, note, quantity, timezone) VALUES ($1, $2, $3, $4, $5, COALESCE(NULLIF($6, ''), (SELECT timezone FROM rsvp.resources WHERE id = $2))) RETURNING id",
+    )
+    .bind(&rsvp.user_id)
+    .bind(&rsvp.resource_id)
+    .bind(timespan)
+    .bind(&rsvp.note)
+    .bind(rsvp.quantity.max(1))
+    .bind(&rsvp.timezone)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // the window may have freed up since the reservation failed
+    self.promote_waitlist(conn, &[rsvp]).await?;
+
+    let entry = sqlx::query_as("SELECT * FROM rsvp.waitlist WHERE id = $1")
+      .bind(id)
+      .fetch_one(conn)
+      .await?;
+
+    Ok(entry)
+  }
+
+  /// reserve waiting entries overlapping the windows `freed` gave up as
+  /// pending, in the order they joined. entries that still can't be reserved
+  /// keep waiting, the promoted reservations show up in the change stream
+  pub(crate) async fn promote_waitlist(
+    &self,
+    conn: &mut PgConnection,
+    freed: &[abi::Reservation],
+  ) -> Result<Vec<abi::Reservation>, Error> {
+    if freed.is_empty() {
+      return Ok(Vec::new());
+    }
+    sqlx::query("SELECT set_config('rsvp.operator', $1, true)")
+      .bind(WAITLIST_OPERATOR)
+      .execute(&mut *conn)
+      .await?;
+
+    let mut promoted = Vec::new();
+    for rsvp in freed {
+      // buffers of the freed reservation and the waiting one both count
+      let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan().into();
+      let entries: Vec<WaitlistEntry> = sqlx::query_as(
+        r#"SELECT w.* FROM rsvp.waitlist w
+        LEFT JOIN rsvp.resources r ON r.id = w.resource_id
+        WHERE w.resource_id = $1
+            AND w.status = 'waiting'
+            AND upper(w.timespan) > now()
+            AND w.timespan && rsvp.pad_timespan(
+                $2,
+                COALESCE(r.buffer_before + r.buffer_after, 0),
+                COALESCE(r.buffer_before + r.buffer_after, 0)
+            )
+        ORDER BY w.created_at, w.id
+        FOR UPDATE OF w"#,
+      )
+      .bind(&rsvp.resource_id)
+      .bind(timespan)
+      .fetch_all(&mut *conn)
+      .await?;
+
+      for entry in entries {
+        let mut request = entry.reservation.unwrap_or_default();
+        request.status = ReservationStatus::Pending as i32;
+
+        // a failed insert aborts the transaction, try each in a savepoint
+        let mut savepoint = conn.begin().await?;
+        match self.insert_reservation(&mut savepoint, request).await {
+          Ok(reserved) => {
+            sqlx::query(
+              "UPDATE rsvp.waitlist SET status = 'promoted', reservation_id = $1 WHERE id = $2",
+            )
+            .bind(reserved.id)
+            .bind(entry.id)
+            .execute(&mut savepoint)
+            .await?;
+            savepoint.commit().await?;
+            promoted.push(reserved);
+          }
+          Err(e @ Error::DbError(_)) => return Err(e),
+          Err(_) => savepoint.rollback().await?,
+        }
+      }
+    }
+
+    Ok(promoted)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ResourceCatalogue, Rsvp};
+  use abi::{Reservation, ReservationUpdateType, WaitlistStatus};
+  use futures::StreamExt;
+
+  async fn make_manager(pool: sqlx::PgPool) -> ReservationManage {
+    let manager = ReservationManage::new(pool);
+    let resource = abi::Resource {
+      id: "room-1".to_string(),
+      name: "room 1".to_string(),
+      capacity: 1,
+      ..Default::default()
+    };
+    manager.create_resource(resource).await.unwrap();
+    manager
+  }
+
+  // promotion skips windows that are over, so these are in the future
+  fn make_reservation(user_id: &str, start: &str, end: &str) -> Reservation {
+    Reservation::new_pending(
+      user_id,
+      "room-1",
+      start.parse().unwrap(),
+      end.parse().unwrap(),
+      "",
+    )
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn cancel_should_promote_first_waiting_entry() {
+    let pool = make_manager(migrated_pool).await;
+    let (start, end) = ("2030-01-07T09:00:00Z", "2030-01-07T10:00:00Z");
+
+    let taken = pool
+      .reserve(make_reservation("aliceId", start, end))
+      .await
+      .unwrap();
+    let first = pool
+      .join_waitlist(make_reservation("bobId", start, end))
+      .await
+      .unwrap();
+    let second = pool
+      .join_waitlist(make_reservation("carolId", start, end))
+      .await
+      .unwrap();
+    assert!(first.is_waiting() && second.is_waiting());
+
+    let mut stream = pool.listen(0).await.unwrap();
+    pool.cancel(taken.id, "aliceId", "", 0).await.unwrap();
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Update as i32);
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Create as i32);
+    assert_eq!(change.operator, WAITLIST_OPERATOR);
+    let promoted = change.new.unwrap();
+    assert_eq!(promoted.user_id, "bobId");
+    assert_eq!(promoted.status, ReservationStatus::Pending as i32);
+    assert_eq!(promoted.start, taken.start);
+
+    // the second one keeps waiting for the promoted reservation
+    let entry = pool.leave_waitlist(second.id).await.unwrap();
+    assert_eq!(entry.status, WaitlistStatus::Withdrawn as i32);
+    assert_eq!(
+      pool.leave_waitlist(first.id).await.unwrap_err(),
+      Error::NotFound
+    );
+    let first: WaitlistEntry =
+      sqlx::query_as("SELECT * FROM rsvp.waitlist WHERE id = $1")
+        .bind(first.id)
+        .fetch_one(&pool.pool)
+        .await
+        .unwrap();
+    assert_eq!(first.status, WaitlistStatus::Promoted as i32);
+    assert_eq!(first.reservation_id, promoted.id);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn expire_should_promote_overlapping_entries() {
+    let pool = make_manager(migrated_pool).await;
+
+    let mut held = make_reservation(
+      "aliceId",
+      "2030-01-07T09:00:00Z",
+      "2030-01-07T12:00:00Z",
+    );
+    held.hold_until = Some("2030-01-01T00:00:00Z".parse().unwrap());
+    pool.reserve(held).await.unwrap();
+
+    let morning = pool
+      .join_waitlist(make_reservation(
+        "bobId",
+        "2030-01-07T09:00:00Z",
+        "2030-01-07T10:00:00Z",
+      ))
+      .await
+      .unwrap();
+    let withdrawn = pool
+      .join_waitlist(make_reservation(
+        "carolId",
+        "2030-01-07T10:00:00Z",
+        "2030-01-07T11:00:00Z",
+      ))
+      .await
+      .unwrap();
+    pool.leave_waitlist(withdrawn.id).await.unwrap();
+
+    let expired = pool
+      .expire("2030-01-02T00:00:00Z".parse().unwrap(), 10)
+      .await
+      .unwrap();
+    assert_eq!(expired.len(), 1);
+
+    let rsvps: Vec<Reservation> = sqlx::query_as(
+      "SELECT * FROM rsvp.reservations WHERE status = 'pending' ORDER BY id",
+    )
+    .fetch_all(&pool.pool)
+    .await
+    .unwrap();
+    assert_eq!(rsvps.len(), 1);
+    assert_eq!(rsvps[0].user_id, "bobId");
+    assert_eq!(rsvps[0].start, morning.reservation.unwrap().start);
+  }
+
+  #[sqlx_database_tester::test(pool(
+    variable = "migrated_pool",
+    migrations = "../migrations"
+  ))]
+  async fn join_waitlist_should_promote_free_window_at_once() {
+    let pool = make_manager(migrated_pool).await;
+
+    let entry = pool
+      .join_waitlist(make_reservation(
+        "bobId",
+        "2030-01-07T09:00:00Z",
+        "2030-01-07T10:00:00Z",
+      ))
+      .await
+      .unwrap();
+    assert_eq!(entry.status, WaitlistStatus::Promoted as i32);
+
+    let rsvp = pool.get(entry.reservation_id).await.unwrap();
+    assert_eq!(rsvp.user_id, "bobId");
+    assert_eq!(rsvp.timezone, "UTC");
+  }
+}
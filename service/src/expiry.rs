@@ -0,0 +1,107 @@
+use std::{num::NonZeroU64, time::Duration};
+
+use abi::HoldConfig;
+use chrono::{DateTime, Utc};
+use reservation::{Idempotency, ReservationManage, Rsvp};
+use tokio::task::JoinHandle;
+
+/// expire pending reservations whose hold ended before `now`, batch by batch
+/// until none is left, returns how many were expired
+pub async fn sweep_expired_holds(
+  manager: &ReservationManage,
+  now: DateTime<Utc>,
+  batch_size: NonZeroU64,
+) -> Result<usize, abi::Error> {
+  let batch_size = batch_size.get() as i64;
+  let mut total = 0;
+  loop {
+    let expired = manager.expire(now, batch_size).await?.len();
+    total += expired;
+    if (expired as i64) < batch_size {
+      return Ok(total);
+    }
+  }
+}
+
+/// periodically sweep expired pending reservations and idempotency keys in
+/// the background, failed sweeps are logged and retried at the next tick
+pub fn spawn_expiry_worker(
+  manager: ReservationManage,
+  config: &HoldConfig,
+) -> Result<JoinHandle<()>, abi::Error> {
+  let batch_size = config.get_batch_size()?;
+  let mut interval = tokio::time::interval(Duration::from_secs(
+    config.get_sweep_interval()?.get(),
+  ));
+
+  Ok(tokio::spawn(async move {
+    loop {
+      interval.tick().await;
+      if let Err(e) =
+        sweep_expired_holds(&manager, Utc::now(), batch_size).await
+      {
+        tracing::error!("failed to expire pending reservations: {}", e);
+      }
+      if let Err(e) = manager.purge_idempotency_keys(Utc::now()).await {
+        tracing::error!("failed to purge idempotency keys: {}", e);
+      }
+    }
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use abi::{
+    convert_local_time_to_utc, convert_to_timestamp, Reservation, Resource,
+  };
+  use chrono::Duration;
+  use reservation::ResourceCatalogue;
+  use sqlx_db_tester::TestDb;
+
+  #[tokio::test]
+  async fn sweep_expired_holds_should_expire_all_batches() {
+    let config = abi::Config::from_file("../service/fixtures/config.yml")
+      .expect("failed to read config file");
+    let tdb = TestDb::new(
+      &config.db.host,
+      config.db.port,
+      &config.db.username,
+      &config.db.password,
+      "../migrations",
+    );
+    let manager = ReservationManage::new(tdb.get_pool().await);
+    let now = convert_local_time_to_utc("2024-01-20 12:00:00");
+
+    for i in 0..5 {
+      let resource = Resource {
+        id: format!("room-{}", i),
+        name: format!("room-{}", i),
+        capacity: 1,
+        ..Default::default()
+      };
+      manager.create_resource(resource).await.unwrap();
+
+      let mut rsvp = Reservation::new_pending(
+        "xiaozhangId",
+        format!("room-{}", i),
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      );
+      rsvp.hold_until = Some(convert_to_timestamp(now - Duration::minutes(i)));
+      manager.reserve(rsvp).await.unwrap();
+    }
+
+    let batch_size = NonZeroU64::new(2).unwrap();
+    let expired = sweep_expired_holds(&manager, now, batch_size)
+      .await
+      .unwrap();
+    assert_eq!(expired, 5);
+
+    let expired = sweep_expired_holds(&manager, now, batch_size)
+      .await
+      .unwrap();
+    assert_eq!(expired, 0);
+  }
+}
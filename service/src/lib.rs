@@ -1,22 +1,37 @@
+mod expiry;
 mod service;
 use std::pin::Pin;
 
-use abi::{Config, Reservation};
+use abi::{Config, ListenResponse, Reservation};
+use chrono::Duration;
 use futures::Stream;
 use reservation::ReservationManage;
 use tonic::Status;
 
+pub use expiry::{spawn_expiry_worker, sweep_expired_holds};
+
 pub struct RsvpServie {
   pub manager: reservation::ReservationManage,
 }
 impl RsvpServie {
   pub async fn from_config(config: &Config) -> Result<Self, abi::Error> {
     let pool = ReservationManage::from_config(&config.db).await?;
-    Ok(Self {
-      manager: ReservationManage::new(pool),
-    })
+    let mut manager = ReservationManage::new(pool);
+    if config.hold.default_ttl > 0 {
+      manager = manager
+        .with_hold_ttl(Duration::seconds(config.hold.default_ttl as i64));
+    }
+    if !config.quota.is_empty() {
+      manager = manager.with_quota(config.quota.clone());
+    }
+    manager = manager
+      .with_idempotency_ttl(Duration::seconds(config.idempotency.ttl as i64));
+    Ok(Self { manager })
   }
 }
 
 type ReservationStream =
   Pin<Box<dyn Stream<Item = Result<Reservation, Status>> + Send>>;
+
+type ListenStream =
+  Pin<Box<dyn Stream<Item = Result<ListenResponse, Status>> + Send>>;
@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use abi::{reservation_service_server::ReservationServiceServer, Config};
-use reservation_service::RsvpServie;
+use reservation_service::{spawn_expiry_worker, RsvpServie};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -28,6 +28,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   let rsvp_service = RsvpServie::from_config(&config).await?;
 
+  spawn_expiry_worker(rsvp_service.manager.clone(), &config.hold)?;
+
   println!("Server listening on {}", addr);
 
   tonic::transport::Server::builder()
@@ -1,13 +1,30 @@
 use abi::{
-  reservation_service_server::ReservationService, CancelRequest,
-  CancelResponse, ConfirmRequest, ConfirmResponse, FilterRequest,
-  FilterResponse, GetRequest, GetResponse, QueryRequest, ReserveRequest,
-  ReserveResponse, UpdateRequest, UpdateResponse,
+  convert_to_utc_time, reservation_service_server::ReservationService,
+  CancelRequest, CancelResponse, CancelSeriesRequest, CancelSeriesResponse,
+  ConfirmRequest, ConfirmResponse, CreateResourceRequest,
+  CreateResourceResponse, DeleteResourceRequest, DeleteResourceResponse, Error,
+  ErrorDetails, ExportIcsRequest, ExportIcsResponse, FilterRequest,
+  FilterResponse, FindAvailabilityRequest, FindAvailabilityResponse,
+  GetQuotaRequest, GetQuotaResponse, GetRequest, GetResourceRequest,
+  GetResourceResponse, GetResponse, GetSeriesRequest, GetSeriesResponse,
+  ImportIcsRequest, ImportIcsResponse, LeaveWaitlistRequest,
+  LeaveWaitlistResponse, ListResourcesRequest, ListResourcesResponse,
+  ListenRequest, QueryRequest, ReservationStatus, ReserveBatchRequest,
+  ReserveBatchResponse, ReserveRequest, ReserveResponse, ReserveSeriesRequest,
+  ReserveSeriesResponse, RestoreRequest, RestoreResponse, TransitionRequest,
+  TransitionResponse, UpdateRequest, UpdateResourceRequest,
+  UpdateResourceResponse, UpdateResponse, UpdateSeriesRequest,
+  UpdateSeriesResponse,
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use reservation::{
+  Alternatives, CalendarExchange, Idempotency, Quotas, RecurringRsvp,
+  ResourceCatalogue, Rsvp, RsvpTx, Waitlist,
 };
-use reservation::Rsvp;
 use tonic::{Request, Response, Status};
 
-use crate::{ReservationStream, RsvpServie};
+use crate::{ListenStream, ReservationStream, RsvpServie};
 
 #[tonic::async_trait]
 impl ReservationService for RsvpServie {
@@ -15,60 +32,517 @@ impl ReservationService for RsvpServie {
     &self,
     request: Request<ReserveRequest>,
   ) -> Result<Response<ReserveResponse>, Status> {
+    let mut request = request.into_inner();
+    let key = std::mem::take(&mut request.idempotency_key);
+    let user_id = request
+      .reservation
+      .as_ref()
+      .map(|rsvp| rsvp.user_id.clone())
+      .unwrap_or_default();
+    let response = self
+      .manager
+      .idempotent(&user_id, &key, "reserve", request, |tx, request| {
+        self.reserve_or_wait(tx, request)
+      })
+      .await?;
+
+    Ok(Response::new(response))
+  }
+  /// make several reservations in one transaction
+  async fn reserve_batch(
+    &self,
+    request: Request<ReserveBatchRequest>,
+  ) -> Result<Response<ReserveBatchResponse>, Status> {
     let request = request.into_inner();
-    if request.reservation.is_none() {
-      return Err(Status::invalid_argument("reservation is required"));
+    let reservations = self.manager.reserve_batch(request.reservations).await?;
+
+    Ok(Response::new(ReserveBatchResponse { reservations }))
+  }
+  /// reserve every occurrence of a recurring reservation
+  async fn reserve_series(
+    &self,
+    request: Request<ReserveSeriesRequest>,
+  ) -> Result<Response<ReserveSeriesResponse>, Status> {
+    let request = request.into_inner();
+    if request.series.is_none() {
+      return Err(Status::invalid_argument("series is required"));
     }
-    let reservation =
-      self.manager.reserve(request.reservation.unwrap()).await?;
+    let (series_id, reservations) =
+      self.manager.reserve_series(request.series.unwrap()).await?;
 
-    Ok(Response::new(ReserveResponse {
-      reservation: Some(reservation),
+    Ok(Response::new(ReserveSeriesResponse {
+      series_id,
+      reservations,
     }))
   }
+  /// update an occurrence and the following ones
+  async fn update_series(
+    &self,
+    request: Request<UpdateSeriesRequest>,
+  ) -> Result<Response<UpdateSeriesResponse>, Status> {
+    let request = request.into_inner();
+    let (Some(from), Some(update_mask)) = (request.from, request.update_mask)
+    else {
+      return Err(Status::invalid_argument(
+        "from and update_mask are required",
+      ));
+    };
+    let reservations = self
+      .manager
+      .update_series(
+        request.series_id,
+        convert_to_utc_time(from),
+        request.reservation.unwrap_or_default(),
+        update_mask,
+      )
+      .await?;
+
+    Ok(Response::new(UpdateSeriesResponse { reservations }))
+  }
+  /// cancel an occurrence and the following ones
+  async fn cancel_series(
+    &self,
+    request: Request<CancelSeriesRequest>,
+  ) -> Result<Response<CancelSeriesResponse>, Status> {
+    let request = request.into_inner();
+    let Some(from) = request.from else {
+      return Err(Status::invalid_argument("from is required"));
+    };
+    let reservations = self
+      .manager
+      .cancel_series(
+        request.series_id,
+        convert_to_utc_time(from),
+        &request.operator,
+        &request.reason,
+      )
+      .await?;
+
+    Ok(Response::new(CancelSeriesResponse { reservations }))
+  }
+  /// get every occurrence of a recurring reservation
+  async fn get_series(
+    &self,
+    request: Request<GetSeriesRequest>,
+  ) -> Result<Response<GetSeriesResponse>, Status> {
+    let request = request.into_inner();
+    let reservations = self.manager.get_series(request.series_id).await?;
+
+    Ok(Response::new(GetSeriesResponse { reservations }))
+  }
   /// update status to CONFIRMED
   async fn confirm(
     &self,
-    _request: Request<ConfirmRequest>,
+    request: Request<ConfirmRequest>,
   ) -> Result<Response<ConfirmResponse>, Status> {
-    todo!()
+    let mut request = request.into_inner();
+    let key = std::mem::take(&mut request.idempotency_key);
+    let user_id = self.key_owner(request.id, &key).await?;
+    let response = self
+      .manager
+      .idempotent(
+        &user_id,
+        &key,
+        "confirm",
+        request,
+        |mut tx, request| async move {
+          let reservation = self
+            .manager
+            .transition_in(
+              &mut tx,
+              request.id,
+              ReservationStatus::Confirmed,
+              "",
+              request.expected_version,
+            )
+            .await?;
+          let response = ConfirmResponse {
+            reservation: Some(reservation),
+          };
+          Ok::<_, Error>((tx, response))
+        },
+      )
+      .await?;
+
+    Ok(Response::new(response))
+  }
+  /// move reservation to another status
+  async fn transition(
+    &self,
+    request: Request<TransitionRequest>,
+  ) -> Result<Response<TransitionResponse>, Status> {
+    let request = request.into_inner();
+    let status = ReservationStatus::try_from(request.status)
+      .map_err(|_| Status::invalid_argument("status is invalid"))?;
+    let reservation = self
+      .manager
+      .transition(
+        request.id,
+        status,
+        &request.operator,
+        request.expected_version,
+      )
+      .await?;
+
+    Ok(Response::new(TransitionResponse {
+      reservation: Some(reservation),
+    }))
   }
   /// update only note
   async fn update(
     &self,
-    _request: Request<UpdateRequest>,
+    request: Request<UpdateRequest>,
   ) -> Result<Response<UpdateResponse>, Status> {
-    todo!()
+    let mut request = request.into_inner();
+    let key = std::mem::take(&mut request.idempotency_key);
+    let user_id = self.key_owner(request.id, &key).await?;
+    let response = self
+      .manager
+      .idempotent(
+        &user_id,
+        &key,
+        "update",
+        request,
+        |mut tx, request| async move {
+          let id = request.id;
+          let version = request.expected_version;
+          let reservation = match request.update_mask {
+            Some(mask) => {
+              let update = request.reservation.unwrap_or_default();
+              self
+                .manager
+                .update_in(&mut tx, id, update, mask, version)
+                .await?
+            }
+            None => {
+              self
+                .manager
+                .update_note_in(&mut tx, id, request.note, version)
+                .await?
+            }
+          };
+          let response = UpdateResponse {
+            reservation: Some(reservation),
+          };
+          Ok::<_, Error>((tx, response))
+        },
+      )
+      .await?;
+
+    Ok(Response::new(response))
   }
   /// cancel reservation
   async fn cancel(
     &self,
-    _request: Request<CancelRequest>,
+    request: Request<CancelRequest>,
   ) -> Result<Response<CancelResponse>, Status> {
-    todo!()
+    let mut request = request.into_inner();
+    let key = std::mem::take(&mut request.idempotency_key);
+    let user_id = self.key_owner(request.id, &key).await?;
+    let response = self
+      .manager
+      .idempotent(
+        &user_id,
+        &key,
+        "cancel",
+        request,
+        |mut tx, request| async move {
+          let reservation = self
+            .manager
+            .cancel_in(
+              &mut tx,
+              request.id,
+              &request.operator,
+              &request.reason,
+              request.expected_version,
+            )
+            .await?;
+          let response = CancelResponse {
+            reservation: Some(reservation),
+          };
+          Ok::<_, Error>((tx, response))
+        },
+      )
+      .await?;
+
+    Ok(Response::new(response))
+  }
+  /// restore a cancelled reservation if its time slot is still free
+  async fn restore(
+    &self,
+    request: Request<RestoreRequest>,
+  ) -> Result<Response<RestoreResponse>, Status> {
+    let request = request.into_inner();
+    let reservation = self
+      .manager
+      .restore(request.id, &request.operator, request.expected_version)
+      .await?;
+
+    Ok(Response::new(RestoreResponse {
+      reservation: Some(reservation),
+    }))
   }
   /// get reservation by id
   async fn get(
     &self,
-    _request: Request<GetRequest>,
+    request: Request<GetRequest>,
   ) -> Result<Response<GetResponse>, Status> {
-    todo!()
+    let request = request.into_inner();
+    let reservation = self.manager.get(request.id).await?;
+
+    Ok(Response::new(GetResponse {
+      reservation: Some(reservation),
+    }))
   }
   /// Server streaming response type for the query method.
   type queryStream = ReservationStream;
   /// query reservations with pagination
   async fn query(
     &self,
-    _request: Request<QueryRequest>,
+    request: Request<QueryRequest>,
   ) -> Result<Response<Self::queryStream>, Status> {
-    todo!()
+    let request = request.into_inner();
+    if request.query.is_none() {
+      return Err(Status::invalid_argument("query is required"));
+    }
+    let stream = self
+      .manager
+      .query(request.query.unwrap())
+      .await?
+      .map_err(Status::from);
+
+    Ok(Response::new(Box::pin(stream)))
   }
   /// filter reservations order by reservation id
   async fn filter(
     &self,
-    _request: Request<FilterRequest>,
+    request: Request<FilterRequest>,
   ) -> Result<Response<FilterResponse>, Status> {
-    todo!()
+    let request = request.into_inner();
+    if request.filter.is_none() {
+      return Err(Status::invalid_argument("filter is required"));
+    }
+    let (pager, reservations) =
+      self.manager.filter(request.filter.unwrap()).await?;
+
+    Ok(Response::new(FilterResponse {
+      reservations,
+      pager: Some(pager),
+    }))
+  }
+  /// find free slots of a resource
+  async fn find_availability(
+    &self,
+    request: Request<FindAvailabilityRequest>,
+  ) -> Result<Response<FindAvailabilityResponse>, Status> {
+    let request = request.into_inner();
+    if request.query.is_none() {
+      return Err(Status::invalid_argument("query is required"));
+    }
+    let windows = self
+      .manager
+      .find_availability(request.query.unwrap())
+      .await?;
+
+    Ok(Response::new(FindAvailabilityResponse { windows }))
+  }
+  /// export reservations as an iCalendar file
+  async fn export_ics(
+    &self,
+    request: Request<ExportIcsRequest>,
+  ) -> Result<Response<ExportIcsResponse>, Status> {
+    let request = request.into_inner();
+    if request.query.is_none() {
+      return Err(Status::invalid_argument("query is required"));
+    }
+    let ics = self.manager.export_ics(request.query.unwrap()).await?;
+
+    Ok(Response::new(ExportIcsResponse { ics }))
+  }
+  /// reserve every event of an iCalendar file
+  async fn import_ics(
+    &self,
+    request: Request<ImportIcsRequest>,
+  ) -> Result<Response<ImportIcsResponse>, Status> {
+    let request = request.into_inner();
+    let reservations = self
+      .manager
+      .import_ics(&request.ics, &request.user_id)
+      .await?;
+
+    Ok(Response::new(ImportIcsResponse { reservations }))
+  }
+  /// what is left of the booking quotas of a user
+  async fn get_quota(
+    &self,
+    request: Request<GetQuotaRequest>,
+  ) -> Result<Response<GetQuotaResponse>, Status> {
+    let request = request.into_inner();
+    let at = request.at.map(convert_to_utc_time).unwrap_or_else(Utc::now);
+    let quotas = self.manager.get_quota(&request.user_id, at).await?;
+
+    Ok(Response::new(GetQuotaResponse { quotas }))
+  }
+  /// leave the waitlist joined by reserve
+  async fn leave_waitlist(
+    &self,
+    request: Request<LeaveWaitlistRequest>,
+  ) -> Result<Response<LeaveWaitlistResponse>, Status> {
+    let request = request.into_inner();
+    let entry = self.manager.leave_waitlist(request.id).await?;
+
+    Ok(Response::new(LeaveWaitlistResponse { entry: Some(entry) }))
+  }
+  /// Server streaming response type for the listen method.
+  type listenStream = ListenStream;
+  /// listen to reservation changes
+  async fn listen(
+    &self,
+    request: Request<ListenRequest>,
+  ) -> Result<Response<Self::listenStream>, Status> {
+    let request = request.into_inner();
+    let stream = self
+      .manager
+      .listen(request.last_change_id)
+      .await?
+      .map_err(Status::from);
+
+    Ok(Response::new(Box::pin(stream)))
+  }
+  /// add a resource to the catalogue
+  async fn create_resource(
+    &self,
+    request: Request<CreateResourceRequest>,
+  ) -> Result<Response<CreateResourceResponse>, Status> {
+    let request = request.into_inner();
+    if request.resource.is_none() {
+      return Err(Status::invalid_argument("resource is required"));
+    }
+    let resource = self
+      .manager
+      .create_resource(request.resource.unwrap())
+      .await?;
+
+    Ok(Response::new(CreateResourceResponse {
+      resource: Some(resource),
+    }))
+  }
+  /// get resource by id
+  async fn get_resource(
+    &self,
+    request: Request<GetResourceRequest>,
+  ) -> Result<Response<GetResourceResponse>, Status> {
+    let request = request.into_inner();
+    let resource = self.manager.get_resource(&request.id).await?;
+
+    Ok(Response::new(GetResourceResponse {
+      resource: Some(resource),
+    }))
+  }
+  /// replace a resource of the catalogue
+  async fn update_resource(
+    &self,
+    request: Request<UpdateResourceRequest>,
+  ) -> Result<Response<UpdateResourceResponse>, Status> {
+    let request = request.into_inner();
+    if request.resource.is_none() {
+      return Err(Status::invalid_argument("resource is required"));
+    }
+    let resource = self
+      .manager
+      .update_resource(request.resource.unwrap())
+      .await?;
+
+    Ok(Response::new(UpdateResourceResponse {
+      resource: Some(resource),
+    }))
+  }
+  /// delete a resource without reservations
+  async fn delete_resource(
+    &self,
+    request: Request<DeleteResourceRequest>,
+  ) -> Result<Response<DeleteResourceResponse>, Status> {
+    let request = request.into_inner();
+    let resource = self.manager.delete_resource(&request.id).await?;
+
+    Ok(Response::new(DeleteResourceResponse {
+      resource: Some(resource),
+    }))
+  }
+  /// list resources order by id
+  async fn list_resources(
+    &self,
+    request: Request<ListResourcesRequest>,
+  ) -> Result<Response<ListResourcesResponse>, Status> {
+    let request = request.into_inner();
+    let resources = self
+      .manager
+      .list_resources(request.include_inactive)
+      .await?;
+
+    Ok(Response::new(ListResourcesResponse { resources }))
+  }
+}
+
+impl RsvpServie {
+  /// user the idempotency `key` of a request on reservation `id` is scoped
+  /// to, the owner of the reservation. requests without a key need none
+  async fn key_owner(&self, id: i64, key: &str) -> Result<String, Error> {
+    if key.is_empty() {
+      return Ok(String::new());
+    }
+    Ok(self.manager.get(id).await?.user_id)
+  }
+
+  /// reserve in `tx`, or if the window is taken join its waitlist or suggest
+  /// alternatives as requested
+  async fn reserve_or_wait(
+    &self,
+    mut tx: RsvpTx,
+    request: ReserveRequest,
+  ) -> Result<(RsvpTx, ReserveResponse), Status> {
+    if request.reservation.is_none() {
+      return Err(Status::invalid_argument("reservation is required"));
+    }
+    let reservation = request.reservation.unwrap();
+    let reserved = self.manager.reserve_in(&mut tx, reservation.clone()).await;
+    let response = match reserved {
+      Ok(reservation) => ReserveResponse {
+        reservation: Some(reservation),
+        waitlist_entry: None,
+      },
+      // the window is taken, wait for it to free up
+      Err(Error::ConflictReservation(_) | Error::CapacityExceeded(_))
+        if request.join_waitlist =>
+      {
+        let entry = self.manager.join_waitlist_in(&mut tx, reservation).await?;
+        ReserveResponse {
+          reservation: None,
+          waitlist_entry: Some(entry),
+        }
+      }
+      // the window is taken, tell what could be reserved instead
+      Err(e @ (Error::ConflictReservation(_) | Error::CapacityExceeded(_)))
+        if request.suggest_alternatives > 0 =>
+      {
+        let alternatives = self
+          .manager
+          .suggest_alternatives(
+            &reservation,
+            request.suggest_alternatives as usize,
+            request.suggest_similar_resources,
+          )
+          .await?;
+        let status = Status::from(e);
+        let mut details =
+          ErrorDetails::from_status(&status).unwrap_or_default();
+        details.alternatives = alternatives;
+        return Err(details.attach_to(status));
+      }
+      Err(e) => return Err(Status::from(e)),
+    };
+
+    Ok((tx, response))
   }
 }
 
@@ -77,7 +551,13 @@ mod tests {
   use std::ops::Deref;
 
   use super::*;
-  use abi::{convert_local_time_to_utc, Config, Reservation};
+  use abi::{
+    convert_local_time_to_utc, convert_to_timestamp, AvailabilityQuery, Config,
+    QuotaConfig, QuotaKind, Reservation, ReservationFilterBuilder,
+    ReservationQueryBuilder, ReservationSeries, ReservationUpdateType,
+    Resource,
+  };
+  use prost_types::{FieldMask, Timestamp};
   use sqlx_db_tester::TestDb;
 
   struct TestConfig {
@@ -111,10 +591,32 @@ mod tests {
       }
     }
   }
+  /// service with the resources the tests reserve against
+  async fn make_service(config: &TestConfig) -> RsvpServie {
+    let service = RsvpServie::from_config(config).await.unwrap();
+    for id in [
+      "testResourceId",
+      "projector-1",
+      "projector-2",
+      "another-room",
+    ] {
+      let request = Request::new(CreateResourceRequest {
+        resource: Some(Resource {
+          id: id.to_string(),
+          name: id.to_string(),
+          capacity: 1,
+          ..Default::default()
+        }),
+      });
+      service.create_resource(request).await.unwrap();
+    }
+    service
+  }
+
   #[tokio::test]
   async fn rpc_reserve_should_work() {
     let config = TestConfig::new();
-    let service = RsvpServie::from_config(&config).await.unwrap();
+    let service = make_service(&config).await;
 
     let request = Request::new(ReserveRequest {
       reservation: Some(Reservation::new_pending(
@@ -124,6 +626,8 @@ mod tests {
         convert_local_time_to_utc("2024-01-22 12:00:00"),
         "test_reserve_should_work_for_valid_window",
       )),
+      join_waitlist: false,
+      ..Default::default()
     });
 
     let response = service.reserve(request).await.unwrap().into_inner();
@@ -144,4 +648,608 @@ mod tests {
       "test_reserve_should_work_for_valid_window"
     );
   }
+
+  async fn make_reservation(service: &RsvpServie) -> Reservation {
+    let request = Request::new(ReserveRequest {
+      reservation: Some(Reservation::new_pending(
+        "xiaozhangId",
+        "testResourceId",
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "test_reserve_should_work_for_valid_window",
+      )),
+      join_waitlist: false,
+      ..Default::default()
+    });
+
+    service
+      .reserve(request)
+      .await
+      .unwrap()
+      .into_inner()
+      .reservation
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn rpc_reserve_batch_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let mut equipment = rsvp.clone();
+    equipment.id = 0;
+    equipment.resource_id = "projector-1".to_string();
+    let request = Request::new(ReserveBatchRequest {
+      reservations: vec![equipment.clone()],
+    });
+    let response = service.reserve_batch(request).await.unwrap();
+    assert_eq!(response.into_inner().reservations.len(), 1);
+
+    // the room is taken, nothing of this batch should be reserved
+    equipment.resource_id = "projector-2".to_string();
+    let request = Request::new(ReserveBatchRequest {
+      reservations: vec![equipment, rsvp],
+    });
+    let status = service.reserve_batch(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+  }
+
+  #[tokio::test]
+  async fn rpc_series_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+
+    let request = Request::new(ReserveSeriesRequest {
+      series: Some(ReservationSeries {
+        reservation: Some(Reservation::new_pending(
+          "xiaozhangId",
+          "testResourceId",
+          convert_local_time_to_utc("2024-01-01 09:00:00"),
+          convert_local_time_to_utc("2024-01-01 10:00:00"),
+          "weekly review",
+        )),
+        rrule: "FREQ=WEEKLY;COUNT=3".to_string(),
+        exdates: vec![],
+      }),
+    });
+    let response = service.reserve_series(request).await.unwrap().into_inner();
+    assert_eq!(response.reservations.len(), 3);
+    let series_id = response.series_id;
+
+    let request = Request::new(CancelSeriesRequest {
+      series_id,
+      from: response.reservations[1].start,
+      operator: "xiaozhangId".to_string(),
+      ..Default::default()
+    });
+    let response = service.cancel_series(request).await.unwrap().into_inner();
+    assert_eq!(response.reservations.len(), 2);
+
+    let request = Request::new(GetSeriesRequest { series_id });
+    let response = service.get_series(request).await.unwrap().into_inner();
+    let statuses: Vec<_> =
+      response.reservations.iter().map(|r| r.status).collect();
+    assert_eq!(
+      statuses,
+      vec![
+        ReservationStatus::Pending as i32,
+        ReservationStatus::Cancelled as i32,
+        ReservationStatus::Cancelled as i32,
+      ]
+    );
+
+    let request = Request::new(ReserveSeriesRequest {
+      series: Some(ReservationSeries {
+        rrule: "FREQ=DAILY".to_string(),
+        ..Default::default()
+      }),
+    });
+    let status = service.reserve_series(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+  }
+
+  #[tokio::test]
+  async fn rpc_confirm_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(ConfirmRequest {
+      id: rsvp.id,
+      expected_version: rsvp.version + 1,
+      ..Default::default()
+    });
+    let status = service.confirm(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Aborted);
+
+    let request = Request::new(ConfirmRequest {
+      id: rsvp.id,
+      expected_version: rsvp.version,
+      ..Default::default()
+    });
+    let response = service.confirm(request).await.unwrap().into_inner();
+
+    let reservation_res = response.reservation.unwrap();
+    assert_eq!(reservation_res.id, rsvp.id);
+    assert_eq!(reservation_res.status, ReservationStatus::Confirmed as i32);
+  }
+
+  #[tokio::test]
+  async fn rpc_confirm_invalid_id_should_reject() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+
+    let request = Request::new(ConfirmRequest {
+      id: 0,
+      ..Default::default()
+    });
+    let status = service.confirm(request).await.unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+  }
+
+  #[tokio::test]
+  async fn rpc_retry_with_idempotency_key_should_replay() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let reserve = || {
+      Request::new(ReserveRequest {
+        reservation: Some(Reservation::new_pending(
+          "xiaozhangId",
+          "testResourceId",
+          convert_local_time_to_utc("2024-01-21 19:00:00"),
+          convert_local_time_to_utc("2024-01-22 12:00:00"),
+          "",
+        )),
+        idempotency_key: "reserve-1".to_string(),
+        ..Default::default()
+      })
+    };
+    let first = service.reserve(reserve()).await.unwrap().into_inner();
+    let retry = service.reserve(reserve()).await.unwrap().into_inner();
+    assert_eq!(retry, first);
+
+    let id = first.reservation.unwrap().id;
+    let confirm = || {
+      Request::new(ConfirmRequest {
+        id,
+        idempotency_key: "confirm-1".to_string(),
+        ..Default::default()
+      })
+    };
+    let first = service.confirm(confirm()).await.unwrap().into_inner();
+    let retry = service.confirm(confirm()).await.unwrap().into_inner();
+    assert_eq!(retry, first);
+
+    let request = Request::new(CancelRequest {
+      id,
+      idempotency_key: "confirm-1".to_string(),
+      ..Default::default()
+    });
+    let status = service.cancel(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    let details = ErrorDetails::from_status(&status).unwrap();
+    assert_eq!(details.info.unwrap().reason, "IDEMPOTENCY_KEY_MISMATCH");
+  }
+
+  #[tokio::test]
+  async fn rpc_transition_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(TransitionRequest {
+      id: rsvp.id,
+      status: ReservationStatus::Cancelled as i32,
+      operator: "admin".to_string(),
+      expected_version: rsvp.version,
+    });
+    let response = service.transition(request).await.unwrap().into_inner();
+    let reservation_res = response.reservation.unwrap();
+    assert_eq!(reservation_res.status, ReservationStatus::Cancelled as i32);
+    assert_eq!(reservation_res.version, rsvp.version + 1);
+
+    let request = Request::new(TransitionRequest {
+      id: rsvp.id,
+      status: ReservationStatus::Confirmed as i32,
+      operator: "admin".to_string(),
+      ..Default::default()
+    });
+    let status = service.transition(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    let request = Request::new(UpdateRequest {
+      id: rsvp.id,
+      note: "stale".to_string(),
+      expected_version: rsvp.version,
+      ..Default::default()
+    });
+    let status = service.update(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Aborted);
+  }
+
+  #[tokio::test]
+  async fn rpc_update_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(UpdateRequest {
+      id: rsvp.id,
+      note: "rpc_update_should_work".to_string(),
+      ..Default::default()
+    });
+    let response = service.update(request).await.unwrap().into_inner();
+
+    let reservation_res = response.reservation.unwrap();
+    assert_eq!(reservation_res.id, rsvp.id);
+    assert_eq!(reservation_res.note, "rpc_update_should_work");
+  }
+
+  #[tokio::test]
+  async fn rpc_update_with_mask_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let mut update = rsvp.clone();
+    update.resource_id = "another-room".to_string();
+    update.note = "not in the mask".to_string();
+    let request = Request::new(UpdateRequest {
+      id: rsvp.id,
+      reservation: Some(update),
+      update_mask: Some(FieldMask {
+        paths: vec!["resource_id".to_string()],
+      }),
+      ..Default::default()
+    });
+    let response = service.update(request).await.unwrap().into_inner();
+
+    let reservation_res = response.reservation.unwrap();
+    assert_eq!(reservation_res.resource_id, "another-room");
+    assert_eq!(reservation_res.note, rsvp.note);
+  }
+
+  #[tokio::test]
+  async fn rpc_cancel_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(CancelRequest {
+      id: rsvp.id,
+      operator: "xiaozhangId".to_string(),
+      reason: "plans changed".to_string(),
+      ..Default::default()
+    });
+    let response = service.cancel(request).await.unwrap().into_inner();
+    let cancelled = response.reservation.unwrap();
+    assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+    assert_eq!(cancelled.reason, "plans changed");
+
+    let request = Request::new(GetRequest { id: rsvp.id });
+    let response = service.get(request).await.unwrap().into_inner();
+    assert_eq!(response.reservation.unwrap(), cancelled);
+  }
+
+  #[tokio::test]
+  async fn rpc_restore_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(CancelRequest {
+      id: rsvp.id,
+      ..Default::default()
+    });
+    service.cancel(request).await.unwrap();
+
+    let request = Request::new(RestoreRequest {
+      id: rsvp.id,
+      operator: "xiaozhangId".to_string(),
+      ..Default::default()
+    });
+    let response = service.restore(request).await.unwrap().into_inner();
+    let restored = response.reservation.unwrap();
+    assert_eq!(restored.version, rsvp.version + 2);
+    assert_eq!((restored.status, restored.note), (rsvp.status, rsvp.note));
+  }
+
+  #[tokio::test]
+  async fn rpc_get_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let request = Request::new(GetRequest { id: rsvp.id });
+    let response = service.get(request).await.unwrap().into_inner();
+
+    assert_eq!(response.reservation.unwrap(), rsvp);
+  }
+
+  #[tokio::test]
+  async fn rpc_query_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let query = ReservationQueryBuilder::default()
+      .user_id("xiaozhangId")
+      .start("2024-01-20T19:00:00Z".parse::<Timestamp>().unwrap())
+      .end("2024-01-23T12:00:00Z".parse::<Timestamp>().unwrap())
+      .status(ReservationStatus::Pending as i32)
+      .build()
+      .unwrap();
+    let request = Request::new(QueryRequest { query: Some(query) });
+    let stream = service.query(request).await.unwrap().into_inner();
+
+    let rsvps: Vec<_> = stream.try_collect().await.unwrap();
+    assert_eq!(rsvps, vec![rsvp]);
+  }
+
+  #[tokio::test]
+  async fn rpc_query_without_query_should_reject() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+
+    let request = Request::new(QueryRequest { query: None });
+    let status = service.query(request).await.err().unwrap();
+
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+  }
+
+  #[tokio::test]
+  async fn rpc_filter_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let filter = ReservationFilterBuilder::default()
+      .user_id("xiaozhangId")
+      .status(ReservationStatus::Pending as i32)
+      .build()
+      .unwrap();
+    let request = Request::new(FilterRequest {
+      filter: Some(filter),
+    });
+    let response = service.filter(request).await.unwrap().into_inner();
+
+    let pager = response.pager.unwrap();
+    assert_eq!(pager.prev, -1);
+    assert_eq!(pager.next, -1);
+    assert_eq!(response.reservations, vec![rsvp]);
+  }
+
+  #[tokio::test]
+  async fn rpc_find_availability_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let start = convert_local_time_to_utc("2024-01-21 12:00:00");
+    let end = convert_local_time_to_utc("2024-01-22 18:00:00");
+    let request = Request::new(FindAvailabilityRequest {
+      query: Some(AvailabilityQuery {
+        resource_id: rsvp.resource_id.clone(),
+        start: Some(convert_to_timestamp(start)),
+        end: Some(convert_to_timestamp(end)),
+        ..Default::default()
+      }),
+    });
+    let response = service.find_availability(request).await.unwrap();
+
+    let windows = response.into_inner().windows;
+    assert_eq!(windows.len(), 2);
+    assert_eq!(windows[0].start, Some(convert_to_timestamp(start)));
+    assert_eq!(windows[0].end, rsvp.start);
+    assert_eq!(windows[1].start, rsvp.end);
+    assert_eq!(windows[1].end, Some(convert_to_timestamp(end)));
+  }
+
+  #[tokio::test]
+  async fn rpc_resource_crud_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+
+    let request = Request::new(GetResourceRequest {
+      id: "projector-1".to_string(),
+    });
+    let mut resource = service
+      .get_resource(request)
+      .await
+      .unwrap()
+      .into_inner()
+      .resource
+      .unwrap();
+    assert!(resource.active);
+
+    resource.active = false;
+    let request = Request::new(UpdateResourceRequest {
+      resource: Some(resource.clone()),
+    });
+    let response = service.update_resource(request).await.unwrap();
+    assert_eq!(response.into_inner().resource, Some(resource));
+
+    let request = Request::new(ListResourcesRequest {
+      include_inactive: false,
+    });
+    let response = service.list_resources(request).await.unwrap();
+    assert_eq!(response.into_inner().resources.len(), 3);
+
+    let request = Request::new(DeleteResourceRequest {
+      id: "projector-1".to_string(),
+    });
+    service.delete_resource(request).await.unwrap();
+
+    let request = Request::new(GetResourceRequest {
+      id: "projector-1".to_string(),
+    });
+    let status = service.get_resource(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+  }
+
+  #[tokio::test]
+  async fn rpc_ics_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = make_reservation(&service).await;
+
+    let query = ReservationQueryBuilder::default()
+      .user_id("xiaozhangId")
+      .status(ReservationStatus::Pending as i32)
+      .start("2024-01-01T00:00:00Z".parse::<Timestamp>().unwrap())
+      .end("2024-12-31T00:00:00Z".parse::<Timestamp>().unwrap())
+      .build()
+      .unwrap();
+    let request = Request::new(ExportIcsRequest { query: Some(query) });
+    let ics = service.export_ics(request).await.unwrap().into_inner().ics;
+    assert!(ics.contains(&format!("UID:{}@reservation", rsvp.id)));
+
+    // move the exported event to another room and import it back
+    let ics = ics.replace(&rsvp.resource_id, "another-room");
+    let request = Request::new(ImportIcsRequest {
+      ics,
+      user_id: String::new(),
+    });
+    let response = service.import_ics(request).await.unwrap().into_inner();
+    assert_eq!(response.reservations.len(), 1);
+    assert_eq!(response.reservations[0].resource_id, "another-room");
+    assert_eq!(response.reservations[0].user_id, rsvp.user_id);
+  }
+
+  #[tokio::test]
+  async fn rpc_get_quota_should_work() {
+    let mut config = TestConfig::new();
+    config.config.quota = QuotaConfig {
+      max_active: 1,
+      ..Default::default()
+    };
+    let service = make_service(&config).await;
+    make_reservation(&service).await;
+
+    let request = Request::new(GetQuotaRequest {
+      user_id: "xiaozhangId".to_string(),
+      at: None,
+    });
+    let quotas = service
+      .get_quota(request)
+      .await
+      .unwrap()
+      .into_inner()
+      .quotas;
+    assert_eq!(quotas.len(), 1);
+    assert_eq!(quotas[0].kind, QuotaKind::ActiveReservations as i32);
+    assert_eq!((quotas[0].used, quotas[0].remaining), (1, 0));
+
+    let request = Request::new(ReserveRequest {
+      reservation: Some(Reservation::new_pending(
+        "xiaozhangId",
+        "another-room",
+        convert_local_time_to_utc("2024-01-21 19:00:00"),
+        convert_local_time_to_utc("2024-01-22 12:00:00"),
+        "",
+      )),
+      join_waitlist: false,
+      ..Default::default()
+    });
+    let status = service.reserve(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+  }
+
+  #[tokio::test]
+  async fn rpc_reserve_should_suggest_alternatives_if_taken() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "testResourceId",
+      "2030-01-07T09:00:00Z".parse().unwrap(),
+      "2030-01-07T10:00:00Z".parse().unwrap(),
+      "",
+    );
+    let request = Request::new(ReserveRequest {
+      reservation: Some(rsvp.clone()),
+      ..Default::default()
+    });
+    service.reserve(request).await.unwrap();
+
+    let request = Request::new(ReserveRequest {
+      reservation: Some(rsvp),
+      suggest_alternatives: 2,
+      ..Default::default()
+    });
+    let status = service.reserve(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    let details = ErrorDetails::from_status(&status).unwrap();
+    assert_eq!(details.conflicts.len(), 1);
+    let starts: Vec<_> = details
+      .alternatives
+      .iter()
+      .map(|window| convert_to_utc_time(window.start.unwrap()).to_rfc3339())
+      .collect();
+    assert_eq!(
+      starts,
+      vec!["2030-01-07T08:00:00+00:00", "2030-01-07T10:00:00+00:00"]
+    );
+  }
+
+  #[tokio::test]
+  async fn rpc_reserve_should_join_waitlist_if_taken() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+    let mut rsvp = Reservation::new_pending(
+      "xiaozhangId",
+      "testResourceId",
+      "2030-01-07T09:00:00Z".parse().unwrap(),
+      "2030-01-07T10:00:00Z".parse().unwrap(),
+      "",
+    );
+
+    let request = Request::new(ReserveRequest {
+      reservation: Some(rsvp.clone()),
+      join_waitlist: true,
+      ..Default::default()
+    });
+    let response = service.reserve(request).await.unwrap().into_inner();
+    assert!(response.waitlist_entry.is_none());
+    let taken = response.reservation.unwrap();
+
+    rsvp.user_id = "xiaoliId".to_string();
+    let request = Request::new(ReserveRequest {
+      reservation: Some(rsvp),
+      join_waitlist: true,
+      ..Default::default()
+    });
+    let response = service.reserve(request).await.unwrap().into_inner();
+    assert!(response.reservation.is_none());
+    let entry = response.waitlist_entry.unwrap();
+    assert!(entry.is_waiting());
+
+    let request = Request::new(CancelRequest {
+      id: taken.id,
+      operator: "xiaozhangId".to_string(),
+      ..Default::default()
+    });
+    service.cancel(request).await.unwrap();
+
+    // promoted, so it can no longer leave the waitlist
+    let request = Request::new(LeaveWaitlistRequest { id: entry.id });
+    let status = service.leave_waitlist(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+  }
+
+  #[tokio::test]
+  async fn rpc_listen_should_work() {
+    let config = TestConfig::new();
+    let service = make_service(&config).await;
+
+    let request = Request::new(ListenRequest { last_change_id: 0 });
+    let mut stream = service.listen(request).await.unwrap().into_inner();
+    let rsvp = make_reservation(&service).await;
+
+    let change = stream.try_next().await.unwrap().unwrap();
+    assert_eq!(change.op, ReservationUpdateType::Create as i32);
+    assert_eq!(change.new, Some(rsvp));
+  }
 }